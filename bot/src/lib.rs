@@ -0,0 +1,549 @@
+use common::{
+    engine::{Budget, Engine},
+    protocol::{JoinRequest, Message},
+    rules::{apply_move, Board, Variant, BOARD_SIZE},
+    AppError, Position,
+};
+use std::{
+    io::{Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    time::Duration,
+};
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+
+pub const RANDOM_ROOM: i32 = -1;
+pub const COMPUTER_ROOM: i32 = -2;
+
+// Ce s-a intamplat in partida; mesajele pe care nu le interpretam ajung in Other.
+#[derive(Debug, PartialEq)]
+pub enum Event {
+    Move((usize, usize)),
+    Over { won: bool },
+    OpponentLeft,
+    Restarted,
+    Other(Message),
+}
+
+// Un jucator fara interfata: face handshake-ul cu serverul si tine tabla la zi.
+pub struct Bot {
+    stream: TcpStream,
+    inbox: Vec<u8>,
+    pub room: i8,
+    pub role: u8,
+    pub board: Board,
+    pub mouse: Position,
+    pub turn: u8,
+    pub variant: Variant,
+    // Zidurile ramase in tura curenta, pentru variantele cu doua ziduri pe tura.
+    pub walls_left: u8,
+    pub token: Option<String>,
+    pub finished: bool,
+}
+
+impl Bot {
+    // Intra in camera si asteapta pana incepe jocul (rolul, soarecele si zidurile de start).
+    pub fn join(address: &str, request: &JoinRequest) -> Result<Self, AppError> {
+        let stream = connect(address)?;
+        let mut bot = Bot {
+            stream,
+            inbox: Vec::new(),
+            room: 0,
+            role: 1,
+            board: [[0; BOARD_SIZE]; BOARD_SIZE],
+            mouse: Position::new(0, 0),
+            turn: 1,
+            variant: Variant::default(),
+            walls_left: 1,
+            token: None,
+            finished: false,
+        };
+        bot.write(request.encode().as_bytes())?;
+
+        let response = bot.read_handshake()?;
+        let response = String::from_utf8_lossy(&response);
+        bot.room = match response.trim() {
+            "-3" => {
+                return Err(AppError::ConnectionError(String::from(
+                    "Serverul este plin",
+                )))
+            }
+            "-4" => {
+                return Err(AppError::ConnectionError(String::from(
+                    "Serverul a respins cererea",
+                )))
+            }
+            room => room.parse().map_err(|_| {
+                AppError::UnexpectedResponse(format!("camera invalida: '{}'", room))
+            })?,
+        };
+        bot.write(b"ok")?;
+
+        // Pana la inceputul jocului serverul poate trimite doar ping-uri.
+        bot.read_handshake()?;
+        bot.write(b"ok")?;
+
+        let role = bot.read_handshake()?;
+        bot.role = if String::from_utf8_lossy(&role).contains("soarecele") {
+            1
+        } else {
+            2
+        };
+        bot.write(b"ok")?;
+
+        let mouse = bot.read_handshake()?;
+        let mouse = String::from_utf8_lossy(&mouse);
+        let (row, col) = mouse
+            .trim()
+            .split_once(',')
+            .and_then(|(row, col)| Some((row.parse().ok()?, col.parse().ok()?)))
+            .filter(|&(row, col): &(usize, usize)| row < BOARD_SIZE && col < BOARD_SIZE)
+            .ok_or_else(|| {
+                AppError::UnexpectedResponse(format!("pozitie invalida a soarecelui: '{}'", mouse))
+            })?;
+        bot.mouse = Position::new(row, col);
+        bot.board[row][col] = 1;
+        bot.write(b"ok")?;
+
+        let walls = bot.read_handshake()?;
+        for wall in walls.chunks_exact(2) {
+            let (row, col) = (wall[0] as usize, wall[1] as usize);
+            if row >= BOARD_SIZE || col >= BOARD_SIZE {
+                return Err(AppError::UnexpectedResponse(format!(
+                    "zid invalid: {}, {}",
+                    row, col
+                )));
+            }
+            bot.board[row][col] = 2;
+        }
+        bot.write(b"ok")?;
+        Ok(bot)
+    }
+
+    pub fn my_turn(&self) -> bool {
+        !self.finished && self.turn == self.role
+    }
+
+    // Asteapta urmatorul eveniment; raspunde singur la ping-uri.
+    pub fn next_event(&mut self) -> Result<Event, AppError> {
+        loop {
+            while let Some((message, used)) = Message::parse(&self.inbox)? {
+                self.inbox.drain(..used);
+                if let Some(event) = self.handle(message)? {
+                    return Ok(event);
+                }
+            }
+            self.fill_inbox()?;
+        }
+    }
+
+    fn handle(&mut self, message: Message) -> Result<Option<Event>, AppError> {
+        match message {
+            Message::Ping(seq) => self.send_message(&Message::Pong(seq))?,
+            Message::Ok | Message::Pong(_) => {}
+            Message::Token(token) => self.token = Some(token),
+            Message::Rules(variant, walls_left) => {
+                self.variant = variant;
+                self.walls_left = walls_left;
+            }
+            Message::Move(row, col) => {
+                let cell = (row as usize, col as usize);
+                if cell.0 >= BOARD_SIZE || cell.1 >= BOARD_SIZE {
+                    return Err(AppError::UnexpectedResponse(format!(
+                        "mutare invalida: {}, {}",
+                        row, col
+                    )));
+                }
+                let role = self.turn;
+                self.played(role, cell);
+                return Ok(Some(Event::Move(cell)));
+            }
+            Message::Win | Message::Lose if !self.finished => {
+                // Clientul grafic confirma rezultatul, asa ca facem la fel; serverul poate
+                // inchide conexiunea inainte sa ajunga confirmarea.
+                let _ = self.send_message(&message);
+                self.finished = true;
+                return Ok(Some(Event::Over {
+                    won: message == Message::Win,
+                }));
+            }
+            Message::Win | Message::Lose => {}
+            Message::Left => {
+                self.finished = true;
+                return Ok(Some(Event::OpponentLeft));
+            }
+            Message::Snapshot(snapshot) => {
+                self.board = snapshot.board;
+                self.mouse = Position::new(snapshot.mouse.0 as usize, snapshot.mouse.1 as usize);
+                self.role = snapshot.player;
+                self.turn = snapshot.turn;
+                self.finished = false;
+                return Ok(Some(Event::Restarted));
+            }
+            message => return Ok(Some(Event::Other(message))),
+        }
+        Ok(None)
+    }
+
+    pub fn send_move(&mut self, cell: (usize, usize)) -> Result<(), AppError> {
+        if !self.my_turn() {
+            return Err(AppError::InvalidMove(String::from("nu este randul tau")));
+        }
+        if !self
+            .variant
+            .legal_moves(&self.board, &self.mouse, self.role)
+            .contains(&cell)
+        {
+            return Err(AppError::InvalidMove(format!(
+                "mutarea {}, {} nu este valida",
+                cell.0, cell.1
+            )));
+        }
+        self.played(self.role, cell);
+        // Si iesirea merge ca mutare; serverul recunoaste singur celula de pe margine.
+        self.send_message(&Message::Move(cell.0 as u8, cell.1 as u8))
+    }
+
+    // Zidurile isi pastreaza randul pana pun toate zidurile turei.
+    fn played(&mut self, role: u8, cell: (usize, usize)) {
+        apply_move(&mut self.board, &mut self.mouse, role, cell);
+        if role == 2 {
+            self.walls_left = self.walls_left.saturating_sub(1);
+            if self.walls_left > 0 {
+                return;
+            }
+        }
+        self.walls_left = self.variant.walls_per_turn();
+        self.turn = 3 - role;
+    }
+
+    pub fn send_message(&mut self, message: &Message) -> Result<(), AppError> {
+        self.write(&message.to_bytes())
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<(), AppError> {
+        self.stream
+            .write_all(bytes)
+            .and_then(|_| self.stream.flush())
+            .map_err(|e| AppError::WriteError(e.to_string()))
+    }
+
+    fn fill_inbox(&mut self) -> Result<(), AppError> {
+        let mut buffer = [0; 1024];
+        match self.stream.read(&mut buffer) {
+            Ok(0) => Err(AppError::ConnectionError(String::from(
+                "Serverul a inchis conexiunea",
+            ))),
+            Ok(n) => {
+                self.inbox.extend_from_slice(&buffer[..n]);
+                Ok(())
+            }
+            Err(e) => Err(AppError::ReadError(e.to_string())),
+        }
+    }
+
+    // Un pas din handshake-ul text; ping-urile din fata le raspundem si le sarim.
+    fn read_handshake(&mut self) -> Result<Vec<u8>, AppError> {
+        loop {
+            self.fill_inbox()?;
+            while let Ok(Some((Message::Ping(seq), used))) = Message::parse(&self.inbox) {
+                self.inbox.drain(..used);
+                self.send_message(&Message::Pong(seq))?;
+            }
+            if !self.inbox.is_empty() {
+                return Ok(std::mem::take(&mut self.inbox));
+            }
+        }
+    }
+}
+
+// Joaca partida pana la capat cu motorul dat; intoarce true daca botul a castigat.
+pub fn play_game(bot: &mut Bot, engine: &mut dyn Engine, budget: Budget) -> Result<bool, AppError> {
+    engine.new_game(&bot.board, &bot.mouse, bot.role)?;
+    let mut stuck = false;
+    loop {
+        if bot.my_turn() && !stuck {
+            match engine.pick_move(budget)? {
+                Some(cell) => bot.send_move(cell)?,
+                // Soarecele blocat: serverul anunta singur sfarsitul jocului.
+                None => stuck = true,
+            }
+            continue;
+        }
+        match bot.next_event()? {
+            Event::Move(cell) => engine.opponent_move(cell)?,
+            Event::Over { won } => return Ok(won),
+            Event::OpponentLeft => return Ok(true),
+            // Regulile vin inaintea tablei; motoarele stiu doar jocul clasic.
+            Event::Restarted if !bot.variant.is_classic() => {
+                return Err(AppError::ConfigError(format!(
+                    "motorul joaca doar regulile clasice, camera are '{}'",
+                    bot.variant
+                )))
+            }
+            Event::Restarted => {
+                stuck = false;
+                engine.new_game(&bot.board, &bot.mouse, bot.role)?
+            }
+            Event::Other(_) => {}
+        }
+    }
+}
+
+pub fn connect(address: &str) -> Result<TcpStream, AppError> {
+    let addresses = address
+        .to_socket_addrs()
+        .map_err(|e| AppError::ConnectionError(format!("{}: {}", address, e)))?;
+
+    let mut last_error = AppError::ConnectionError(format!("{}: adresa nu exista", address));
+    for socket_address in addresses {
+        match TcpStream::connect_timeout(&socket_address, CONNECT_TIMEOUT) {
+            Ok(stream) => return Ok(stream),
+            Err(e) => last_error = AppError::ConnectionError(format!("{}: {}", address, e)),
+        }
+    }
+    Err(last_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::protocol::{RoomOptions, Snapshot};
+    use std::{net::TcpListener, thread};
+
+    fn request(code: i32) -> JoinRequest {
+        JoinRequest {
+            code,
+            name: String::from("bot"),
+            ticket: String::new(),
+            options: RoomOptions::default(),
+        }
+    }
+
+    // Serverul de proba: fiecare raspuns pleaca abia dupa ce a sosit confirmarea celui dinainte.
+    fn serve(replies: Vec<Vec<u8>>) -> (String, thread::JoinHandle<Vec<u8>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buffer = [0; 256];
+            let n = stream.read(&mut buffer).unwrap();
+            let mut received = buffer[..n].to_vec();
+            for reply in replies {
+                stream.write_all(&reply).unwrap();
+                // Pong-urile pentru ping-urile din raspuns vin inaintea confirmarii.
+                while !received.ends_with(b"ok") {
+                    match stream.read(&mut buffer).unwrap() {
+                        0 => return received,
+                        n => received.extend_from_slice(&buffer[..n]),
+                    }
+                }
+                received.truncate(received.len() - 2);
+            }
+            received
+        });
+        (address, server)
+    }
+
+    // Un bot deja in joc, legat de capatul serverului, pentru testele fara handshake.
+    fn bot_in_game(role: u8) -> (Bot, TcpStream) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let stream = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (server, _) = listener.accept().unwrap();
+        server
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let mut board = [[0; BOARD_SIZE]; BOARD_SIZE];
+        board[5][5] = 1;
+        let bot = Bot {
+            stream,
+            inbox: Vec::new(),
+            room: 0,
+            role,
+            board,
+            mouse: Position::new(5, 5),
+            turn: 1,
+            variant: Variant::default(),
+            walls_left: 1,
+            token: None,
+            finished: false,
+        };
+        (bot, server)
+    }
+
+    #[test]
+    fn join_reads_the_room_role_mouse_and_walls() {
+        let mut start = Message::Ping(7).to_bytes();
+        start.extend_from_slice(b"Jocul a inceput in camera! 3");
+        let (address, server) = serve(vec![
+            b"3".to_vec(),
+            start,
+            b"Tu esti soarecele !".to_vec(),
+            b"4,6".to_vec(),
+            vec![0, 3, 10, 10, 2, 7],
+        ]);
+
+        let bot = Bot::join(&address, &request(RANDOM_ROOM)).unwrap();
+        assert_eq!(bot.room, 3);
+        assert_eq!(bot.role, 1);
+        assert_eq!(bot.mouse, Position::new(4, 6));
+        assert_eq!(bot.board[4][6], 1);
+        for (row, col) in [(0, 3), (10, 10), (2, 7)] {
+            assert_eq!(bot.board[row][col], 2);
+        }
+        assert_eq!(
+            bot.board
+                .iter()
+                .flatten()
+                .filter(|&&cell| cell == 2)
+                .count(),
+            3
+        );
+
+        let received = server.join().unwrap();
+        let mut expected = request(RANDOM_ROOM).encode().into_bytes();
+        expected.extend_from_slice(&Message::Pong(7).to_bytes());
+        assert_eq!(received, expected);
+    }
+
+    #[test]
+    fn join_takes_the_walls_role_and_any_number_of_walls() {
+        let walls: Vec<u8> = (0..10).flat_map(|col| [0, col]).collect();
+        let (address, server) = serve(vec![
+            b"-2".to_vec(),
+            b"Jocul a inceput in camera! -2".to_vec(),
+            b"Tu pui zidurile!".to_vec(),
+            b"5,5".to_vec(),
+            walls,
+        ]);
+
+        let bot = Bot::join(&address, &request(COMPUTER_ROOM)).unwrap();
+        assert_eq!(bot.room, -2);
+        assert_eq!(bot.role, 2);
+        assert!((0..10).all(|col| bot.board[0][col] == 2));
+        assert_eq!(bot.board[0][10], 0);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn join_reports_a_full_server_and_a_rejected_request() {
+        for reply in ["-3", "-4"] {
+            let (address, server) = serve(vec![reply.as_bytes().to_vec()]);
+            assert!(matches!(
+                Bot::join(&address, &request(RANDOM_ROOM)),
+                Err(AppError::ConnectionError(_))
+            ));
+            server.join().unwrap();
+        }
+    }
+
+    #[test]
+    fn join_rejects_a_mouse_or_wall_off_the_board() {
+        let (address, server) = serve(vec![
+            b"1".to_vec(),
+            b"Jocul a inceput in camera! 1".to_vec(),
+            b"Tu pui zidurile!".to_vec(),
+            b"11,3".to_vec(),
+        ]);
+        assert!(Bot::join(&address, &request(RANDOM_ROOM)).is_err());
+        server.join().unwrap();
+
+        let (address, server) = serve(vec![
+            b"1".to_vec(),
+            b"Jocul a inceput in camera! 1".to_vec(),
+            b"Tu pui zidurile!".to_vec(),
+            b"5,5".to_vec(),
+            vec![3, 3, 4, 11],
+        ]);
+        assert!(Bot::join(&address, &request(RANDOM_ROOM)).is_err());
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn double_walls_keep_the_turn_until_both_walls_are_placed() {
+        let (mut bot, _server) = bot_in_game(2);
+        let variant = Variant::parse("ziduri2").unwrap();
+        assert_eq!(bot.handle(Message::Rules(variant, 2)).unwrap(), None);
+
+        assert_eq!(
+            bot.handle(Message::Move(5, 6)).unwrap(),
+            Some(Event::Move((5, 6)))
+        );
+        assert_eq!(bot.mouse, Position::new(5, 6));
+        assert!(bot.my_turn());
+        assert_eq!(bot.walls_left, 2);
+
+        bot.send_move((4, 6)).unwrap();
+        assert!(bot.my_turn());
+        assert_eq!(bot.walls_left, 1);
+        bot.send_move((4, 7)).unwrap();
+        assert!(!bot.my_turn());
+        assert_eq!(bot.walls_left, 2);
+        assert_eq!((bot.board[4][6], bot.board[4][7]), (2, 2));
+    }
+
+    #[test]
+    fn classic_walls_pass_the_turn_after_one_wall() {
+        let (mut bot, _server) = bot_in_game(1);
+        bot.send_move((5, 6)).unwrap();
+        assert!(!bot.my_turn());
+        assert!(bot.send_move((5, 7)).is_err());
+
+        bot.handle(Message::Move(4, 6)).unwrap();
+        assert_eq!(bot.board[4][6], 2);
+        assert!(bot.my_turn());
+        assert!(bot.send_move((4, 6)).is_err());
+    }
+
+    #[test]
+    fn snapshot_replaces_the_board_and_restarts_the_game() {
+        let (mut bot, _server) = bot_in_game(1);
+        bot.finished = true;
+        let mut board = [[0; BOARD_SIZE]; BOARD_SIZE];
+        board[2][3] = 1;
+        board[7][7] = 2;
+        let snapshot = Snapshot {
+            player: 2,
+            turn: 1,
+            mouse: (2, 3),
+            board,
+        };
+
+        assert_eq!(
+            bot.handle(Message::Snapshot(snapshot)).unwrap(),
+            Some(Event::Restarted)
+        );
+        assert_eq!(bot.board, board);
+        assert_eq!(bot.mouse, Position::new(2, 3));
+        assert_eq!((bot.role, bot.turn), (2, 1));
+        assert!(!bot.finished);
+    }
+
+    #[test]
+    fn game_over_is_confirmed_once() {
+        let (mut bot, mut server) = bot_in_game(2);
+        assert_eq!(
+            bot.handle(Message::Lose).unwrap(),
+            Some(Event::Over { won: false })
+        );
+        assert!(bot.finished);
+        assert_eq!(bot.handle(Message::Win).unwrap(), None);
+        assert_eq!(bot.handle(Message::Ping(4)).unwrap(), None);
+
+        assert_eq!(Message::read_from(&mut server).unwrap(), Message::Lose);
+        assert_eq!(Message::read_from(&mut server).unwrap(), Message::Pong(4));
+    }
+
+    #[test]
+    fn next_event_answers_pings_and_skips_known_messages() {
+        let (mut bot, mut server) = bot_in_game(2);
+        let mut bytes = Message::Ping(1).to_bytes();
+        bytes.extend_from_slice(&Message::Token(String::from("abc")).to_bytes());
+        bytes.extend_from_slice(&Message::Move(5, 4).to_bytes());
+        server.write_all(&bytes).unwrap();
+
+        assert_eq!(bot.next_event().unwrap(), Event::Move((5, 4)));
+        assert_eq!(bot.token.as_deref(), Some("abc"));
+        assert_eq!(Message::read_from(&mut server).unwrap(), Message::Pong(1));
+    }
+}
@@ -0,0 +1,150 @@
+use bot::{play_game, Bot, RANDOM_ROOM};
+use common::{
+    engine::{strategy, Budget, Local},
+    protocol::{JoinRequest, RoomOptions},
+    AppError,
+};
+use std::{
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+const USAGE: &str = "Utilizare: bot [optiuni]
+
+Optiuni:
+  --server <adresa>   serverul (implicit 127.0.0.1:8080)
+  --room <cod>        camera (implicit -1 = aleatoare, -2 = contra calculatorului)
+  --name <nume>       numele afisat adversarului
+  --engine <motor>    euristic, anticipare sau mcts (implicit euristic)
+  --time <ms>         timpul de gandire pe mutare (implicit 500)
+  --games <n>         cate partide joaca fiecare bot (implicit 1)
+  --bots <n>          cati boti joaca in paralel (implicit 1)";
+
+struct Options {
+    server: String,
+    room: i32,
+    name: String,
+    engine: String,
+    time: u64,
+    games: usize,
+    bots: usize,
+}
+
+#[derive(Default)]
+struct Results {
+    won: usize,
+    lost: usize,
+    failed: usize,
+}
+
+fn parse_args(args: &[String]) -> Result<Options, AppError> {
+    let mut options = Options {
+        server: String::from("127.0.0.1:8080"),
+        room: RANDOM_ROOM,
+        name: String::from("bot"),
+        engine: String::from("euristic"),
+        time: 500,
+        games: 1,
+        bots: 1,
+    };
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        let value = args
+            .next()
+            .ok_or_else(|| AppError::ConfigError(format!("{} are nevoie de o valoare", flag)))?;
+        let number = || {
+            value
+                .parse::<u64>()
+                .map_err(|_| AppError::ConfigError(format!("{} asteapta un numar", flag)))
+        };
+        match flag.as_str() {
+            "--server" => options.server = value.clone(),
+            "--room" => {
+                options.room = value.parse().map_err(|_| {
+                    AppError::ConfigError(format!("cod de camera invalid: {}", value))
+                })?
+            }
+            "--name" => options.name = value.clone(),
+            "--engine" => options.engine = value.clone(),
+            "--time" => options.time = number()?,
+            "--games" => options.games = number()? as usize,
+            "--bots" => options.bots = number()?.max(1) as usize,
+            _ => return Err(AppError::ConfigError(String::from(USAGE))),
+        }
+    }
+    // Verificam motorul inainte sa pornim firele.
+    strategy(&options.engine, Budget::default(), 1)?;
+    Ok(options)
+}
+
+fn run_bot(index: usize, options: &Options, results: &Mutex<Results>) {
+    let budget = Budget::time(Duration::from_millis(options.time));
+    for game in 0..options.games {
+        let request = JoinRequest {
+            code: options.room,
+            name: format!("{}{}", options.name, index + 1),
+            ticket: String::new(),
+            options: RoomOptions::default(),
+        };
+        let result = strategy(&options.engine, budget, 1).and_then(|strategy| {
+            let mut engine = Local::new(strategy);
+            let mut bot = Bot::join(&options.server, &request)?;
+            let won = play_game(&mut bot, &mut engine, budget)?;
+            println!(
+                "Botul {} partida {}: camera {}, {} ({})",
+                index + 1,
+                game + 1,
+                bot.room,
+                if won { "castigata" } else { "pierduta" },
+                if bot.role == 1 { "soarece" } else { "ziduri" }
+            );
+            Ok(won)
+        });
+        let mut results = results.lock().unwrap();
+        match result {
+            Ok(true) => results.won += 1,
+            Ok(false) => results.lost += 1,
+            Err(e) => {
+                eprintln!("Botul {} partida {}: {}", index + 1, game + 1, e);
+                results.failed += 1;
+            }
+        }
+    }
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|arg| arg == "--help" || arg == "-h") {
+        println!("{}", USAGE);
+        return;
+    }
+    let options = match parse_args(&args) {
+        Ok(options) => Arc::new(options),
+        Err(e) => {
+            e.log();
+            std::process::exit(2);
+        }
+    };
+
+    let results = Arc::new(Mutex::new(Results::default()));
+    let bots: Vec<_> = (0..options.bots)
+        .map(|index| {
+            let options = Arc::clone(&options);
+            let results = Arc::clone(&results);
+            thread::spawn(move || run_bot(index, &options, &results))
+        })
+        .collect();
+    for bot in bots {
+        let _ = bot.join();
+    }
+
+    let results = results.lock().unwrap();
+    println!(
+        "Castigate: {}, pierdute: {}, esuate: {}",
+        results.won, results.lost, results.failed
+    );
+    if results.failed > 0 {
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,169 @@
+use common::{
+    ai::escape_routes,
+    analysis::{accuracy, analyse, GameRecord, Ply, Review, Verdict},
+    rules::BOARD_SIZE,
+};
+use eframe::egui::{self, Color32, RichText, TextureHandle};
+
+use crate::board;
+
+pub struct Analysis {
+    plies: Vec<Ply>,
+    reviews: Vec<Review>,
+    player: u8,
+    current: usize,
+}
+
+impl Analysis {
+    pub fn new(record: &GameRecord, player: u8) -> Self {
+        Analysis {
+            plies: record.plies(),
+            reviews: analyse(record),
+            player,
+            current: 0,
+        }
+    }
+
+    // Intoarce true cand jucatorul vrea sa revina la tabla.
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        texture: Option<&TextureHandle>,
+        show_escape: &mut bool,
+    ) -> bool {
+        self.show_moves(ctx);
+
+        egui::CentralPanel::default()
+            .show(ctx, |ui| {
+                let back = ui
+                    .vertical_centered(|ui| {
+                        ui.add_space(10.0);
+                        ui.label(RichText::new("Analiza partidei").size(24.0));
+                        ui.label(RichText::new(self.describe()).size(16.0));
+                        ui.checkbox(show_escape, "Arata drumul de scapare");
+                        ui.button("Inapoi").clicked()
+                    })
+                    .inner;
+                self.paint(ui, texture, *show_escape);
+                back
+            })
+            .inner
+    }
+
+    fn describe(&self) -> String {
+        match self.reviews.get(self.current) {
+            Some(review) => {
+                let (row, col) = review.played;
+                let mut text = format!(
+                    "Mutarea {}/{}: {} {},{} - {}",
+                    self.current + 1,
+                    self.reviews.len(),
+                    role_name(review.role),
+                    row,
+                    col,
+                    review.verdict
+                );
+                if let Some((row, col)) = review.best.filter(|&best| best != review.played) {
+                    text.push_str(&format!(" (calculatorul: {},{})", row, col));
+                }
+                text
+            }
+            None => String::from("Pozitia finala"),
+        }
+    }
+
+    fn paint(&self, ui: &egui::Ui, texture: Option<&TextureHandle>, show_escape: bool) {
+        let painter = ui.painter();
+        let ply = &self.plies[self.current];
+        let review = self.reviews.get(self.current);
+        for row in 0..BOARD_SIZE {
+            for col in 0..BOARD_SIZE {
+                let fill = if ply.board[row][col] == 0 {
+                    board::FREE
+                } else {
+                    board::WALL
+                };
+                let center = board::cell_center(row, col);
+                board::paint_cell(painter, texture, center, ply.board[row][col], fill);
+                if review.is_some_and(|review| review.played == (row, col)) {
+                    board::outline_cell(painter, center, Color32::BLACK);
+                } else if review.is_some_and(|review| review.best == Some((row, col))) {
+                    board::outline_cell(painter, center, board::SUGGESTED);
+                }
+            }
+        }
+        if show_escape {
+            let routes = escape_routes(&ply.board, ply.mouse.x, ply.mouse.y);
+            board::draw_escape_routes(painter, routes.as_ref());
+        }
+    }
+
+    fn show_moves(&mut self, ctx: &egui::Context) {
+        egui::Window::new("Mutari")
+            .id(egui::Id::new("analysis_moves"))
+            .anchor(egui::Align2::RIGHT_TOP, [-10.0, 10.0])
+            .collapsible(true)
+            .resizable(false)
+            .default_width(260.0)
+            .show(ctx, |ui| {
+                for role in [1, 2] {
+                    let you = if role == self.player { " (tu)" } else { "" };
+                    let text = match accuracy(&self.reviews, role) {
+                        Some(percent) => {
+                            format!("Precizie {}{}: {}%", role_name(role), you, percent)
+                        }
+                        None => format!("Precizie {}{}: -", role_name(role), you),
+                    };
+                    ui.label(RichText::new(text).strong());
+                }
+                ui.horizontal(|ui| {
+                    if ui.button("<").clicked() {
+                        self.current = self.current.saturating_sub(1);
+                    }
+                    if ui.button(">").clicked() {
+                        self.current = (self.current + 1).min(self.plies.len() - 1);
+                    }
+                });
+                ui.separator();
+                egui::ScrollArea::vertical()
+                    .max_height(400.0)
+                    .show(ui, |ui| {
+                        for (index, review) in self.reviews.iter().enumerate() {
+                            let (row, col) = review.played;
+                            let mut text = RichText::new(format!(
+                                "{}. {} {},{} - {}",
+                                index + 1,
+                                role_name(review.role),
+                                row,
+                                col,
+                                review.verdict
+                            ))
+                            .color(verdict_color(review.verdict));
+                            if index == self.current {
+                                text = text.strong();
+                            }
+                            if ui.selectable_label(index == self.current, text).clicked() {
+                                self.current = index;
+                            }
+                        }
+                    });
+            });
+    }
+}
+
+fn role_name(role: u8) -> &'static str {
+    if role == 1 {
+        "soarece"
+    } else {
+        "ziduri"
+    }
+}
+
+fn verdict_color(verdict: Verdict) -> Color32 {
+    match verdict {
+        Verdict::Best => Color32::from_rgb(0, 150, 0),
+        Verdict::Good => Color32::from_rgb(90, 160, 90),
+        Verdict::Inaccuracy => Color32::from_rgb(200, 150, 0),
+        Verdict::Blunder => Color32::RED,
+    }
+}
@@ -0,0 +1,111 @@
+use common::ai::EscapeRoutes;
+use eframe::egui::{self, Color32, Painter, Pos2, Rect, Shape, Stroke, TextureHandle, Vec2};
+
+pub const HEX_RADIUS: f32 = 23.0;
+pub const START_X: f32 = 160.0;
+pub const START_Y: f32 = 140.0;
+pub const FREE: Color32 = Color32::from_rgb(100, 200, 100);
+pub const WALL: Color32 = Color32::from_rgb(255, 51, 0);
+pub const LEGAL: Color32 = Color32::from_rgb(170, 235, 140);
+pub const HOLE: Color32 = Color32::from_rgb(90, 60, 40);
+pub const GATE: Color32 = Color32::from_rgb(80, 160, 255);
+pub const SUGGESTED: Color32 = Color32::from_rgb(0, 90, 220);
+
+pub fn cell_size() -> Vec2 {
+    Vec2::new(3.0_f32.sqrt() * HEX_RADIUS, 2.0 * HEX_RADIUS)
+}
+
+pub fn cell_center(row: usize, col: usize) -> Pos2 {
+    let size = cell_size();
+    let x = START_X + col as f32 * size.x * 1.10;
+    let y = START_Y + row as f32 * size.y * 0.85;
+
+    let x = if row % 2 == 1 { x + size.x * 0.5 } else { x };
+    Pos2::new(x, y)
+}
+
+pub fn paint_cell(
+    painter: &Painter,
+    texture: Option<&TextureHandle>,
+    center: Pos2,
+    value: u8,
+    fill: Color32,
+) {
+    let hex_points = hexagon_points(center.x, center.y, HEX_RADIUS);
+
+    if value == 1 {
+        if let Some(texture) = texture {
+            let rect =
+                Rect::from_center_size(center, Vec2::new(HEX_RADIUS * 1.5, HEX_RADIUS * 1.5));
+
+            painter.image(
+                texture.id(),
+                rect,
+                Rect::from_min_max(Pos2::new(0.0, 0.0), Pos2::new(1.0, 1.0)),
+                Color32::WHITE,
+            );
+
+            painter.add(Shape::convex_polygon(
+                hex_points,
+                Color32::TRANSPARENT,
+                Stroke::new(2.0, Color32::BLACK),
+            ));
+        }
+    } else {
+        painter.add(Shape::convex_polygon(
+            hex_points,
+            fill,
+            Stroke::new(2.0, Color32::BLACK),
+        ));
+    }
+}
+
+pub fn outline_cell(painter: &Painter, center: Pos2, color: Color32) {
+    painter.add(Shape::convex_polygon(
+        hexagon_points(center.x, center.y, HEX_RADIUS - 3.0),
+        Color32::TRANSPARENT,
+        Stroke::new(4.0, color),
+    ));
+}
+
+pub fn draw_escape_routes(painter: &Painter, routes: Option<&EscapeRoutes>) {
+    let color = Color32::from_rgb(255, 200, 0);
+    let text = match routes {
+        Some(routes) => {
+            for &((fx, fy), (tx, ty)) in routes.steps.iter() {
+                painter.line_segment(
+                    [cell_center(fx, fy), cell_center(tx, ty)],
+                    Stroke::new(3.0, color),
+                );
+            }
+            for &(x, y) in routes.exits.iter() {
+                painter.circle_filled(cell_center(x, y), 6.0, color);
+            }
+            format!(
+                "Scapare in {}\n{} iesiri",
+                routes.distance,
+                routes.exits.len()
+            )
+        }
+        None => String::from("Soarecele\nnu mai scapa"),
+    };
+    painter.text(
+        Pos2::new(15.0, START_Y + 140.0),
+        egui::Align2::LEFT_TOP,
+        text,
+        egui::FontId::proportional(18.0),
+        Color32::BLACK,
+    );
+}
+
+fn hexagon_points(center_x: f32, center_y: f32, radius: f32) -> Vec<Pos2> {
+    (0..6)
+        .map(|i| {
+            let angle = std::f32::consts::PI / 3.0 * i as f32 - std::f32::consts::PI / 6.0;
+            Pos2::new(
+                center_x + radius * angle.cos(),
+                center_y + radius * angle.sin(),
+            )
+        })
+        .collect()
+}
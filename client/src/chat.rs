@@ -0,0 +1,87 @@
+use common::protocol::MAX_CHAT_LEN;
+use eframe::egui::{self, RichText, Vec2};
+use std::collections::VecDeque;
+
+const CHAT_HISTORY: usize = 100;
+
+pub struct ChatLine {
+    pub from: String,
+    pub text: String,
+}
+
+#[derive(Default)]
+pub struct Chat {
+    pub lines: VecDeque<ChatLine>,
+    pub input: String,
+    pub open: bool,
+    pub status: Option<String>,
+}
+
+impl Chat {
+    pub fn push(&mut self, from: String, text: String) {
+        self.lines.push_back(ChatLine { from, text });
+        if self.lines.len() > CHAT_HISTORY {
+            self.lines.pop_front();
+        }
+    }
+
+    // Deseneaza panoul de chat si intoarce mesajul scris, daca a fost trimis.
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        id: &str,
+        title: &str,
+        can_send: bool,
+    ) -> Option<String> {
+        let response = egui::Window::new(title)
+            .id(egui::Id::new(id))
+            .anchor(egui::Align2::RIGHT_TOP, [-10.0, 10.0])
+            .default_open(false)
+            .collapsible(true)
+            .resizable(false)
+            .default_width(260.0)
+            .show(ctx, |ui| {
+                if let Some(status) = &self.status {
+                    ui.label(RichText::new(status).color(egui::Color32::RED));
+                }
+                egui::ScrollArea::vertical()
+                    .max_height(250.0)
+                    .stick_to_bottom(true)
+                    .show(ui, |ui| {
+                        for line in self.lines.iter() {
+                            if line.from.is_empty() {
+                                ui.label(
+                                    RichText::new(&line.text)
+                                        .italics()
+                                        .color(egui::Color32::GRAY),
+                                );
+                            } else {
+                                ui.label(format!("{}: {}", line.from, line.text));
+                            }
+                        }
+                    });
+                if !can_send {
+                    return None;
+                }
+                ui.separator();
+                ui.horizontal(|ui| {
+                    let input = ui.add_sized(
+                        Vec2::new(190.0, 24.0),
+                        egui::TextEdit::singleline(&mut self.input).char_limit(MAX_CHAT_LEN),
+                    );
+                    let enter =
+                        input.lost_focus() && ui.input(|input| input.key_pressed(egui::Key::Enter));
+                    if (ui.button("Trimite").clicked() || enter) && !self.input.trim().is_empty() {
+                        input.request_focus();
+                        return Some(std::mem::take(&mut self.input));
+                    }
+                    None
+                })
+                .inner
+            });
+
+        let inner = response.and_then(|response| response.inner);
+        self.open = inner.is_some();
+        inner.flatten()
+    }
+}
@@ -1,17 +1,60 @@
-use common::{convert_to_i32, send_ok, AppError, Position};
+use common::{
+    ai::computer_move,
+    analysis::GameRecord,
+    clock::TimeControl,
+    convert_to_i32,
+    engine::{Budget, Strategy},
+    mcts::Mcts,
+    protocol::{parse_games, JoinRequest, MatchState, Message, RoomOptions},
+    rules::{
+        check_any_left_move, generate_mouse_pos, generate_wall_positions, is_border_on, new_board,
+        Board, Variant, BOARD_SIZE, INITIAL_WALLS,
+    },
+    send_ok,
+    tablebase::Tablebase,
+    AppError, Position,
+};
+
+use crate::{
+    analysis::Analysis,
+    chat::Chat,
+    settings::ServerSettings,
+    tournament::{join_tournament, Tournament},
+};
+
 use std::{
     io::{ErrorKind, Read, Write},
-    net::TcpStream,
+    net::{TcpStream, ToSocketAddrs},
     sync::{Arc, RwLock},
-    thread,
-    time::Duration,
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
 };
 
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+const RECONNECT_LIMIT: Duration = Duration::from_secs(60);
+const LOBBY_RETRY: Duration = Duration::from_secs(5);
+
+type Search = JoinHandle<Option<(usize, usize)>>;
+
+// Cat se gandeste calculatorul MCTS la o mutare.
+const MCTS_TIME: Duration = Duration::from_millis(1500);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Level {
+    Normal,
+    Mcts,
+    Perfect,
+}
+
 pub enum Screen {
     MainMenu,
     WaitingForPlayers,
     ConnectingError,
     GameBoard,
+    Settings,
+    Spectate,
+    Tournament,
+    Analysis,
 }
 
 pub enum State {
@@ -22,14 +65,40 @@ pub enum State {
     GetMouse,
     GetWalls,
     Play,
+    Reconnect,
+    Spectate,
+    PlayComputer,
     Finished,
     PlayAgain,
 }
 
+pub enum Rematch {
+    None,
+    Requested,
+    Offered,
+    Declined,
+}
+
+pub enum Takeback {
+    None,
+    Requested,
+    Offered,
+    Declined,
+}
+
+pub enum ConnectionQuality {
+    Good,
+    Fair,
+    Poor,
+    Lost,
+}
+
 pub struct Game {
     pub screen: Screen,
     pub show_input_1: bool,
     pub show_input_2: bool,
+    pub show_input_3: bool,
+    pub show_input_4: bool,
     pub room_input: String,
     pub stream: Option<TcpStream>,
     pub player_text: Option<String>,
@@ -37,12 +106,65 @@ pub struct Game {
     pub mouse_texture: Option<eframe::egui::TextureHandle>,
     pub initial_mouse: bool,
     pub mouse: Position,
-    pub board: [[u8; 11]; 11],
+    pub board: Board,
     pub has_to_read: bool,
     pub win: bool,
     pub state: State,
     pub win_state: String,
     pub try_connect: bool,
+    pub offline: bool,
+    pub ai_search: Option<Search>,
+    pub server: ServerSettings,
+    pub connect_error: Option<String>,
+    pub inbox: Vec<u8>,
+    pub resume_token: Option<String>,
+    pub reconnecting_since: Option<Instant>,
+    pub awaiting_snapshot: bool,
+    pub opponent_waiting: bool,
+    pub last_seen: Instant,
+    pub next_ping: Instant,
+    pub ping_seq: u32,
+    pub ping_sent: Option<(u32, Instant)>,
+    pub missed_pings: u32,
+    pub rtt: Option<Duration>,
+    pub time_mode: usize,
+    pub time_seconds: String,
+    pub time_increment: String,
+    pub time_error: Option<String>,
+    pub match_games: String,
+    // Regulile alese in meniu pentru camera noua; `variant` sunt cele ale jocului curent.
+    pub rules: Variant,
+    pub room_options: RoomOptions,
+    pub clocks: Option<[Duration; 2]>,
+    pub clock_turn: i32,
+    pub clock_received: Instant,
+    pub name: String,
+    pub names: [String; 2],
+    pub spectating: bool,
+    pub turn: i32,
+    pub variant: Variant,
+    pub walls_placed: u8,
+    pub chat: Chat,
+    pub lobby_chat: Chat,
+    pub lobby_stream: Option<TcpStream>,
+    pub lobby_inbox: Vec<u8>,
+    pub lobby_retry: Instant,
+    pub rematch: Rematch,
+    pub series: Option<[u8; 2]>,
+    pub match_state: Option<MatchState>,
+    pub ticket: String,
+    pub tournament: Tournament,
+    pub takeback: Takeback,
+    pub history: Vec<(Board, (usize, usize))>,
+    pub show_moves: bool,
+    pub show_escape: bool,
+    pub record: Option<GameRecord>,
+    pub analysis: Option<Analysis>,
+    pub hint: Option<(Board, (usize, usize))>,
+    pub hint_search: Option<(Board, Search)>,
+    pub tablebase: Option<Arc<Tablebase>>,
+    pub level: Level,
+    pub size: usize,
 }
 
 impl Default for Game {
@@ -51,6 +173,8 @@ impl Default for Game {
             screen: Screen::MainMenu,
             show_input_1: false,
             show_input_2: false,
+            show_input_3: false,
+            show_input_4: false,
             room_input: String::from("-1"),
             stream: None,
             try_connect: false,
@@ -64,297 +188,1085 @@ impl Default for Game {
             win: false,
             state: State::WaitTryConnect,
             win_state: String::new(),
+            offline: false,
+            ai_search: None,
+            server: ServerSettings::load(),
+            connect_error: None,
+            inbox: Vec::new(),
+            resume_token: None,
+            reconnecting_since: None,
+            awaiting_snapshot: false,
+            opponent_waiting: false,
+            last_seen: Instant::now(),
+            next_ping: Instant::now(),
+            ping_seq: 0,
+            ping_sent: None,
+            missed_pings: 0,
+            rtt: None,
+            time_mode: 0,
+            time_seconds: String::from("30"),
+            time_increment: String::from("0"),
+            time_error: None,
+            match_games: String::from("1"),
+            rules: Variant::default(),
+            room_options: RoomOptions::default(),
+            clocks: None,
+            clock_turn: 1,
+            clock_received: Instant::now(),
+            name: String::new(),
+            names: [String::new(), String::new()],
+            spectating: false,
+            turn: 1,
+            variant: Variant::default(),
+            walls_placed: 0,
+            chat: Chat::default(),
+            lobby_chat: Chat::default(),
+            lobby_stream: None,
+            lobby_inbox: Vec::new(),
+            lobby_retry: Instant::now(),
+            rematch: Rematch::None,
+            series: None,
+            match_state: None,
+            ticket: String::new(),
+            tournament: Tournament::default(),
+            takeback: Takeback::None,
+            history: Vec::new(),
+            show_moves: false,
+            show_escape: false,
+            record: None,
+            analysis: None,
+            hint: None,
+            hint_search: None,
+            tablebase: None,
+            level: Level::Normal,
+            size: BOARD_SIZE,
         }
     }
 }
 
 impl Game {
     pub fn start_tcp(game: Arc<RwLock<Self>>) {
-        {
-            let mut game = game.write().unwrap();
-
-            match TcpStream::connect("127.0.0.1:8080") {
-                Ok(stream) => {
-                    game.stream = Some(stream);
+        thread::spawn(move || loop {
+            let address = {
+                let game = game.read().unwrap();
+                match game.state {
+                    State::Connect if game.stream.is_none() => Some(game.server.address()),
+                    _ => None,
                 }
-                Err(e) => {
-                    AppError::ConnectionError(e.to_string()).log();
-                    game.screen = Screen::ConnectingError;
-                    return;
+            };
+            let resume = {
+                let game = game.read().unwrap();
+                match (&game.state, &game.resume_token) {
+                    (State::Reconnect, Some(token)) => Some((game.server.address(), token.clone())),
+                    _ => None,
                 }
-            }
-        }
-        thread::spawn(move || {
-            loop {
-                {
-                    let mut game = game.write().unwrap();
-
-                    match game.state {
-                        State::WaitTryConnect => {
-                            if game.try_connect {
-                                game.state = State::Connect;
-                            }
+            };
+            if let Some((address, token)) = resume {
+                let result = address.and_then(|address| resume_game(&address, &token));
+                let mut game = game.write().unwrap();
+                match result {
+                    Ok(stream) => {
+                        game.stream = Some(stream);
+                        game.awaiting_snapshot = true;
+                        game.reset_heartbeat();
+                        game.state = State::Play;
+                    }
+                    Err(e) => {
+                        e.log();
+                        if game
+                            .reconnecting_since
+                            .is_some_and(|since| since.elapsed() > RECONNECT_LIMIT)
+                        {
+                            game.finish_with(String::from("CONEXIUNEA CU SERVERUL S-A PIERDUT"));
                         }
-                        State::Connect => {
-                            let room_id = convert_to_i32(&game.room_input);
-
-                            match game
-                                .stream
-                                .as_ref()
-                                .unwrap()
-                                .write_all(room_id.to_string().as_bytes())
-                            {
-                                Ok(_) => {}
-                                Err(e) => {
-                                    AppError::WriteError(e.to_string()).log();
-                                }
-                            }
+                        drop(game);
+                        thread::sleep(Duration::from_secs(1));
+                    }
+                }
+            }
 
-                            let mut buffer = [0; 1024];
+            let lobby = {
+                let game = game.read().unwrap();
+                if game.lobby_chat.open
+                    && game.lobby_stream.is_none()
+                    && Instant::now() >= game.lobby_retry
+                {
+                    Some((game.server.address(), game.name.clone()))
+                } else {
+                    None
+                }
+            };
+            if let Some((address, name)) = lobby {
+                let result = address.and_then(|address| join_lobby(&address, &name));
+                let mut game = game.write().unwrap();
+                match result {
+                    Ok(stream) => {
+                        game.lobby_stream = Some(stream);
+                        game.lobby_chat.status = None;
+                    }
+                    Err(e) => {
+                        game.lobby_chat.status = Some(e.log().to_string());
+                        game.lobby_retry = Instant::now() + LOBBY_RETRY;
+                    }
+                }
+            }
 
-                            match game.stream.as_ref().unwrap().read(&mut buffer) {
-                                Ok(0) => {
-                                    game.screen = Screen::ConnectingError;
+            let tournament = {
+                let game = game.read().unwrap();
+                match (&game.tournament.request, &game.tournament.stream) {
+                    (Some(request), None) => Some((game.server.address(), request.clone())),
+                    _ => None,
+                }
+            };
+            if let Some((address, request)) = tournament {
+                let result = address.and_then(|address| join_tournament(&address, &request));
+                let mut game = game.write().unwrap();
+                game.tournament.request = None;
+                match result {
+                    Ok(stream) => {
+                        game.tournament.stream = Some(stream);
+                        game.tournament.status = None;
+                    }
+                    Err(e) => game.tournament.status = Some(e.log().to_string()),
+                }
+            }
 
-                                    return;
-                                }
-                                Ok(n) => {
-                                    let response = String::from_utf8_lossy(&buffer[..n]);
-                                    game.room_input = response.to_string();
-                                    send_ok(&game.stream);
+            if let Some(address) = address {
+                let result = address.and_then(|address| connect(&address));
+                let mut game = game.write().unwrap();
+                match result {
+                    Ok(stream) => {
+                        game.stream = Some(stream);
+                        game.connect_error = None;
+                        game.server.remember();
+                    }
+                    Err(e) => {
+                        game.connect_error = Some(e.log().to_string());
+                        game.screen = Screen::ConnectingError;
+                        game.try_connect = false;
+                        game.state = State::WaitTryConnect;
+                    }
+                }
+            }
 
-                                    game.screen = Screen::WaitingForPlayers;
+            {
+                let mut game = game.write().unwrap();
 
-                                    game.state = State::WaitToStart;
+                match game.state {
+                    State::WaitTryConnect => {
+                        if game.try_connect {
+                            game.state = State::Connect;
+                        }
+                    }
+                    State::Connect if game.spectating => {
+                        let request = format!("spectate {}", convert_to_i32(&game.room_input));
+                        if let Err(e) = game.stream.as_ref().unwrap().write_all(request.as_bytes())
+                        {
+                            AppError::WriteError(e.to_string()).log();
+                        }
+                        println!("Privim jocul din camera: {}", game.room_input);
+                        game.screen = Screen::Spectate;
+                        game.state = State::Spectate;
+                    }
+                    State::Connect => {
+                        let request = JoinRequest {
+                            code: convert_to_i32(&game.room_input),
+                            name: game.name.clone(),
+                            ticket: std::mem::take(&mut game.ticket),
+                            options: std::mem::take(&mut game.room_options),
+                        }
+                        .encode();
 
-                                    println!("Te-ai conectat in camera: {}", game.room_input);
-                                }
-                                Err(e) => {
-                                    AppError::ReadError(e.to_string()).log();
-                                }
+                        match game.stream.as_ref().unwrap().write_all(request.as_bytes()) {
+                            Ok(_) => {}
+                            Err(e) => {
+                                AppError::WriteError(e.to_string()).log();
                             }
                         }
-                        State::WaitToStart => {
-                            let mut buffer = [0; 1024];
-                            match game.stream.as_ref().unwrap().read(&mut buffer) {
-                                Ok(0) => {
-                                    game.screen = Screen::ConnectingError;
-                                    break;
-                                }
-                                Ok(n) => {
-                                    let message = String::from_utf8_lossy(&buffer[..n]);
-                                    println!("START! {}", message);
-                                    game.stream.as_ref().unwrap().flush().expect("Err flush");
 
-                                    game.screen = Screen::GameBoard;
-                                    send_ok(&game.stream);
-                                    game.state = State::GetTurn;
-                                }
-                                Err(e) if e.kind() == ErrorKind::WouldBlock => {}
-                                Err(e) => {
-                                    AppError::ReadError(e.to_string()).log();
-                                }
+                        let mut buffer = [0; 1024];
+
+                        match game.stream.as_ref().unwrap().read(&mut buffer) {
+                            Ok(0) => {
+                                game.disconnect(String::from("Serverul a inchis conexiunea"));
                             }
-                        }
-                        State::GetTurn => {
-                            let mut buffer = [0; 1024];
-                            match game.stream.as_ref().unwrap().read(&mut buffer) {
-                                Ok(0) => {
-                                    game.screen = Screen::ConnectingError;
-                                    break;
-                                }
-                                Ok(n) => {
-                                    game.player_text =
-                                        Some(String::from_utf8_lossy(&buffer[..n]).to_string());
-                                    if game.player_text.as_ref().unwrap().contains("soarecele") {
-                                        game.player = 1;
-                                    } else {
-                                        game.player = 2;
-                                        game.has_to_read = true;
-                                    }
-                                    send_ok(&game.stream);
-                                    game.state = State::GetMouse;
-                                    println!("Player {}", game.player_text.as_ref().unwrap());
+                            Ok(n) => {
+                                let response = String::from_utf8_lossy(&buffer[..n]);
+                                if response.trim() == "-3" {
+                                    game.disconnect(String::from("Serverul este plin"));
+                                    continue;
                                 }
-                                Err(e) if e.kind() == ErrorKind::WouldBlock => {}
-                                Err(e) => {
-                                    AppError::ReadError(e.to_string()).log();
+                                if response.trim() == "-4" {
+                                    game.disconnect(String::from("Serverul a respins cererea"));
+                                    continue;
                                 }
+                                game.room_input = response.to_string();
+                                send_ok(&game.stream);
+
+                                game.screen = Screen::WaitingForPlayers;
+
+                                game.state = State::WaitToStart;
+
+                                println!("Te-ai conectat in camera: {}", game.room_input);
+                            }
+                            Err(e) => {
+                                AppError::ReadError(e.to_string()).log();
                             }
                         }
-                        State::GetMouse => {
-                            let mut buffer = [0; 1024];
-
-                            let mut row = 0;
-                            let mut col = 0;
-                            match game.stream.as_ref().unwrap().read(&mut buffer) {
-                                Ok(0) => {
-                                    game.screen = Screen::ConnectingError;
-                                    break;
+                    }
+                    State::WaitToStart => {
+                        if let Err(e) = game.stream.as_ref().unwrap().set_nonblocking(true) {
+                            AppError::StreamUnavailable(e.to_string()).log();
+                        }
+                        let mut buffer = [0; 1024];
+                        match game.stream.as_ref().unwrap().read(&mut buffer) {
+                            Ok(0) => {
+                                game.disconnect(String::from("Serverul a inchis conexiunea"));
+                            }
+                            Ok(n) => {
+                                let mut data = &buffer[..n];
+                                while let Ok(Some((Message::Ping(seq), used))) =
+                                    Message::parse(data)
+                                {
+                                    game.send_message(&Message::Pong(seq));
+                                    data = &data[used..];
                                 }
-                                Ok(n) => {
-                                    let msg = String::from_utf8_lossy(&buffer[..n]);
-                                    let parts: Vec<&str> = msg.split(',').collect();
-                                    if parts.len() >= 2 {
-                                        if let Ok(r) = parts[0].parse::<usize>() {
-                                            row = r;
-                                        }
-                                        if let Ok(c) = parts[1].parse::<usize>() {
-                                            col = c;
-                                        }
-                                        game.mouse = Position::new(row, col);
-                                        game.board[row][col] = 1;
-                                        println!("Mouse initial la: {}, {}", row, col);
-                                        game.initial_mouse = true;
-                                        send_ok(&game.stream);
-
-                                        game.state = State::GetWalls;
-                                    } else {
-                                        println!("Mesaj invalid: {}", msg);
-                                    }
+                                if data.is_empty() {
+                                    continue;
                                 }
-                                Err(e) => {
-                                    AppError::ReadError(e.to_string()).log();
+                                let message = String::from_utf8_lossy(data);
+                                println!("START! {}", message);
+                                game.stream.as_ref().unwrap().flush().expect("Err flush");
+                                if let Err(e) = game.stream.as_ref().unwrap().set_nonblocking(false)
+                                {
+                                    AppError::StreamUnavailable(e.to_string()).log();
                                 }
+
+                                game.screen = Screen::GameBoard;
+                                send_ok(&game.stream);
+                                game.state = State::GetTurn;
+                            }
+                            Err(e) if e.kind() == ErrorKind::WouldBlock => {}
+                            Err(e) => {
+                                AppError::ReadError(e.to_string()).log();
                             }
                         }
-                        State::GetWalls => {
-                            let mut buffer = [0; 20];
-                            let result = game.stream.as_ref().unwrap().read(&mut buffer);
-                            match result {
-                                Ok(n) if n > 0 => {
-                                    game.board[buffer[0] as usize][buffer[1] as usize] = 2;
-                                    game.board[buffer[2] as usize][buffer[3] as usize] = 2;
-                                    game.board[buffer[4] as usize][buffer[5] as usize] = 2;
-                                    game.board[buffer[6] as usize][buffer[7] as usize] = 2;
-                                    game.board[buffer[8] as usize][buffer[9] as usize] = 2;
-
-                                    game.board[buffer[10] as usize][buffer[11] as usize] = 2;
-                                    game.board[buffer[12] as usize][buffer[13] as usize] = 2;
-                                    game.board[buffer[14] as usize][buffer[15] as usize] = 2;
-                                    game.board[buffer[16] as usize][buffer[17] as usize] = 2;
-                                    game.board[buffer[18] as usize][buffer[19] as usize] = 2;
-                                    game.state = State::Play;
-                                }
-                                Ok(0) => {
-                                    game.screen = Screen::ConnectingError;
-                                    return;
-                                }
-                                Ok(_) => {}
-                                Err(e) => {
-                                    AppError::ReadError(e.to_string()).log();
+                    }
+                    State::GetTurn => {
+                        let mut buffer = [0; 1024];
+                        match game.stream.as_ref().unwrap().read(&mut buffer) {
+                            Ok(0) => {
+                                game.screen = Screen::ConnectingError;
+                                break;
+                            }
+                            Ok(n) => {
+                                game.player_text =
+                                    Some(String::from_utf8_lossy(&buffer[..n]).to_string());
+                                if game.player_text.as_ref().unwrap().contains("soarecele") {
+                                    game.player = 1;
+                                } else {
+                                    game.player = 2;
+                                    game.has_to_read = true;
                                 }
+                                send_ok(&game.stream);
+                                game.state = State::GetMouse;
+                                println!("Player {}", game.player_text.as_ref().unwrap());
+                            }
+                            Err(e) if e.kind() == ErrorKind::WouldBlock => {}
+                            Err(e) => {
+                                AppError::ReadError(e.to_string()).log();
                             }
-                            send_ok(&game.stream);
                         }
-                        State::Play => {
-                            let mut buffer: [u8; 2] = [0; 2];
-                            match game.stream.as_ref().unwrap().set_nonblocking(true) {
-                                Ok(_) => {}
-                                Err(e) => {
-                                    AppError::StreamUnavailable(e.to_string()).log();
-                                }
+                    }
+                    State::GetMouse => {
+                        let mut buffer = [0; 1024];
+
+                        let mut row = 0;
+                        let mut col = 0;
+                        match game.stream.as_ref().unwrap().read(&mut buffer) {
+                            Ok(0) => {
+                                game.screen = Screen::ConnectingError;
+                                break;
                             }
-                            match game.stream.as_ref().unwrap().read(&mut buffer) {
-                                Ok(n) if n > 0 => {
-                                    let nr1 = buffer[0];
-                                    let nr2 = buffer[1];
-                                    println!("Mutare primită: {}, {}", nr1, nr2);
-                                    if buffer[0] == b'w' && buffer[1] == b'i' {
-                                        let mut buffer = [0; 2];
-                                        buffer[0] = b'w';
-                                        buffer[1] = b'i';
-                                        match game.stream.as_ref().unwrap().write(&buffer) {
-                                            Ok(_) => {}
-                                            Err(e) => {
-                                                AppError::WriteError(e.to_string()).log();
-                                            }
-                                        }
-                                        game.win_state = String::from("AI CASTIGAT!");
-                                        game.win = true;
-                                        game.state = State::Finished;
-                                    } else if buffer[0] == b'l' && buffer[1] == b'o' {
-                                        let mut buffer = [0; 2];
-                                        buffer[0] = b'l';
-                                        buffer[1] = b'o';
-                                        match game.stream.as_ref().unwrap().write(&buffer) {
-                                            Ok(_) => {}
-                                            Err(e) => {
-                                                AppError::WriteError(e.to_string()).log();
-                                            }
-                                        }
-                                        game.win_state = String::from("AI PIERDUT!");
-                                        game.win = true;
-                                        game.state = State::Finished;
-                                    } else if buffer[0] == b'y' && buffer[1] == b'e' {
-                                        game.win = true;
-                                        game.win_state = String::from(
-                                            "          AI CASTIGAT \n PLAYERUL S-A DECONECTAT",
-                                        );
-                                        game.state = State::Finished;
-                                    } else if game.player == 2 {
-                                        let temp = Position::new_from_pos(&game.mouse);
-                                        game.board[temp.x][temp.y] = 0;
-                                        game.board[nr1 as usize][nr2 as usize] = 1;
-                                        game.mouse = Position::new(nr1 as usize, nr2 as usize);
-                                    } else {
-                                        game.board[nr1 as usize][nr2 as usize] = 2;
+                            Ok(n) => {
+                                let msg = String::from_utf8_lossy(&buffer[..n]);
+                                let parts: Vec<&str> = msg.split(',').collect();
+                                if parts.len() >= 2 {
+                                    if let Ok(r) = parts[0].parse::<usize>() {
+                                        row = r;
                                     }
-
-                                    game.has_to_read = false;
-                                    if game.player == 1
-                                        && !check_any_left_move(&game.board, &game.mouse)
-                                    {
-                                        let mut buffer = [0; 2];
-                                        buffer[0] = b'l';
-                                        buffer[1] = b'o';
-                                        if let Err(e) = game.stream.as_ref().unwrap().write(&buffer)
-                                        {
-                                            AppError::WriteError(e.to_string()).log();
-                                        }
-                                        game.win = true;
-                                        game.win_state = String::from("AI PIERDUT!");
-                                        game.state = State::Finished;
-                                        // break;
+                                    if let Ok(c) = parts[1].parse::<usize>() {
+                                        col = c;
                                     }
-                                }
-                                Ok(0) => {
-                                    game.win = true;
-                                    game.win_state = String::from(
-                                        "          AI CASTIGAT \n PLAYERUL S-A DECONECTAT",
-                                    );
-                                    game.state = State::Finished;
-                                }
-                                Ok(_) => {}
-                                Err(ref e) if e.kind() == ErrorKind::WouldBlock => {
-                                    // thread::sleep(Duration::from_millis(100));
-                                }
-                                Err(e) => {
-                                    AppError::ReadError(e.to_string()).log();
+                                    game.mouse = Position::new(row, col);
+                                    game.board[row][col] = 1;
+                                    println!("Mouse initial la: {}, {}", row, col);
+                                    game.initial_mouse = true;
+                                    send_ok(&game.stream);
+
+                                    game.state = State::GetWalls;
+                                } else {
+                                    println!("Mesaj invalid: {}", msg);
                                 }
                             }
+                            Err(e) => {
+                                AppError::ReadError(e.to_string()).log();
+                            }
                         }
-                        State::Finished => {}
-                        State::PlayAgain => match TcpStream::connect("127.0.0.1:8080") {
-                            Ok(stream) => {
-                                game.stream = Some(stream);
-                                game.state = State::WaitTryConnect;
-                                game.screen = Screen::MainMenu;
-                                println!("Conectare dinou reusita");
+                    }
+                    State::GetWalls => {
+                        let mut buffer = [0; 1024];
+                        let result = game.stream.as_ref().unwrap().read(&mut buffer);
+                        match result {
+                            Ok(n) if n > 0 => {
+                                for wall in buffer[..n].chunks_exact(2) {
+                                    game.board[wall[0] as usize][wall[1] as usize] = 2;
+                                }
+                                game.record = Some(GameRecord::new(game.board, game.mouse, 1));
+                                game.reset_heartbeat();
+                                game.state = State::Play;
                             }
+                            Ok(0) => {
+                                game.screen = Screen::ConnectingError;
+                                return;
+                            }
+                            Ok(_) => {}
                             Err(e) => {
-                                AppError::ConnectionError(e.to_string()).log();
-                                break;
+                                AppError::ReadError(e.to_string()).log();
                             }
-                        },
+                        }
+                        send_ok(&game.stream);
+                    }
+                    State::Play => game.read_stream(),
+                    State::Reconnect => {}
+                    State::Spectate => game.read_spectator(),
+                    State::PlayComputer => game.play_computer(),
+                    State::Finished if game.stream.is_some() && game.spectating => {
+                        game.read_spectator()
+                    }
+                    State::Finished if game.stream.is_some() => game.read_stream(),
+                    State::Finished => {}
+                    State::PlayAgain => {
+                        if let Some(stream) = game.stream.take() {
+                            let _ = stream.shutdown(std::net::Shutdown::Both);
+                        }
+                        game.state = State::WaitTryConnect;
+                        game.screen = if game.tournament.view.is_some() {
+                            Screen::Tournament
+                        } else {
+                            Screen::MainMenu
+                        };
+                    }
+                }
+
+                game.read_lobby();
+                game.poll_hint();
+                game.tournament.read();
+                game.join_tournament_game();
+                drop(game);
+            }
+            thread::sleep(Duration::from_millis(100));
+        });
+    }
+
+    fn read_stream(&mut self) {
+        if let Err(e) = self.stream.as_ref().unwrap().set_nonblocking(true) {
+            AppError::StreamUnavailable(e.to_string()).log();
+        }
+        let mut buffer = [0; 1024];
+        match self.stream.as_ref().unwrap().read(&mut buffer) {
+            Ok(0) => self.lose_connection(),
+            Ok(n) => {
+                self.last_seen = Instant::now();
+                self.inbox.extend_from_slice(&buffer[..n]);
+                self.read_messages();
+            }
+            Err(ref e) if e.kind() == ErrorKind::WouldBlock => {}
+            Err(e) => {
+                AppError::ReadError(e.to_string()).log();
+                self.lose_connection();
+            }
+        }
+        if self.stream.is_some() && matches!(self.state, State::Play | State::Finished) {
+            self.heartbeat();
+        }
+    }
+
+    fn read_spectator(&mut self) {
+        if let Err(e) = self.stream.as_ref().unwrap().set_nonblocking(true) {
+            AppError::StreamUnavailable(e.to_string()).log();
+        }
+        let mut buffer = [0; 1024];
+        match self.stream.as_ref().unwrap().read(&mut buffer) {
+            Ok(0) => self.lose_connection(),
+            Ok(_) if self.inbox.is_empty() && buffer[0] == b'-' => {
+                self.disconnect(String::from(
+                    "Nu exista niciun joc in desfasurare in aceasta camera",
+                ));
+            }
+            Ok(n) => {
+                self.inbox.extend_from_slice(&buffer[..n]);
+                self.read_messages();
+            }
+            Err(ref e) if e.kind() == ErrorKind::WouldBlock => {}
+            Err(e) => {
+                AppError::ReadError(e.to_string()).log();
+                self.lose_connection();
+            }
+        }
+    }
+
+    fn read_messages(&mut self) {
+        loop {
+            match Message::parse(&self.inbox) {
+                Ok(Some((message, used))) => {
+                    self.inbox.drain(..used);
+                    if self.spectating {
+                        self.handle_spectator_message(message);
+                    } else {
+                        self.handle_message(message);
                     }
+                }
+                Ok(None) => break,
+                Err(e) => {
+                    e.log();
+                    self.inbox.clear();
+                    break;
+                }
+            }
+        }
+    }
 
-                    drop(game);
+    fn read_lobby(&mut self) {
+        let mut buffer = [0; 1024];
+        let result = match self.lobby_stream.as_ref() {
+            Some(stream) => (&*stream).read(&mut buffer),
+            None => return,
+        };
+        let error = match result {
+            Ok(0) => Some(AppError::ConnectionError(String::from(
+                "Conexiunea la chat s-a pierdut",
+            ))),
+            Ok(n) => {
+                self.lobby_inbox.extend_from_slice(&buffer[..n]);
+                loop {
+                    match Message::parse(&self.lobby_inbox) {
+                        Ok(Some((message, used))) => {
+                            self.lobby_inbox.drain(..used);
+                            match message {
+                                Message::Chat { from, text } => self.lobby_chat.push(from, text),
+                                Message::Ping(seq) => self.send_lobby(&Message::Pong(seq)),
+                                _ => {}
+                            }
+                        }
+                        Ok(None) => break None,
+                        Err(e) => break Some(e),
+                    }
                 }
-                thread::sleep(Duration::from_millis(100));
             }
+            Err(ref e) if e.kind() == ErrorKind::WouldBlock => None,
+            Err(e) => Some(AppError::ReadError(e.to_string())),
+        };
+        if let Some(error) = error {
+            self.lobby_chat.status = Some(error.log().to_string());
+            self.lobby_stream = None;
+            self.lobby_inbox.clear();
+            self.lobby_retry = Instant::now() + LOBBY_RETRY;
+        }
+    }
+
+    // Intra singur in partida de turneu cand jucatorul asteapta pe ecranul turneului.
+    fn join_tournament_game(&mut self) {
+        if !matches!(self.screen, Screen::Tournament)
+            || !matches!(self.state, State::WaitTryConnect)
+            || self.stream.is_some()
+        {
+            return;
+        }
+        if let Some((room, ticket)) = self.tournament.assigned.take() {
+            self.play_tournament_game(room, ticket);
+        }
+    }
+
+    pub fn play_tournament_game(&mut self, room: i8, ticket: String) {
+        println!("Partida de turneu in camera {}", room);
+        self.room_input = room.to_string();
+        self.ticket = ticket;
+        self.room_options = RoomOptions::default();
+        self.tournament.playing = true;
+        self.try_connect = true;
+        self.state = State::Connect;
+    }
+
+    pub fn send_lobby_chat(&mut self, text: String) {
+        self.send_lobby(&Message::Chat {
+            from: String::new(),
+            text,
+        });
+    }
+
+    fn send_lobby(&mut self, message: &Message) {
+        if let Some(stream) = self.lobby_stream.as_ref() {
+            if let Err(e) = (&*stream).write_all(&message.to_bytes()) {
+                self.lobby_chat.status =
+                    Some(AppError::WriteError(e.to_string()).log().to_string());
+            }
+        }
+    }
+
+    pub fn can_take_back(&self) -> bool {
+        if self.offline {
+            return !self.history.is_empty();
+        }
+        !self.win && !self.spectating && self.stream.is_some()
+    }
+
+    // Contra calculatorului anularea e imediata; online trebuie acceptata de adversar.
+    pub fn request_takeback(&mut self) {
+        if !self.offline {
+            self.send_message(&Message::Takeback(true));
+            self.takeback = Takeback::Requested;
+            return;
+        }
+        let Some((board, mouse)) = self.history.pop() else {
+            return;
+        };
+        self.ai_search = None;
+        self.board = board;
+        self.mouse = Position::new(mouse.0, mouse.1);
+        if let Some(record) = self.record.as_mut() {
+            record.rewind_to(&board, self.player as u8);
+        }
+        self.has_to_read = false;
+        self.win = false;
+        self.win_state.clear();
+        self.state = State::PlayComputer;
+    }
+
+    // Sugestiile sunt doar pentru jocurile contra calculatorului.
+    pub fn can_hint(&self) -> bool {
+        self.offline && !self.win && !self.has_to_read && self.hint_search.is_none()
+    }
+
+    pub fn request_hint(&mut self) {
+        if !self.can_hint() || self.hint().is_some() {
+            return;
+        }
+        self.hint_search = Some((self.board, self.search(self.player as u8)));
+    }
+
+    // Pe tabla mica muta tabelul de finaluri, altfel calculatorul ales in meniu.
+    fn search(&self, role: u8) -> Search {
+        let board = self.board;
+        let mouse = self.mouse;
+        let table = self.tablebase.clone().filter(|_| self.size < BOARD_SIZE);
+        let level = self.level;
+        thread::spawn(move || match table {
+            Some(table) => table.best_move(&board, &mouse, role),
+            None if level == Level::Mcts => {
+                let threads = thread::available_parallelism().map_or(1, |n| n.get().min(4));
+                Mcts::new(Budget::time(MCTS_TIME), threads).choose(&board, &mouse, role)
+            }
+            None => computer_move(&board, &mouse, role),
+        })
+    }
+
+    // Sugestia e valabila doar pentru tabla pe care a fost calculata.
+    pub fn hint(&self) -> Option<(usize, usize)> {
+        self.hint
+            .filter(|(board, _)| *board == self.board && !self.has_to_read)
+            .map(|(_, cell)| cell)
+    }
+
+    fn poll_hint(&mut self) {
+        let Some((board, search)) = self.hint_search.take() else {
+            return;
+        };
+        if !search.is_finished() {
+            self.hint_search = Some((board, search));
+            return;
+        }
+        match search.join() {
+            Ok(Some(cell)) => self.hint = Some((board, cell)),
+            Ok(None) => {}
+            Err(_) => {
+                AppError::UnexpectedResponse(String::from("Cautarea sugestiei a esuat")).log();
+            }
+        }
+    }
+
+    pub fn can_analyse(&self) -> bool {
+        self.size == BOARD_SIZE
+            && self.variant.is_classic()
+            && self
+                .record
+                .as_ref()
+                .is_some_and(|record| !record.moves.is_empty())
+    }
+
+    pub fn analyse(&mut self) {
+        if let Some(record) = &self.record {
+            self.analysis = Some(Analysis::new(record, self.player as u8));
+            self.screen = Screen::Analysis;
+        }
+    }
+
+    pub fn answer_takeback(&mut self, accepted: bool) {
+        self.send_message(&Message::Takeback(accepted));
+        self.takeback = Takeback::None;
+    }
+
+    pub fn request_rematch(&mut self) {
+        self.send_message(&Message::Rematch(true));
+        self.rematch = Rematch::Requested;
+    }
+
+    pub fn send_chat(&mut self, text: String) {
+        self.send_message(&Message::Chat {
+            from: String::new(),
+            text,
         });
     }
+
+    fn handle_message(&mut self, message: Message) {
+        if self.win
+            && matches!(
+                message,
+                Message::Move(..) | Message::Win | Message::Lose | Message::Left
+            )
+        {
+            return;
+        }
+        match message {
+            Message::Win => {
+                self.send_message(&Message::Win);
+                if self.clock(3 - self.player) == Some(Duration::ZERO) {
+                    self.finish_with(String::from(
+                        "          AI CASTIGAT! \n ADVERSARULUI I-A EXPIRAT TIMPUL",
+                    ));
+                } else {
+                    self.finish(true);
+                }
+            }
+            Message::Lose => {
+                self.send_message(&Message::Lose);
+                if self.clock(self.player) == Some(Duration::ZERO) {
+                    self.finish_with(String::from("AI PIERDUT! \n TIMPUL A EXPIRAT"));
+                } else {
+                    self.finish(false);
+                }
+            }
+            Message::Clock(remaining, turn) => self.set_clocks(remaining, turn),
+            Message::Name(role, name) => self.set_name(role, name),
+            Message::Rules(variant, walls_left) => self.set_rules(variant, walls_left),
+            Message::Over(_)
+            | Message::Tournament(_)
+            | Message::Assigned(..)
+            | Message::StartTournament => {}
+            Message::Chat { from, text } => self.chat.push(from, text),
+            Message::Score(score) => self.series = Some(score),
+            Message::Match(state) => self.match_state = Some(state),
+            Message::Takeback(true) => self.takeback = Takeback::Offered,
+            Message::Takeback(false) => {
+                self.takeback = match self.takeback {
+                    Takeback::Requested => Takeback::Declined,
+                    _ => Takeback::None,
+                }
+            }
+            Message::Rematch(true) => self.rematch = Rematch::Offered,
+            Message::Rematch(false) => self.rematch = Rematch::Declined,
+            Message::Left => {
+                self.finish_with(String::from(
+                    "          AI CASTIGAT \n PLAYERUL S-A DECONECTAT",
+                ));
+            }
+            Message::Ok => {}
+            Message::Ping(seq) => self.send_message(&Message::Pong(seq)),
+            Message::Pong(seq) => {
+                if let Some((sent_seq, sent_at)) = self.ping_sent {
+                    if sent_seq == seq {
+                        self.rtt = Some(sent_at.elapsed());
+                        self.missed_pings = 0;
+                        self.ping_sent = None;
+                    }
+                }
+            }
+            Message::Waiting => self.opponent_waiting = true,
+            Message::Back => self.opponent_waiting = false,
+            Message::Token(token) => self.resume_token = Some(token),
+            Message::Snapshot(snapshot) => {
+                println!("Jocul a fost reluat");
+                self.board = snapshot.board;
+                self.mouse = Position::new(snapshot.mouse.0 as usize, snapshot.mouse.1 as usize);
+                self.player = snapshot.player as i32;
+                self.player_text = Some(String::from(if self.player == 1 {
+                    "Tu esti soarecele !"
+                } else {
+                    "Tu pui zidurile!"
+                }));
+                self.has_to_read = snapshot.turn != snapshot.player;
+                if !self
+                    .record
+                    .as_mut()
+                    .is_some_and(|record| record.rewind_to(&snapshot.board, snapshot.turn))
+                {
+                    self.record = Some(GameRecord::new(self.board, self.mouse, snapshot.turn));
+                }
+                self.initial_mouse = true;
+                self.win = false;
+                self.win_state.clear();
+                self.rematch = Rematch::None;
+                self.takeback = Takeback::None;
+                if self.match_state.is_some_and(|state| state.finished) {
+                    self.match_state = None;
+                }
+                self.state = State::Play;
+                self.awaiting_snapshot = false;
+                self.reconnecting_since = None;
+                self.opponent_waiting = false;
+            }
+            Message::Move(row, col) => {
+                println!("Mutare primită: {}, {}", row, col);
+                if let Some(record) = self.record.as_mut() {
+                    record.push((row as usize, col as usize));
+                }
+                self.has_to_read = false;
+                if self.player == 2 {
+                    let temp = Position::new_from_pos(&self.mouse);
+                    self.board[temp.x][temp.y] = 0;
+                    self.board[row as usize][col as usize] = 1;
+                    self.mouse = Position::new(row as usize, col as usize);
+                    self.walls_placed = 0;
+                } else {
+                    self.board[row as usize][col as usize] = 2;
+                    // Soarecele asteapta pana vin toate zidurile turei.
+                    self.walls_placed += 1;
+                    if self.walls_placed < self.variant.walls_per_turn() {
+                        self.has_to_read = true;
+                    } else {
+                        self.walls_placed = 0;
+                    }
+                }
+
+                if self.player == 1 && self.variant.is_trapped(&self.board, &self.mouse) {
+                    self.send_message(&Message::Lose);
+                    self.finish(false);
+                }
+            }
+        }
+    }
+
+    fn handle_spectator_message(&mut self, message: Message) {
+        match message {
+            Message::Name(role, name) => self.set_name(role, name),
+            Message::Rules(variant, walls_left) => self.set_rules(variant, walls_left),
+            Message::Snapshot(snapshot) => {
+                self.board = snapshot.board;
+                self.mouse = Position::new(snapshot.mouse.0 as usize, snapshot.mouse.1 as usize);
+                self.turn = snapshot.turn as i32;
+                self.initial_mouse = true;
+                self.win = false;
+                self.win_state.clear();
+                if self.match_state.is_some_and(|state| state.finished) {
+                    self.match_state = None;
+                }
+                self.state = State::Spectate;
+            }
+            Message::Move(row, col) => {
+                let (row, col) = (row as usize, col as usize);
+                if self.turn == 1 {
+                    self.board[self.mouse.x][self.mouse.y] = 0;
+                    self.board[row][col] = 1;
+                    self.mouse = Position::new(row, col);
+                    self.turn = 2;
+                } else {
+                    self.board[row][col] = 2;
+                    self.walls_placed += 1;
+                    if self.walls_placed >= self.variant.walls_per_turn() {
+                        self.walls_placed = 0;
+                        self.turn = 1;
+                    }
+                }
+            }
+            Message::Clock(remaining, turn) => self.set_clocks(remaining, turn),
+            Message::Chat { from, text } => self.chat.push(from, text),
+            Message::Score(score) => self.series = Some(score),
+            Message::Match(state) => self.match_state = Some(state),
+            Message::Over(winner) => {
+                let text = match winner {
+                    1 => format!("A CASTIGAT SOARECELE \n {}", self.names[0]),
+                    2 => format!("AU CASTIGAT ZIDURILE \n {}", self.names[1]),
+                    _ => String::from("JOC ABANDONAT"),
+                };
+                self.finish_with(text);
+            }
+            message => println!("Mesaj ignorat: {:?}", message),
+        }
+    }
+
+    fn set_clocks(&mut self, remaining: [u32; 2], turn: u8) {
+        self.clocks = Some(remaining.map(|ms| Duration::from_millis(ms as u64)));
+        self.clock_turn = turn as i32;
+        self.clock_received = Instant::now();
+    }
+
+    fn set_rules(&mut self, variant: Variant, walls_left: u8) {
+        self.variant = variant;
+        self.walls_placed = variant.walls_per_turn().saturating_sub(walls_left);
+    }
+
+    fn set_name(&mut self, role: u8, name: String) {
+        if let Some(slot) = self.names.get_mut((role as usize).wrapping_sub(1)) {
+            *slot = name;
+        }
+    }
+
+    fn reset_heartbeat(&mut self) {
+        self.last_seen = Instant::now();
+        self.next_ping = Instant::now();
+        self.ping_sent = None;
+        self.missed_pings = 0;
+        self.rtt = None;
+    }
+
+    fn heartbeat(&mut self) {
+        let interval = self.server.heartbeat_interval;
+        if let Some((_, sent_at)) = self.ping_sent {
+            if sent_at.elapsed() > interval {
+                self.missed_pings += 1;
+                self.ping_sent = None;
+            }
+        }
+        if Instant::now() >= self.next_ping && self.ping_sent.is_none() {
+            self.ping_seq = self.ping_seq.wrapping_add(1);
+            self.send_message(&Message::Ping(self.ping_seq));
+            self.ping_sent = Some((self.ping_seq, Instant::now()));
+            self.next_ping = Instant::now() + interval;
+        }
+        if self.last_seen.elapsed() > self.server.heartbeat_timeout {
+            println!("Serverul nu mai raspunde");
+            self.lose_connection();
+        }
+    }
+
+    pub fn room_options(&self) -> Result<RoomOptions, AppError> {
+        Ok(RoomOptions {
+            time_control: self.time_control()?,
+            games: parse_games(&self.match_games)?,
+            variant: self.rules,
+        })
+    }
+
+    fn time_control(&self) -> Result<TimeControl, AppError> {
+        let spec = match self.time_mode {
+            1 => format!("mutare:{}", self.time_seconds.trim()),
+            2 => format!(
+                "banca:{}+{}",
+                self.time_seconds.trim(),
+                self.time_increment.trim()
+            ),
+            _ => String::from("nelimitat"),
+        };
+        TimeControl::parse(&spec)
+    }
+
+    // Timpul ramas pentru rolul dat (1 soarecele, 2 zidurile).
+    pub fn clock(&self, role: i32) -> Option<Duration> {
+        let remaining = self.clocks?[(role - 1) as usize];
+        if role == self.clock_turn
+            && !self.win
+            && matches!(self.state, State::Play | State::Spectate)
+        {
+            Some(remaining.saturating_sub(self.clock_received.elapsed()))
+        } else {
+            Some(remaining)
+        }
+    }
+
+    pub fn connection_quality(&self) -> ConnectionQuality {
+        match (self.missed_pings, self.rtt) {
+            (missed, _) if missed >= 2 => ConnectionQuality::Lost,
+            (1, _) => ConnectionQuality::Poor,
+            (_, Some(rtt)) if rtt >= Duration::from_millis(300) => ConnectionQuality::Poor,
+            (_, Some(rtt)) if rtt >= Duration::from_millis(100) => ConnectionQuality::Fair,
+            _ => ConnectionQuality::Good,
+        }
+    }
+
+    fn send_message(&self, message: &Message) {
+        if let Some(stream) = self.stream.as_ref() {
+            if let Err(e) = (&*stream).write_all(&message.to_bytes()) {
+                AppError::WriteError(e.to_string()).log();
+            }
+        }
+    }
+
+    fn lose_connection(&mut self) {
+        self.stream = None;
+        self.inbox.clear();
+        if self.win {
+            self.rematch = Rematch::Declined;
+            return;
+        }
+        if self.resume_token.is_some() && !self.awaiting_snapshot && !self.win {
+            println!("Conexiunea s-a pierdut, incercam sa reluam jocul");
+            self.reconnecting_since.get_or_insert_with(Instant::now);
+            self.state = State::Reconnect;
+        } else {
+            self.finish_with(String::from("CONEXIUNEA CU SERVERUL S-A PIERDUT"));
+        }
+    }
+
+    fn disconnect(&mut self, reason: String) {
+        println!("{}", reason);
+        self.connect_error = Some(reason);
+        self.screen = Screen::ConnectingError;
+        self.stream = None;
+        self.try_connect = false;
+        self.spectating = false;
+        self.state = State::WaitTryConnect;
+    }
+
+    pub fn start_computer(&mut self, player: i32) {
+        match self
+            .tablebase
+            .clone()
+            .filter(|_| self.level == Level::Perfect)
+        {
+            // Pe tabla mica ziduri cam cat pe cea mare, proportional cu numarul de celule.
+            Some(table) => {
+                (self.board, self.mouse) = table.balanced_start(table.size * table.size / 5);
+                self.size = table.size;
+            }
+            None => {
+                let (row, col) = generate_mouse_pos();
+                let walls = generate_wall_positions((row as u8, col as u8), INITIAL_WALLS);
+                self.board = new_board((row, col), &walls);
+                self.mouse = Position::new(row, col);
+                self.size = BOARD_SIZE;
+            }
+        }
+        self.initial_mouse = true;
+        self.player = player;
+        self.player_text = Some(String::from(if player == 1 {
+            "Tu esti soarecele !"
+        } else {
+            "Tu pui zidurile!"
+        }));
+        self.has_to_read = player == 2;
+        self.win = false;
+        self.win_state = String::new();
+        self.offline = true;
+        self.variant = Variant::default();
+        self.walls_placed = 0;
+        self.ai_search = None;
+        self.history.clear();
+        self.record = Some(GameRecord::new(self.board, self.mouse, 1));
+        self.screen = Screen::GameBoard;
+        self.state = State::PlayComputer;
+    }
+
+    fn play_computer(&mut self) {
+        if self.win || !self.has_to_read {
+            return;
+        }
+
+        let search = match self.ai_search.take() {
+            None => {
+                self.ai_search = Some(self.search(3 - self.player as u8));
+                return;
+            }
+            Some(search) if !search.is_finished() => {
+                self.ai_search = Some(search);
+                return;
+            }
+            Some(search) => search,
+        };
+
+        let computer_move = match search.join() {
+            Ok(computer_move) => computer_move,
+            Err(_) => {
+                AppError::UnexpectedResponse(String::from("Cautarea calculatorului a esuat")).log();
+                None
+            }
+        };
+
+        if let (Some(record), Some(cell)) = (self.record.as_mut(), computer_move) {
+            record.push(cell);
+        }
+        match computer_move {
+            Some((row, col)) if self.player == 2 => {
+                self.board[self.mouse.x][self.mouse.y] = 0;
+                self.board[row][col] = 1;
+                self.mouse = Position::new(row, col);
+                if is_border_on(row, col, self.size) {
+                    self.finish(false);
+                }
+            }
+            Some((row, col)) => {
+                self.board[row][col] = 2;
+                if !check_any_left_move(&self.board, &self.mouse) {
+                    self.finish(false);
+                }
+            }
+            None => self.finish(self.player == 2),
+        }
+        self.has_to_read = false;
+    }
+
+    fn finish(&mut self, won: bool) {
+        self.finish_with(String::from(if won {
+            "AI CASTIGAT!"
+        } else {
+            "AI PIERDUT!"
+        }));
+    }
+
+    fn finish_with(&mut self, win_state: String) {
+        self.win_state = win_state;
+        self.win = true;
+        self.resume_token = None;
+        self.reconnecting_since = None;
+        self.opponent_waiting = false;
+        self.state = State::Finished;
+    }
+}
+
+pub fn connect(address: &str) -> Result<TcpStream, AppError> {
+    let addresses = address
+        .to_socket_addrs()
+        .map_err(|e| AppError::ConnectionError(format!("{}: {}", address, e)))?;
+
+    let mut last_error = AppError::ConnectionError(format!("{}: adresa nu exista", address));
+    for socket_address in addresses {
+        match TcpStream::connect_timeout(&socket_address, CONNECT_TIMEOUT) {
+            Ok(stream) => {
+                println!("Conectat la {}", address);
+                return Ok(stream);
+            }
+            Err(e) => last_error = AppError::ConnectionError(format!("{}: {}", address, e)),
+        }
+    }
+    Err(last_error)
+}
+
+fn join_lobby(address: &str, name: &str) -> Result<TcpStream, AppError> {
+    let mut stream = connect(address)?;
+    stream
+        .write_all(format!("lobby {}", name).as_bytes())
+        .map_err(|e| AppError::WriteError(e.to_string()))?;
+    stream
+        .set_nonblocking(true)
+        .map_err(|e| AppError::StreamUnavailable(e.to_string()))?;
+    Ok(stream)
+}
+
+fn resume_game(address: &str, token: &str) -> Result<TcpStream, AppError> {
+    let mut stream = connect(address)?;
+    stream
+        .write_all(format!("resume {}", token).as_bytes())
+        .map_err(|e| AppError::WriteError(e.to_string()))?;
+    Ok(stream)
 }
 
 pub fn check_move(
@@ -362,73 +1274,27 @@ pub fn check_move(
     c: usize,
     mouse: &Position,
     player: u8,
-    board: &[[u8; 11]; 11],
+    board: &Board,
+    variant: &Variant,
 ) -> bool {
     if player == 2 {
         return board[r][c] == 0;
     }
-    if board[r][c] != 0 {
-        return false;
-    }
-    let mut valid_moves: Vec<(i32, i32)> = Vec::new();
-    let mouse_x = mouse.x as i32;
-    let mouse_y = mouse.y as i32;
-    let r = r as i32;
-    let c = c as i32;
-    if mouse_x % 2 == 0 {
-        valid_moves.push((mouse_x - 1, mouse_y - 1));
-        valid_moves.push((mouse_x - 1, mouse_y));
-        valid_moves.push((mouse_x, mouse_y - 1));
-        valid_moves.push((mouse_x, mouse_y + 1));
-        valid_moves.push((mouse_x + 1, mouse_y - 1));
-        valid_moves.push((mouse_x + 1, mouse_y));
-    } else {
-        valid_moves.push((mouse_x - 1, mouse_y));
-        valid_moves.push((mouse_x - 1, mouse_y + 1));
-        valid_moves.push((mouse_x, mouse_y - 1));
-        valid_moves.push((mouse_x, mouse_y + 1));
-        valid_moves.push((mouse_x + 1, mouse_y));
-        valid_moves.push((mouse_x + 1, mouse_y + 1));
-    }
-    if valid_moves.iter().any(|&(x, y)| x == r && y == c) {
+    if variant.mouse_moves(board, mouse).contains(&(r, c)) {
         return true;
     }
     println!("Mutarea {}, {} este invalidă!", r, c);
     false
 }
 
-pub fn check_any_left_move(board: &[[u8; 11]; 11], mouse: &Position) -> bool {
-    let mut valid_moves: Vec<(i32, i32)> = Vec::new();
-    let mouse_x = mouse.x as i32;
-    let mouse_y = mouse.y as i32;
-
-    if mouse_x % 2 == 0 {
-        valid_moves.push((mouse_x - 1, mouse_y - 1));
-        valid_moves.push((mouse_x - 1, mouse_y));
-        valid_moves.push((mouse_x, mouse_y - 1));
-        valid_moves.push((mouse_x, mouse_y + 1));
-        valid_moves.push((mouse_x + 1, mouse_y - 1));
-        valid_moves.push((mouse_x + 1, mouse_y));
-    } else {
-        valid_moves.push((mouse_x - 1, mouse_y));
-        valid_moves.push((mouse_x - 1, mouse_y + 1));
-        valid_moves.push((mouse_x, mouse_y - 1));
-        valid_moves.push((mouse_x, mouse_y + 1));
-        valid_moves.push((mouse_x + 1, mouse_y));
-        valid_moves.push((mouse_x + 1, mouse_y + 1));
+pub fn send_move(game: &mut Game, row: usize, col: usize) {
+    if game.offline {
+        game.history
+            .push((game.board, (game.mouse.x, game.mouse.y)));
     }
-
-    let mut ok = false;
-    for pos in valid_moves {
-        if pos.0 >= 0 && pos.1 <= 10 && board[pos.0 as usize][pos.1 as usize] == 0 {
-            ok = true;
-            break;
-        }
+    if let Some(record) = game.record.as_mut() {
+        record.push((row, col));
     }
-    ok
-}
-
-pub fn send_move(game: &mut Game, row: usize, col: usize) {
     if game.player == 1 {
         game.board[game.mouse.x][game.mouse.y] = 0;
         game.board[row][col] = 1;
@@ -437,14 +1303,25 @@ pub fn send_move(game: &mut Game, row: usize, col: usize) {
     } else {
         game.board[row][col] = 2;
     }
-    let mut buffer: [u8; 2] = [0; 2];
-    if (game.player == 1) && (row == 0 || row == 10 || col == 0 || col == 10) {
-        buffer[0] = b'w';
-        buffer[1] = b'i';
-    } else {
-        buffer[0] = row as u8;
-        buffer[1] = col as u8;
+    if game.offline {
+        let won = if game.player == 1 {
+            is_border_on(row, col, game.size)
+        } else {
+            !check_any_left_move(&game.board, &game.mouse)
+        };
+        if won {
+            game.finish(true);
+        }
+        return;
+    }
+    if game.player == 2 {
+        game.walls_placed += 1;
+        if game.walls_placed >= game.variant.walls_per_turn() {
+            game.walls_placed = 0;
+        }
     }
+    // Si iesirea merge ca mutare: serverul vede celula aleasa si anunta el castigul.
+    let buffer = [row as u8, col as u8];
     match game.stream.as_ref().unwrap().write_all(&buffer) {
         Ok(_) => {}
         Err(e) => {
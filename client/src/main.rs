@@ -1,9 +1,24 @@
-use common::{AppError, Position};
-use eframe::egui::{self, Pos2, Rect, RichText, Shape, Stroke, TextureOptions, Vec2};
+use common::{
+    ai::escape_routes,
+    clock::format_clock,
+    protocol::MAX_NAME_LEN,
+    rules::{Variant, BOARD_SIZE, GATE, HOLE},
+    tablebase::Tablebase,
+    AppError, Position,
+};
+use eframe::egui::{self, Pos2, RichText, TextureOptions, Vec2};
 use image::{load_from_memory_with_format, ImageFormat};
 use std::sync::{Arc, RwLock};
+mod analysis;
+mod board;
+mod chat;
 mod game;
-use game::{check_move, send_move, Game, Screen, State};
+mod settings;
+mod tournament;
+use game::{
+    check_move, send_move, ConnectionQuality, Game, Level, Rematch, Screen, State, Takeback,
+};
+use tournament::Action;
 
 struct GameApp {
     game: Arc<RwLock<Game>>,
@@ -13,6 +28,16 @@ impl eframe::App for GameApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         let mut game = self.game.write().unwrap();
 
+        if matches!(game.screen, Screen::MainMenu | Screen::WaitingForPlayers) {
+            let can_send = game.lobby_stream.is_some();
+            if let Some(text) = game
+                .lobby_chat
+                .show(ctx, "lobby_chat", "Chat comun", can_send)
+            {
+                game.send_lobby_chat(text);
+            }
+        }
+
         match game.screen {
             Screen::MainMenu => {
                 egui::CentralPanel::default().show(ctx, |ui| {
@@ -68,6 +93,58 @@ impl eframe::App for GameApp {
                                                     .font(egui::TextStyle::Heading),
                                             );
                                             ui.add_space(10.0);
+                                            ui.label(RichText::new("Timp:").heading());
+                                            ui.horizontal(|ui| {
+                                                ui.radio_value(&mut game.time_mode, 0, "Nelimitat");
+                                                ui.radio_value(&mut game.time_mode, 1, "Pe mutare");
+                                                ui.radio_value(&mut game.time_mode, 2, "Banca");
+                                            });
+                                            if game.time_mode != 0 {
+                                                ui.horizontal(|ui| {
+                                                    ui.label("Secunde:");
+                                                    ui.add_sized(
+                                                        Vec2::new(60.0, 20.0),
+                                                        egui::TextEdit::singleline(
+                                                            &mut game.time_seconds,
+                                                        ),
+                                                    );
+                                                    if game.time_mode == 2 {
+                                                        ui.label("Increment:");
+                                                        ui.add_sized(
+                                                            Vec2::new(40.0, 20.0),
+                                                            egui::TextEdit::singleline(
+                                                                &mut game.time_increment,
+                                                            ),
+                                                        );
+                                                    }
+                                                });
+                                            }
+                                            ui.label(RichText::new("Reguli:").heading());
+                                            ui.checkbox(
+                                                &mut game.rules.double_walls,
+                                                "Doua ziduri pe tura",
+                                            );
+                                            ui.checkbox(
+                                                &mut game.rules.long_jump,
+                                                "Soarecele sare doua celule",
+                                            );
+                                            ui.checkbox(&mut game.rules.holes, "Gauri");
+                                            ui.checkbox(&mut game.rules.gate, "O singura poarta");
+                                            ui.horizontal(|ui| {
+                                                ui.label("Jocuri in meci:");
+                                                ui.add_sized(
+                                                    Vec2::new(40.0, 20.0),
+                                                    egui::TextEdit::singleline(
+                                                        &mut game.match_games,
+                                                    ),
+                                                );
+                                            });
+                                            if let Some(error) = &game.time_error {
+                                                ui.label(
+                                                    RichText::new(error).color(egui::Color32::RED),
+                                                );
+                                            }
+                                            ui.add_space(10.0);
                                             if ui
                                                 .add_sized(
                                                     Vec2::new(280.0, 50.0),
@@ -77,13 +154,20 @@ impl eframe::App for GameApp {
                                                 )
                                                 .clicked()
                                             {
-                                                println!(
-                                                    "Intrat in camera cu numarul: {}",
-                                                    game.room_input
-                                                );
-                                                game.show_input_1 = false;
-                                                game.try_connect = true;
-                                                game.state = State::Connect;
+                                                match game.room_options() {
+                                                    Ok(options) => {
+                                                        println!(
+                                                            "Intrat in camera cu numarul: {}",
+                                                            game.room_input
+                                                        );
+                                                        game.room_options = options;
+                                                        game.time_error = None;
+                                                        game.show_input_1 = false;
+                                                        game.try_connect = true;
+                                                        game.state = State::Connect;
+                                                    }
+                                                    Err(e) => game.time_error = Some(e.to_string()),
+                                                }
                                             }
                                         });
                                     });
@@ -136,6 +220,51 @@ impl eframe::App for GameApp {
                                         });
                                     });
                                 }
+
+                                ui.add_space(10.0);
+
+                                if ui
+                                    .add_sized(
+                                        button_size,
+                                        egui::Button::new(
+                                            RichText::new("Priveste un Joc").size(18.0),
+                                        ),
+                                    )
+                                    .clicked()
+                                {
+                                    game.show_input_4 = true;
+                                    game.show_input_1 = false;
+                                    game.show_input_2 = false;
+                                }
+
+                                if game.show_input_4 {
+                                    ui.add_space(20.0);
+                                    ui.group(|ui| {
+                                        ui.vertical_centered(|ui| {
+                                            ui.label(RichText::new("Numar camera:").heading());
+                                            ui.add_sized(
+                                                Vec2::new(200.0, 30.0),
+                                                egui::TextEdit::singleline(&mut game.room_input)
+                                                    .font(egui::TextStyle::Heading),
+                                            );
+                                            ui.add_space(10.0);
+                                            if ui
+                                                .add_sized(
+                                                    Vec2::new(280.0, 50.0),
+                                                    egui::Button::new(
+                                                        RichText::new("Priveste").size(18.0),
+                                                    ),
+                                                )
+                                                .clicked()
+                                            {
+                                                game.show_input_4 = false;
+                                                game.spectating = true;
+                                                game.try_connect = true;
+                                                game.state = State::Connect;
+                                            }
+                                        });
+                                    });
+                                }
                             });
                         });
 
@@ -156,16 +285,202 @@ impl eframe::App for GameApp {
                                     .clicked()
                                 {
                                     println!("Joaca cu Calculatorul selectata");
-                                    game.room_input = String::from("-2");
-                                    game.try_connect = true;
+                                    game.show_input_3 = true;
+                                }
+
+                                if game.show_input_3 {
+                                    ui.add_space(10.0);
+                                    ui.group(|ui| {
+                                        ui.vertical_centered(|ui| {
+                                            ui.label(RichText::new("Alege rolul:").heading());
+                                            ui.horizontal(|ui| {
+                                                ui.radio_value(
+                                                    &mut game.level,
+                                                    Level::Normal,
+                                                    "Normal",
+                                                );
+                                                ui.radio_value(
+                                                    &mut game.level,
+                                                    Level::Mcts,
+                                                    "Greu (MCTS)",
+                                                );
+                                                if let Some(size) =
+                                                    game.tablebase.as_ref().map(|table| table.size)
+                                                {
+                                                    ui.radio_value(
+                                                        &mut game.level,
+                                                        Level::Perfect,
+                                                        format!(
+                                                            "Perfect (tabla {}x{})",
+                                                            size, size
+                                                        ),
+                                                    );
+                                                }
+                                            });
+                                            ui.add_space(10.0);
+                                            if ui
+                                                .add_sized(
+                                                    Vec2::new(280.0, 50.0),
+                                                    egui::Button::new(
+                                                        RichText::new("Soarecele").size(18.0),
+                                                    ),
+                                                )
+                                                .clicked()
+                                            {
+                                                game.show_input_3 = false;
+                                                game.start_computer(1);
+                                            }
+                                            ui.add_space(10.0);
+                                            if ui
+                                                .add_sized(
+                                                    Vec2::new(280.0, 50.0),
+                                                    egui::Button::new(
+                                                        RichText::new("Zidurile").size(18.0),
+                                                    ),
+                                                )
+                                                .clicked()
+                                            {
+                                                game.show_input_3 = false;
+                                                game.start_computer(2);
+                                            }
+                                        });
+                                    });
                                 }
                             });
                         });
 
+                        ui.add_space(10.0);
+                        if ui
+                            .add_sized(
+                                Vec2::new(300.0, 40.0),
+                                egui::Button::new(RichText::new("Turnee").size(18.0)),
+                            )
+                            .clicked()
+                        {
+                            game.screen = Screen::Tournament;
+                        }
+
+                        ui.add_space(30.0);
+                        ui.label(format!(
+                            "Server: {}:{}",
+                            game.server.host.trim(),
+                            game.server.port.trim()
+                        ));
+                        if ui
+                            .add_sized(
+                                Vec2::new(300.0, 40.0),
+                                egui::Button::new(RichText::new("Setari").size(18.0)),
+                            )
+                            .clicked()
+                        {
+                            game.screen = Screen::Settings;
+                        }
+                        ui.add_space(20.0);
+                    });
+                });
+            }
+            Screen::Settings => {
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    ui.vertical_centered(|ui| {
                         ui.add_space(50.0);
+                        ui.label(RichText::new("Setari").size(32.0));
+                        ui.add_space(30.0);
+
+                        ui.group(|ui| {
+                            ui.set_max_width(300.0);
+                            ui.vertical_centered(|ui| {
+                                ui.label(RichText::new("Adresa server:").heading());
+                                ui.add_sized(
+                                    Vec2::new(280.0, 30.0),
+                                    egui::TextEdit::singleline(&mut game.server.host)
+                                        .font(egui::TextStyle::Heading),
+                                );
+                                ui.add_space(10.0);
+                                ui.label(RichText::new("Port:").heading());
+                                ui.add_sized(
+                                    Vec2::new(280.0, 30.0),
+                                    egui::TextEdit::singleline(&mut game.server.port)
+                                        .font(egui::TextStyle::Heading),
+                                );
+                                ui.add_space(10.0);
+                                ui.label(RichText::new("Nume jucator:").heading());
+                                ui.add_sized(
+                                    Vec2::new(280.0, 30.0),
+                                    egui::TextEdit::singleline(&mut game.name)
+                                        .char_limit(MAX_NAME_LEN)
+                                        .font(egui::TextStyle::Heading),
+                                );
+                            });
+                        });
+
+                        if !game.server.recent.is_empty() {
+                            ui.add_space(20.0);
+                            ui.label(RichText::new("Servere recente:").heading());
+                            let mut selected = None;
+                            for server in game.server.recent.iter() {
+                                if ui
+                                    .add_sized(
+                                        Vec2::new(300.0, 30.0),
+                                        egui::Button::new(RichText::new(server).size(16.0)),
+                                    )
+                                    .clicked()
+                                {
+                                    selected = Some(server.clone());
+                                }
+                            }
+                            if let Some(server) = selected {
+                                game.server.select(&server);
+                            }
+                        }
+
+                        ui.add_space(20.0);
+                        match game.server.address() {
+                            Ok(_) => {
+                                if ui
+                                    .add_sized(
+                                        Vec2::new(300.0, 50.0),
+                                        egui::Button::new(RichText::new("Inapoi").size(18.0)),
+                                    )
+                                    .clicked()
+                                {
+                                    game.screen = Screen::MainMenu;
+                                }
+                            }
+                            Err(e) => {
+                                ui.label(RichText::new(e.to_string()).color(egui::Color32::RED));
+                            }
+                        }
+                    });
+                });
+            }
+            Screen::Tournament => {
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    egui::ScrollArea::vertical().show(ui, |ui| {
+                        ui.vertical_centered(|ui| {
+                            let name = game.name.clone();
+                            match game.tournament.show(ui, &name) {
+                                Some(Action::Play(room, ticket)) => {
+                                    game.play_tournament_game(room, ticket)
+                                }
+                                Some(Action::Back) => game.screen = Screen::MainMenu,
+                                None => {}
+                            }
+                        });
                     });
                 });
             }
+            Screen::Analysis => {
+                let game = &mut *game;
+                let back = match game.analysis.as_mut() {
+                    Some(analysis) => {
+                        analysis.show(ctx, game.mouse_texture.as_ref(), &mut game.show_escape)
+                    }
+                    None => true,
+                };
+                if back {
+                    game.screen = Screen::GameBoard;
+                }
+            }
             Screen::WaitingForPlayers => {
                 egui::CentralPanel::default().show(ctx, |ui| {
                     ui.vertical_centered(|ui| {
@@ -183,10 +498,35 @@ impl eframe::App for GameApp {
                 egui::CentralPanel::default().show(ctx, |ui| {
                     ui.vertical_centered(|ui| {
                         ui.label(RichText::new("Eroare la conectare!").size(24.0));
+                        if let Some(connect_error) = &game.connect_error {
+                            ui.label(RichText::new(connect_error).size(16.0));
+                        }
+                        ui.add_space(20.0);
+                        if ui
+                            .add_sized(
+                                Vec2::new(300.0, 50.0),
+                                egui::Button::new(RichText::new("Inapoi").size(18.0)),
+                            )
+                            .clicked()
+                        {
+                            game.tournament.playing = false;
+                            game.screen = if game.tournament.view.is_some() {
+                                Screen::Tournament
+                            } else {
+                                Screen::MainMenu
+                            };
+                        }
                     });
                 });
             }
-            Screen::GameBoard => {
+            Screen::GameBoard | Screen::Spectate => {
+                let spectating = matches!(game.screen, Screen::Spectate);
+                if !game.offline {
+                    let can_send = !spectating && game.stream.is_some();
+                    if let Some(text) = game.chat.show(ctx, "game_chat", "Chat", can_send) {
+                        game.send_chat(text);
+                    }
+                }
                 if game.mouse_texture.is_none() {
                     let image_data = include_bytes!("mouse.png");
 
@@ -209,101 +549,263 @@ impl eframe::App for GameApp {
                 egui::CentralPanel::default().show(ctx, |ui| {
                     ui.vertical_centered(|ui| {
                         ui.add_space(30.0);
-                        ui.label(RichText::new("Jocul a inceput...").size(24.0));
+                        if spectating {
+                            ui.label(
+                                RichText::new(format!(
+                                    "Privesti camera {}",
+                                    game.room_input.trim()
+                                ))
+                                .size(24.0),
+                            );
+                            let series = game
+                                .series
+                                .map(|score| format!("   (scor {} - {})", score[0], score[1]))
+                                .unwrap_or_default();
+                            ui.label(
+                                RichText::new(format!(
+                                    "Soarecele: {}   Zidurile: {}{}",
+                                    game.names[0], game.names[1], series
+                                ))
+                                .size(18.0),
+                            );
+                        } else {
+                            ui.label(RichText::new("Jocul a inceput...").size(24.0));
+                        }
+                        if let Some(state) = &game.match_state {
+                            let text = if spectating {
+                                format!(
+                                    "Meci: jocul {}/{}   {} {} - {} {}",
+                                    (state.game + 1).min(state.games),
+                                    state.games,
+                                    game.names[0],
+                                    state.score[0],
+                                    state.score[1],
+                                    game.names[1]
+                                )
+                            } else {
+                                format!(
+                                    "Meci: jocul {}/{}   Scor {} - {}",
+                                    (state.game + 1).min(state.games),
+                                    state.games,
+                                    state.score[0],
+                                    state.score[1]
+                                )
+                            };
+                            ui.label(RichText::new(text).size(16.0));
+                        }
 
-                        if game.player_text.is_some() {
-                            ui.label(RichText::new(game.player_text.as_ref().unwrap()).size(18.0));
+                        if let Some(player_text) = &game.player_text {
+                            ui.label(RichText::new(player_text).size(18.0));
+                        }
+                        let opponent = &game.names[(2 - game.player).clamp(0, 1) as usize];
+                        if !spectating && !game.offline && !opponent.is_empty() {
+                            let series = game
+                                .series
+                                .map(|score| format!("   Scor serie: {} - {}", score[0], score[1]))
+                                .unwrap_or_default();
+                            ui.label(
+                                RichText::new(format!("Adversar: {}{}", opponent, series))
+                                    .size(14.0),
+                            );
+                        }
+
+                        if !game.offline && matches!(game.state, State::Play) {
+                            let (text, color) = match game.connection_quality() {
+                                ConnectionQuality::Good => {
+                                    ("Buna", egui::Color32::from_rgb(0, 150, 0))
+                                }
+                                ConnectionQuality::Fair => {
+                                    ("Medie", egui::Color32::from_rgb(200, 150, 0))
+                                }
+                                ConnectionQuality::Poor => ("Slaba", egui::Color32::RED),
+                                ConnectionQuality::Lost => ("Fara raspuns", egui::Color32::RED),
+                            };
+                            let rtt = game
+                                .rtt
+                                .map(|rtt| format!(" ({} ms)", rtt.as_millis()))
+                                .unwrap_or_default();
+                            ui.label(
+                                RichText::new(format!("Conexiune: {}{}", text, rtt))
+                                    .size(14.0)
+                                    .color(color),
+                            );
                         }
 
-                        if game.has_to_read && !game.win {
+                        if matches!(game.state, State::Reconnect) {
+                            ui.label(
+                                RichText::new("Conexiune pierduta, reconectare...")
+                                    .size(18.0)
+                                    .color(egui::Color32::RED),
+                            );
+                        } else if game.opponent_waiting {
+                            ui.label(
+                                RichText::new("Asteptam reconectarea adversarului...").size(18.0),
+                            );
+                        } else if spectating && !game.win {
+                            let turn = if game.turn == 1 {
+                                "soarecelui"
+                            } else {
+                                "zidurilor"
+                            };
+                            ui.label(RichText::new(format!("Este randul {}", turn)).size(18.0));
+                        } else if game.has_to_read && !game.win {
                             ui.label(RichText::new("Asteptati tura..").size(18.0));
                         }
+
+                        if !spectating && !game.win {
+                            match game.takeback {
+                                Takeback::Offered => {
+                                    ui.label(
+                                        RichText::new("Adversarul vrea sa-si anuleze mutarea")
+                                            .size(16.0),
+                                    );
+                                    ui.horizontal(|ui| {
+                                        if ui.button("Accepta").clicked() {
+                                            game.answer_takeback(true);
+                                        }
+                                        if ui.button("Refuza").clicked() {
+                                            game.answer_takeback(false);
+                                        }
+                                    });
+                                }
+                                Takeback::Requested => {
+                                    ui.label(
+                                        RichText::new("Asteptam raspunsul la anulare...")
+                                            .size(16.0),
+                                    );
+                                }
+                                Takeback::None | Takeback::Declined => {
+                                    if matches!(game.takeback, Takeback::Declined) {
+                                        ui.label(
+                                            RichText::new("Anularea a fost refuzata")
+                                                .size(14.0)
+                                                .color(egui::Color32::RED),
+                                        );
+                                    }
+                                    if game.can_take_back()
+                                        && ui.button("Anuleaza mutarea").clicked()
+                                    {
+                                        game.request_takeback();
+                                    }
+                                }
+                            }
+                        }
+
+                        // Drumul de scapare ar ajuta in partidele online, deci il aratam doar la antrenament.
+                        if (game.offline || spectating) && game.size == BOARD_SIZE {
+                            ui.checkbox(&mut game.show_escape, "Arata drumul de scapare");
+                        }
+                        if !spectating {
+                            ui.checkbox(&mut game.show_moves, "Arata mutarile posibile");
+                            if game.offline {
+                                if game.hint_search.is_some() {
+                                    ui.label("Calculam sugestia...");
+                                } else if ui
+                                    .add_enabled(game.can_hint(), egui::Button::new("Sugestie"))
+                                    .clicked()
+                                {
+                                    game.request_hint();
+                                }
+                            }
+                        }
                     });
 
                     ui.vertical(|ui| {
                         ui.add_space(10.0);
 
-                        let hex_radius = 23.0;
-                        let hex_height = 2.0 * hex_radius;
-                        let hex_width = (3.0_f32.sqrt()) * hex_radius;
-                        let horizontal_spacing = hex_width * 1.10;
-                        let vertical_spacing = hex_height * 0.85;
-
                         let painter = ui.painter();
-                        let start_x = 160.0;
-                        let start_y = 140.0;
-
-                        for row in 0..11 {
-                            for col in 0..11 {
-                                let x = start_x + col as f32 * horizontal_spacing;
-                                let y = start_y + row as f32 * vertical_spacing;
-
-                                let x = if row % 2 == 1 { x + hex_width * 0.5 } else { x };
+                        let start_y = board::START_Y;
 
-                                let hex_points = hexagon_points(x, y, hex_radius);
-
-                                if game.board[row][col] == 1 {
-                                    if let Some(texture) = &game.mouse_texture {
-                                        let rect = Rect::from_center_size(
-                                            Pos2::new(x, y),
-                                            Vec2::new(hex_radius * 1.5, hex_radius * 1.5),
-                                        );
+                        for (role, name) in [(1, "Soarece"), (2, "Ziduri")] {
+                            if let Some(remaining) = game.clock(role) {
+                                let color = if remaining < std::time::Duration::from_secs(10) {
+                                    egui::Color32::RED
+                                } else if role == game.clock_turn {
+                                    egui::Color32::BLACK
+                                } else {
+                                    egui::Color32::GRAY
+                                };
+                                painter.text(
+                                    Pos2::new(15.0, start_y + (role - 1) as f32 * 60.0),
+                                    egui::Align2::LEFT_TOP,
+                                    format!("{}\n{}", name, format_clock(remaining)),
+                                    egui::FontId::proportional(20.0),
+                                    color,
+                                );
+                            }
+                        }
 
-                                        painter.image(
-                                            texture.id(),
-                                            rect,
-                                            Rect::from_min_max(
-                                                Pos2::new(0.0, 0.0),
-                                                Pos2::new(1.0, 1.0),
-                                            ),
-                                            egui::Color32::WHITE,
-                                        );
+                        let my_turn = !spectating
+                            && !game.win
+                            && !game.has_to_read
+                            && (game.offline || game.stream.is_some());
+                        let legal = if game.show_moves && my_turn {
+                            game.variant
+                                .legal_moves(&game.board, &game.mouse, game.player as u8)
+                        } else {
+                            Vec::new()
+                        };
+                        let hint = game.hint();
 
-                                        painter.add(Shape::convex_polygon(
-                                            hex_points.clone(),
-                                            egui::Color32::TRANSPARENT,
-                                            Stroke::new(2.0, egui::Color32::BLACK),
-                                        ));
-                                    }
+                        for row in 0..game.size {
+                            for col in 0..game.size {
+                                let center = board::cell_center(row, col);
+                                let fill = if legal.contains(&(row, col)) {
+                                    board::LEGAL
+                                } else if game.board[row][col] == 0 {
+                                    board::FREE
+                                } else if game.board[row][col] == HOLE {
+                                    board::HOLE
+                                } else if game.board[row][col] == GATE {
+                                    board::GATE
                                 } else {
-                                    let color = if game.board[row][col] == 0 {
-                                        egui::Color32::from_rgb(100, 200, 100)
-                                    } else {
-                                        egui::Color32::from_rgb(255, 51, 0)
-                                    };
-
-                                    painter.add(Shape::convex_polygon(
-                                        hex_points.clone(),
-                                        color,
-                                        Stroke::new(2.0, egui::Color32::BLACK),
-                                    ));
+                                    board::WALL
+                                };
+                                board::paint_cell(
+                                    painter,
+                                    game.mouse_texture.as_ref(),
+                                    center,
+                                    game.board[row][col],
+                                    fill,
+                                );
+                                if hint == Some((row, col)) {
+                                    board::outline_cell(painter, center, board::SUGGESTED);
                                 }
 
                                 let response = ui.interact(
-                                    egui::Rect::from_center_size(
-                                        egui::Pos2::new(x, y),
-                                        egui::Vec2::new(hex_width, hex_height),
-                                    ),
+                                    egui::Rect::from_center_size(center, board::cell_size()),
                                     egui::Id::new(format!("hexagon_{}_{}", row, col)),
                                     egui::Sense::click(),
                                 );
 
                                 if response.clicked()
+                                    && !spectating
                                     && !game.has_to_read
+                                    && (game.offline || game.stream.is_some())
+                                    && !game.awaiting_snapshot
                                     && check_move(
                                         row,
                                         col,
                                         &game.mouse,
                                         game.player as u8,
                                         &game.board,
+                                        &game.variant,
                                     )
-                                    && game.board[row][col] == 0
                                 {
                                     send_move(&mut game, row, col);
-                                    game.has_to_read = true;
+                                    // Cu doua ziduri pe tura randul ramane pana la al doilea.
+                                    game.has_to_read = game.walls_placed == 0;
                                 }
                             }
                         }
+
+                        if game.show_escape
+                            && (game.offline || spectating)
+                            && game.size == BOARD_SIZE
+                        {
+                            let routes = escape_routes(&game.board, game.mouse.x, game.mouse.y);
+                            board::draw_escape_routes(painter, routes.as_ref());
+                        }
                     });
                     if game.win {
                         let screen_rect = ctx.screen_rect();
@@ -332,6 +834,102 @@ impl eframe::App for GameApp {
                             .show(ctx, |ui| {
                                 let button_size = egui::Vec2::new(300.0, 50.0);
 
+                                let match_running =
+                                    game.match_state.is_some_and(|state| !state.finished);
+                                if let Some(state) = game.match_state.filter(|state| state.finished)
+                                {
+                                    let result = match (spectating, state.winner) {
+                                        (_, 0) => String::from("Meci egal"),
+                                        (false, 1) => String::from("Ai castigat meciul!"),
+                                        (false, _) => String::from("Ai pierdut meciul"),
+                                        (true, winner) => format!(
+                                            "Meciul l-a castigat {}",
+                                            game.names[winner as usize - 1]
+                                        ),
+                                    };
+                                    let tiebreak = if state.tiebreak {
+                                        " (la departajare)"
+                                    } else {
+                                        ""
+                                    };
+                                    ui.label(
+                                        RichText::new(format!(
+                                            "{}{}   {} - {}",
+                                            result, tiebreak, state.score[0], state.score[1]
+                                        ))
+                                        .size(20.0)
+                                        .color(egui::Color32::WHITE),
+                                    );
+                                    ui.add_space(10.0);
+                                }
+                                if match_running && game.stream.is_some() {
+                                    ui.label(
+                                        RichText::new("Urmatorul joc incepe in curand...")
+                                            .size(18.0)
+                                            .color(egui::Color32::WHITE),
+                                    );
+                                    ui.add_space(10.0);
+                                } else if !game.offline && !spectating && !game.tournament.playing {
+                                    let status = match game.rematch {
+                                        Rematch::None => None,
+                                        Rematch::Requested => {
+                                            Some("Asteptam raspunsul adversarului...")
+                                        }
+                                        Rematch::Offered => Some("Adversarul vrea revansa!"),
+                                        Rematch::Declined => Some("Revansa nu mai este posibila"),
+                                    };
+                                    if let Some(status) = status {
+                                        ui.label(
+                                            RichText::new(status)
+                                                .size(18.0)
+                                                .color(egui::Color32::WHITE),
+                                        );
+                                    }
+                                    if matches!(game.rematch, Rematch::None | Rematch::Offered)
+                                        && game.stream.is_some()
+                                        && ui
+                                            .add_sized(
+                                                button_size,
+                                                egui::Button::new(
+                                                    RichText::new("Revansa").size(18.0),
+                                                ),
+                                            )
+                                            .clicked()
+                                    {
+                                        game.request_rematch();
+                                    }
+                                    ui.add_space(10.0);
+                                }
+
+                                if game.can_analyse()
+                                    && ui
+                                        .add_sized(
+                                            button_size,
+                                            egui::Button::new(
+                                                RichText::new("Analizeaza partida").size(18.0),
+                                            ),
+                                        )
+                                        .clicked()
+                                {
+                                    game.analyse();
+                                }
+                                ui.add_space(10.0);
+
+                                if game.offline
+                                    && game.can_take_back()
+                                    && ui
+                                        .add_sized(
+                                            button_size,
+                                            egui::Button::new(
+                                                RichText::new("Anuleaza mutarea").size(18.0),
+                                            ),
+                                        )
+                                        .clicked()
+                                {
+                                    game.request_takeback();
+                                }
+                                ui.add_space(10.0);
+
                                 if ui
                                     .add_sized(
                                         button_size,
@@ -343,7 +941,12 @@ impl eframe::App for GameApp {
                                 {
                                     println!("Play Again!");
 
-                                    game.screen = Screen::MainMenu;
+                                    game.screen = if game.tournament.view.is_some() {
+                                        Screen::Tournament
+                                    } else {
+                                        Screen::MainMenu
+                                    };
+                                    game.tournament.playing = false;
                                     game.show_input_1 = false;
                                     game.show_input_2 = false;
                                     game.room_input = String::from("-1");
@@ -351,12 +954,36 @@ impl eframe::App for GameApp {
                                     game.player = -1;
                                     game.initial_mouse = false;
                                     game.mouse = Position::new(0, 0);
-                                    game.board = [[0; 11]; 11];
+                                    game.board = [[0; BOARD_SIZE]; BOARD_SIZE];
+                                    game.size = BOARD_SIZE;
                                     game.has_to_read = false;
                                     game.win = false;
-                                    game.state = State::PlayAgain;
+                                    game.state = if game.offline {
+                                        State::WaitTryConnect
+                                    } else {
+                                        State::PlayAgain
+                                    };
                                     game.win_state = String::new();
                                     game.try_connect = false;
+                                    game.offline = false;
+                                    game.ai_search = None;
+                                    game.inbox.clear();
+                                    game.resume_token = None;
+                                    game.opponent_waiting = false;
+                                    game.clocks = None;
+                                    game.rematch = Rematch::None;
+                                    game.takeback = Takeback::None;
+                                    game.history.clear();
+                                    game.record = None;
+                                    game.analysis = None;
+                                    game.series = None;
+                                    game.match_state = None;
+                                    game.chat.lines.clear();
+                                    game.spectating = false;
+                                    game.names = [String::new(), String::new()];
+                                    game.turn = 1;
+                                    game.variant = Variant::default();
+                                    game.walls_placed = 0;
                                 }
                             });
                     }
@@ -367,23 +994,25 @@ impl eframe::App for GameApp {
     }
 }
 
-fn hexagon_points(center_x: f32, center_y: f32, radius: f32) -> Vec<Pos2> {
-    (0..6)
-        .map(|i| {
-            let angle = std::f32::consts::PI / 3.0 * i as f32 - std::f32::consts::PI / 6.0;
-            Pos2::new(
-                center_x + radius * angle.cos(),
-                center_y + radius * angle.sin(),
-            )
-        })
-        .collect()
-}
-
 fn main() {
     std::env::set_var("WINIT_UNIX_BACKEND", "x11");
 
     let options = eframe::NativeOptions::default();
-    let game = Arc::new(RwLock::new(Game::default()));
+    let mut game = Game::default();
+    if let Err(e) = game.server.apply_args(std::env::args().skip(1)) {
+        e.log();
+        std::process::exit(2);
+    }
+    if let Some(path) = game.server.tablebase.clone() {
+        match Tablebase::load(&path) {
+            Ok(table) => game.tablebase = Some(Arc::new(table)),
+            Err(e) => {
+                e.log();
+                std::process::exit(2);
+            }
+        }
+    }
+    let game = Arc::new(RwLock::new(game));
     Game::start_tcp(Arc::clone(&game));
     match eframe::run_native(
         "Trap the mouse!",
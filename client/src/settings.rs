@@ -0,0 +1,157 @@
+use common::AppError;
+use std::{fs, path::PathBuf, time::Duration};
+
+const DEFAULT_HOST: &str = "127.0.0.1";
+const DEFAULT_PORT: u16 = 8080;
+const MAX_RECENT_SERVERS: usize = 8;
+const RECENT_SERVERS_FILE: &str = ".trap_the_mouse_servers";
+const DEFAULT_HEARTBEAT_INTERVAL: Duration = Duration::from_secs(2);
+const DEFAULT_HEARTBEAT_TIMEOUT: Duration = Duration::from_secs(10);
+
+const USAGE: &str = "Utilizare: client [--host <adresa>] [--port <port>] [<adresa>:<port>]
+               [--heartbeat-interval <ms>] [--heartbeat-timeout <ms>]
+               [--tablebase <fisier>]";
+
+pub struct ServerSettings {
+    pub host: String,
+    pub port: String,
+    pub recent: Vec<String>,
+    pub heartbeat_interval: Duration,
+    pub heartbeat_timeout: Duration,
+    pub tablebase: Option<PathBuf>,
+}
+
+impl ServerSettings {
+    pub fn load() -> Self {
+        let recent: Vec<String> = fs::read_to_string(recent_servers_path())
+            .map(|contents| {
+                contents
+                    .lines()
+                    .map(|line| line.trim().to_string())
+                    .filter(|line| !line.is_empty())
+                    .take(MAX_RECENT_SERVERS)
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let mut settings = ServerSettings {
+            host: String::from(DEFAULT_HOST),
+            port: DEFAULT_PORT.to_string(),
+            recent,
+            heartbeat_interval: DEFAULT_HEARTBEAT_INTERVAL,
+            heartbeat_timeout: DEFAULT_HEARTBEAT_TIMEOUT,
+            tablebase: None,
+        };
+        if let Some(last) = settings.recent.first().cloned() {
+            settings.select(&last);
+        }
+        settings
+    }
+
+    pub fn apply_args(&mut self, args: impl Iterator<Item = String>) -> Result<(), AppError> {
+        let mut args = args.peekable();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--host" => {
+                    self.host = args.next().ok_or_else(|| {
+                        AppError::ConfigError(format!("--host are nevoie de o adresa\n{}", USAGE))
+                    })?;
+                }
+                "--port" => {
+                    self.port = args.next().ok_or_else(|| {
+                        AppError::ConfigError(format!("--port are nevoie de un numar\n{}", USAGE))
+                    })?;
+                }
+                "--heartbeat-interval" => {
+                    self.heartbeat_interval = parse_millis(&arg, args.next())?;
+                }
+                "--heartbeat-timeout" => {
+                    self.heartbeat_timeout = parse_millis(&arg, args.next())?;
+                }
+                "--tablebase" => {
+                    self.tablebase = Some(args.next().map(PathBuf::from).ok_or_else(|| {
+                        AppError::ConfigError(format!(
+                            "--tablebase are nevoie de un fisier\n{}",
+                            USAGE
+                        ))
+                    })?);
+                }
+                _ if !arg.starts_with('-') && arg.contains(':') => self.select(&arg),
+                _ => {
+                    return Err(AppError::ConfigError(format!(
+                        "argument necunoscut '{}'\n{}",
+                        arg, USAGE
+                    )))
+                }
+            }
+        }
+        if self.heartbeat_interval < Duration::from_millis(100) {
+            return Err(AppError::ConfigError(String::from(
+                "--heartbeat-interval trebuie sa fie cel putin 100 ms",
+            )));
+        }
+        if self.heartbeat_timeout < self.heartbeat_interval * 2 {
+            return Err(AppError::ConfigError(String::from(
+                "--heartbeat-timeout trebuie sa fie cel putin dublul intervalului",
+            )));
+        }
+        self.address().map(|_| ())
+    }
+
+    pub fn select(&mut self, address: &str) {
+        if let Some((host, port)) = address.rsplit_once(':') {
+            self.host = host.to_string();
+            self.port = port.to_string();
+        }
+    }
+
+    pub fn address(&self) -> Result<String, AppError> {
+        let host = self.host.trim();
+        if host.is_empty() {
+            return Err(AppError::ConfigError(String::from(
+                "adresa serverului este goala",
+            )));
+        }
+        match self.port.trim().parse::<u16>() {
+            Ok(port) if port > 0 => Ok(format!("{}:{}", host, port)),
+            _ => Err(AppError::ConfigError(format!(
+                "portul '{}' nu este valid (1-65535)",
+                self.port.trim()
+            ))),
+        }
+    }
+
+    pub fn remember(&mut self) {
+        let Ok(address) = self.address() else {
+            return;
+        };
+        self.recent.retain(|server| *server != address);
+        self.recent.insert(0, address);
+        self.recent.truncate(MAX_RECENT_SERVERS);
+
+        if let Err(e) = fs::write(recent_servers_path(), self.recent.join("\n")) {
+            AppError::IOError(e).log();
+        }
+    }
+}
+
+fn parse_millis(flag: &str, value: Option<String>) -> Result<Duration, AppError> {
+    value
+        .as_deref()
+        .and_then(|value| value.parse::<u64>().ok())
+        .map(Duration::from_millis)
+        .ok_or_else(|| {
+            AppError::ConfigError(format!(
+                "{} asteapta un numar de milisecunde\n{}",
+                flag, USAGE
+            ))
+        })
+}
+
+fn recent_servers_path() -> PathBuf {
+    std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .map(PathBuf::from)
+        .unwrap_or_default()
+        .join(RECENT_SERVERS_FILE)
+}
@@ -0,0 +1,420 @@
+use common::{
+    clock::TimeControl,
+    protocol::{parse_games, Message, RoomOptions},
+    tournament::{
+        format_points, Format, Outcome, Status, TournamentRequest, TournamentView, BYE, NO_ROOM,
+    },
+    AppError,
+};
+use eframe::egui::{self, RichText, Vec2};
+use std::{
+    io::{ErrorKind, Read, Write},
+    net::TcpStream,
+};
+
+use crate::game::connect;
+
+pub enum Action {
+    Play(i8, String),
+    Back,
+}
+
+pub struct Tournament {
+    pub stream: Option<TcpStream>,
+    pub inbox: Vec<u8>,
+    pub view: Option<TournamentView>,
+    pub request: Option<TournamentRequest>,
+    pub status: Option<String>,
+    pub assigned: Option<(i8, String)>,
+    // Cheia primita la inscriere si turneul ei, ca sa putem relua locul dupa o deconectare.
+    pub token: Option<(Option<u8>, String)>,
+    pub playing: bool,
+    pub format: Format,
+    pub rounds: String,
+    pub time: String,
+    pub games: String,
+    pub code: String,
+}
+
+impl Default for Tournament {
+    fn default() -> Self {
+        Tournament {
+            stream: None,
+            inbox: Vec::new(),
+            view: None,
+            request: None,
+            status: None,
+            assigned: None,
+            token: None,
+            playing: false,
+            format: Format::RoundRobin,
+            rounds: String::new(),
+            time: String::new(),
+            games: String::from("1"),
+            code: String::new(),
+        }
+    }
+}
+
+impl Tournament {
+    pub fn read(&mut self) {
+        let mut buffer = [0; 1024];
+        let result = match self.stream.as_ref() {
+            Some(stream) => (&*stream).read(&mut buffer),
+            None => return,
+        };
+        let error = match result {
+            Ok(0) => Some(AppError::ConnectionError(String::from(
+                "Conexiunea cu turneul s-a pierdut",
+            ))),
+            Ok(_) if self.view.is_none() && self.inbox.is_empty() && buffer[0] == b'-' => {
+                Some(AppError::ServerError(String::from(
+                    "Turneul nu exista, a inceput deja sau numele este folosit",
+                )))
+            }
+            Ok(n) => {
+                self.inbox.extend_from_slice(&buffer[..n]);
+                loop {
+                    match Message::parse(&self.inbox) {
+                        Ok(Some((message, used))) => {
+                            self.inbox.drain(..used);
+                            self.handle_message(message);
+                        }
+                        Ok(None) => break None,
+                        Err(e) => break Some(e),
+                    }
+                }
+            }
+            Err(ref e) if e.kind() == ErrorKind::WouldBlock => None,
+            Err(e) => Some(AppError::ReadError(e.to_string())),
+        };
+        if let Some(error) = error {
+            self.status = Some(error.log().to_string());
+            self.stream = None;
+            self.inbox.clear();
+        }
+    }
+
+    fn handle_message(&mut self, message: Message) {
+        match message {
+            Message::Tournament(view) => {
+                // Biletul nu mai e bun daca partida lui s-a terminat.
+                let pending = |room: i8| {
+                    view.pairings
+                        .iter()
+                        .any(|pairing| pairing.room == room && pairing.outcome == Outcome::Pending)
+                };
+                if self
+                    .assigned
+                    .as_ref()
+                    .is_some_and(|(room, _)| !pending(*room))
+                {
+                    self.assigned = None;
+                }
+                // Cheia vine inaintea primei stari, cand nu stim inca numarul unui turneu nou.
+                if let Some((tournament @ None, _)) = self.token.as_mut() {
+                    *tournament = Some(view.id);
+                }
+                self.view = Some(view);
+            }
+            Message::Assigned(room, ticket) => self.assigned = Some((room, ticket)),
+            Message::Token(token) => self.token = Some((None, token)),
+            Message::Ping(seq) => self.send(&Message::Pong(seq)),
+            message => println!("Mesaj ignorat in turneu: {:?}", message),
+        }
+    }
+
+    fn request(&self, id: Option<u8>, name: &str) -> Result<TournamentRequest, AppError> {
+        let mut request = TournamentRequest {
+            id,
+            name: name.to_string(),
+            format: self.format,
+            ..TournamentRequest::default()
+        };
+        if let Some((Some(tournament), token)) = &self.token {
+            if id == Some(*tournament) {
+                request.token = token.clone();
+            }
+        }
+        if id.is_none() {
+            let time_control = match self.time.trim() {
+                "" => TimeControl::Unlimited,
+                seconds => TimeControl::parse(&format!("mutare:{}", seconds))?,
+            };
+            request.options = RoomOptions {
+                time_control,
+                games: parse_games(&self.games)?,
+                ..RoomOptions::default()
+            };
+            request.rounds = match self.rounds.trim() {
+                "" => 0,
+                rounds => rounds.parse::<u8>().map_err(|_| {
+                    AppError::ConfigError(format!("numar de runde invalid: '{}'", rounds))
+                })?,
+            };
+        }
+        Ok(request)
+    }
+
+    fn send(&mut self, message: &Message) {
+        if let Some(stream) = self.stream.as_ref() {
+            if let Err(e) = (&*stream).write_all(&message.to_bytes()) {
+                self.status = Some(AppError::WriteError(e.to_string()).log().to_string());
+            }
+        }
+    }
+
+    pub fn leave(&mut self) {
+        if let Some(stream) = self.stream.take() {
+            let _ = stream.shutdown(std::net::Shutdown::Both);
+        }
+        self.inbox.clear();
+        self.view = None;
+        self.assigned = None;
+        self.status = None;
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui, name: &str) -> Option<Action> {
+        ui.add_space(30.0);
+        ui.label(RichText::new("Turnee").size(32.0));
+        ui.add_space(20.0);
+        if let Some(status) = &self.status {
+            ui.label(RichText::new(status).color(egui::Color32::RED));
+            ui.add_space(10.0);
+        }
+
+        let action = match self.view.take() {
+            Some(view) => {
+                let action = self.show_view(ui, &view);
+                if self.view.is_none() && self.stream.is_some() {
+                    self.view = Some(view);
+                }
+                action
+            }
+            None if self.stream.is_some() || self.request.is_some() => {
+                ui.label(RichText::new("Ne conectam la turneu...").size(18.0));
+                None
+            }
+            None => self.show_form(ui, name),
+        };
+
+        ui.add_space(20.0);
+        if ui
+            .add_sized(
+                Vec2::new(300.0, 40.0),
+                egui::Button::new(RichText::new("Inapoi").size(18.0)),
+            )
+            .clicked()
+        {
+            return Some(Action::Back);
+        }
+        action
+    }
+
+    fn show_form(&mut self, ui: &mut egui::Ui, name: &str) -> Option<Action> {
+        ui.group(|ui| {
+            ui.set_max_width(320.0);
+            ui.vertical_centered(|ui| {
+                ui.label(RichText::new("Turneu nou").heading());
+                ui.horizontal(|ui| {
+                    ui.radio_value(&mut self.format, Format::RoundRobin, "Campionat");
+                    ui.radio_value(&mut self.format, Format::Swiss, "Sistem elvetian");
+                });
+                if self.format == Format::Swiss {
+                    ui.horizontal(|ui| {
+                        ui.label("Runde (gol = automat):");
+                        ui.add_sized(
+                            Vec2::new(40.0, 20.0),
+                            egui::TextEdit::singleline(&mut self.rounds),
+                        );
+                    });
+                }
+                ui.horizontal(|ui| {
+                    ui.label("Secunde pe mutare (gol = nelimitat):");
+                    ui.add_sized(
+                        Vec2::new(40.0, 20.0),
+                        egui::TextEdit::singleline(&mut self.time),
+                    );
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Jocuri in meci:");
+                    ui.add_sized(
+                        Vec2::new(40.0, 20.0),
+                        egui::TextEdit::singleline(&mut self.games),
+                    );
+                });
+                if ui
+                    .add_sized(
+                        Vec2::new(280.0, 40.0),
+                        egui::Button::new(RichText::new("Creeaza turneul").size(18.0)),
+                    )
+                    .clicked()
+                {
+                    match self.request(None, name) {
+                        Ok(request) => {
+                            self.status = None;
+                            self.request = Some(request);
+                        }
+                        Err(e) => self.status = Some(e.to_string()),
+                    }
+                }
+            });
+        });
+
+        ui.add_space(20.0);
+        ui.group(|ui| {
+            ui.set_max_width(320.0);
+            ui.vertical_centered(|ui| {
+                ui.label(RichText::new("Cod turneu:").heading());
+                ui.add_sized(
+                    Vec2::new(200.0, 30.0),
+                    egui::TextEdit::singleline(&mut self.code).font(egui::TextStyle::Heading),
+                );
+                if ui
+                    .add_sized(
+                        Vec2::new(280.0, 40.0),
+                        egui::Button::new(RichText::new("Inscrie-te").size(18.0)),
+                    )
+                    .clicked()
+                {
+                    match self.code.trim().parse::<u8>() {
+                        Ok(id) => {
+                            self.status = None;
+                            self.request = self.request(Some(id), name).ok();
+                        }
+                        Err(_) => self.status = Some(String::from("Cod de turneu invalid")),
+                    }
+                }
+            });
+        });
+        None
+    }
+
+    fn show_view(&mut self, ui: &mut egui::Ui, view: &TournamentView) -> Option<Action> {
+        let status = match view.status {
+            Status::Registering => String::from("inscrieri deschise"),
+            Status::Running => format!("runda {}/{}", view.round, view.rounds),
+            Status::Finished => String::from("terminat"),
+        };
+        ui.label(
+            RichText::new(format!(
+                "Turneul {} ({}) - {}",
+                view.id, view.format, status
+            ))
+            .size(20.0),
+        );
+        let games = if view.options.games > 1 {
+            format!(", meci din {} jocuri", view.options.games)
+        } else {
+            String::new()
+        };
+        ui.label(format!("Timp: {}{}", view.options.time_control, games));
+        ui.add_space(10.0);
+
+        let name = |index: u8| match view.players.get(index as usize) {
+            Some(player) => player.name.as_str(),
+            None => "?",
+        };
+        egui::Grid::new("standings")
+            .striped(true)
+            .num_columns(5)
+            .show(ui, |ui| {
+                for title in ["Loc", "Jucator", "Puncte", "Jocuri", "Buchholz"] {
+                    ui.label(RichText::new(title).strong());
+                }
+                ui.end_row();
+                for (place, index) in view.ranking().into_iter().enumerate() {
+                    let player = &view.players[index];
+                    let mut text = RichText::new(&player.name);
+                    if index == view.you as usize {
+                        text = text.strong().color(egui::Color32::from_rgb(0, 120, 200));
+                    }
+                    ui.label((place + 1).to_string());
+                    ui.label(text);
+                    ui.label(format_points(player.points.into()));
+                    ui.label(player.played.to_string());
+                    ui.label(format_points(player.buchholz));
+                    ui.end_row();
+                }
+            });
+
+        if !view.pairings.is_empty() {
+            ui.add_space(10.0);
+            ui.label(RichText::new(format!("Runda {}", view.round)).heading());
+            for pairing in view.pairings.iter() {
+                let text = if pairing.second == BYE {
+                    format!("{} sta runda aceasta (victorie)", name(pairing.first))
+                } else {
+                    let result = match pairing.outcome {
+                        Outcome::Pending if pairing.room == NO_ROOM => {
+                            String::from("asteapta o camera")
+                        }
+                        Outcome::Pending => format!("in camera {}", pairing.room),
+                        Outcome::FirstWon => String::from("1 - 0"),
+                        Outcome::SecondWon => String::from("0 - 1"),
+                        Outcome::Draw => String::from("1/2 - 1/2"),
+                        Outcome::BothLost => String::from("0 - 0"),
+                    };
+                    format!(
+                        "{} - {}: {}",
+                        name(pairing.first),
+                        name(pairing.second),
+                        result
+                    )
+                };
+                ui.label(text);
+            }
+        }
+
+        ui.add_space(20.0);
+        let button_size = Vec2::new(300.0, 40.0);
+        if view.status == Status::Registering && view.you == 0 {
+            let enough = view.players.len() >= 2;
+            if ui
+                .add_enabled(
+                    enough,
+                    egui::Button::new(RichText::new("Porneste turneul").size(18.0))
+                        .min_size(button_size),
+                )
+                .clicked()
+            {
+                self.send(&Message::StartTournament);
+            }
+        }
+        if let Some((room, ticket)) = self.assigned.clone() {
+            if ui
+                .add_sized(
+                    button_size,
+                    egui::Button::new(
+                        RichText::new(format!("Joaca in camera {}", room)).size(18.0),
+                    ),
+                )
+                .clicked()
+            {
+                self.assigned = None;
+                return Some(Action::Play(room, ticket));
+            }
+        }
+        if ui
+            .add_sized(
+                button_size,
+                egui::Button::new(RichText::new("Paraseste turneul").size(18.0)),
+            )
+            .clicked()
+        {
+            self.leave();
+        }
+        None
+    }
+}
+
+pub fn join_tournament(address: &str, request: &TournamentRequest) -> Result<TcpStream, AppError> {
+    let mut stream = connect(address)?;
+    stream
+        .write_all(format!("tournament {}", request.encode()).as_bytes())
+        .map_err(|e| AppError::WriteError(e.to_string()))?;
+    stream
+        .set_nonblocking(true)
+        .map_err(|e| AppError::StreamUnavailable(e.to_string()))?;
+    Ok(stream)
+}
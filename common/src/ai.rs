@@ -0,0 +1,593 @@
+use std::{cmp, collections::VecDeque};
+
+use crate::rules::{
+    apply_move, check_any_left_move, is_border, legal_moves, neighbours, zobrist, zobrist_after,
+    Board, BOARD_SIZE,
+};
+use crate::transposition::{Score, TranspositionTable};
+use crate::Position;
+
+pub fn computer_move(board: &Board, mouse: &Position, role: u8) -> Option<(usize, usize)> {
+    if role == 1 {
+        computer_mouse_move(board, mouse)
+    } else {
+        computer_wall_move(board, mouse)
+    }
+}
+
+pub const WIN_SCORE: i32 = 1000;
+
+// Evaluarea unei pozitii din perspectiva soarecelui, cu `to_move` rolul care urmeaza la mutare.
+pub fn evaluate(board: &Board, mouse: &Position, to_move: u8) -> i32 {
+    if is_border(mouse.x, mouse.y) {
+        return WIN_SCORE;
+    }
+    if !check_any_left_move(board, mouse) {
+        return -WIN_SCORE;
+    }
+    match escape_distance(board, mouse.x, mouse.y) {
+        // Inchis intr-o zona fara iesire: pierde sigur, doar mai tarziu.
+        None => -WIN_SCORE / 2,
+        Some((1, exits)) if to_move == 1 || exits > 1 => WIN_SCORE,
+        Some((distance, exits)) => exits.min(8) as i32 * 5 - distance as i32 * 40,
+    }
+}
+
+pub fn computer_mouse_move(board: &Board, mouse: &Position) -> Option<(usize, usize)> {
+    find_shortest_path_to_border(board, mouse.x, mouse.y)
+}
+
+// Cate ture de ziduri anticipeaza soarecele; fiecare inseamna o mutare si un raspuns.
+pub const LOOKAHEAD_DEPTH: usize = 2;
+// 2^16 intrari, cam un megaoctet si jumatate.
+pub const LOOKAHEAD_TABLE_BITS: u32 = 16;
+const ROUTE_SCORE: i32 = 50;
+const DISTANCE_SCORE: i32 = 40;
+
+// Soarecele care se uita inainte: incearca fiecare mutare impotriva zidurilor care il
+// incurca cel mai tare si prefera pozitiile cu mai multe drumuri independente spre margine.
+// Pozitiile la care se ajunge pe mai multe cai le evaluam o data, prin tabela de transpozitii.
+pub fn lookahead_mouse_move(
+    board: &Board,
+    mouse: &Position,
+    depth: usize,
+    table: &mut TranspositionTable<Score>,
+) -> Option<(usize, usize)> {
+    let mut moves = legal_moves(board, mouse, 1);
+    if let Some(&exit) = moves.iter().find(|&&(x, y)| is_border(x, y)) {
+        return Some(exit);
+    }
+    moves.sort_by_key(|&(x, y)| escape_distance(board, x, y).map_or(usize::MAX, |(d, _)| d));
+    let mut best = None;
+    let mut alpha = -WIN_SCORE - 1;
+    let key = zobrist(board, 1);
+    for cell in moves {
+        let mut next = *board;
+        let mut position = *mouse;
+        let next_key = zobrist_after(key, mouse, 1, cell);
+        apply_move(&mut next, &mut position, 1, cell);
+        let search = Search {
+            board: &next,
+            mouse: &position,
+            key: next_key,
+            depth,
+        };
+        let value = walls_reply(search, alpha, WIN_SCORE + 1, table);
+        if best.is_none() || value > alpha {
+            alpha = value;
+            best = Some(cell);
+        }
+    }
+    best
+}
+
+// Pozitia din cautare, cu cheia ei Zobrist tinuta la zi mutare cu mutare.
+#[derive(Clone, Copy)]
+struct Search<'a> {
+    board: &'a Board,
+    mouse: &'a Position,
+    key: u64,
+    depth: usize,
+}
+
+// Zidurile la mutare; intoarce valoarea pentru soarece (alpha-beta).
+fn walls_reply(
+    search: Search,
+    alpha: i32,
+    beta: i32,
+    table: &mut TranspositionTable<Score>,
+) -> i32 {
+    if let Some(value) = table
+        .get(search.key)
+        .and_then(|score| score.cutoff(search.depth, alpha, beta))
+    {
+        return value;
+    }
+    let Search {
+        board,
+        mouse,
+        key,
+        depth,
+    } = search;
+    let outlook = mouse_outlook(board, mouse);
+    let cells = blocking_cells(board, mouse);
+    let value = if depth == 0 || outlook.abs() >= WIN_SCORE / 2 || cells.is_empty() {
+        outlook
+    } else {
+        let mut value = i32::MAX;
+        let mut window = beta;
+        for cell in cells {
+            let mut next = *board;
+            next[cell.0][cell.1] = 2;
+            let reply = Search {
+                board: &next,
+                mouse,
+                key: zobrist_after(key, mouse, 2, cell),
+                depth: depth - 1,
+            };
+            value = value.min(mouse_reply(reply, alpha, window, table));
+            if value <= alpha {
+                break;
+            }
+            window = window.min(value);
+        }
+        value
+    };
+    table.insert(key, Score::new(value, depth, alpha, beta));
+    value
+}
+
+fn mouse_reply(
+    search: Search,
+    alpha: i32,
+    beta: i32,
+    table: &mut TranspositionTable<Score>,
+) -> i32 {
+    let moves = legal_moves(search.board, search.mouse, 1);
+    if moves.is_empty() {
+        return -WIN_SCORE;
+    }
+    if moves.iter().any(|&(x, y)| is_border(x, y)) {
+        return WIN_SCORE;
+    }
+    if let Some(value) = table
+        .get(search.key)
+        .and_then(|score| score.cutoff(search.depth, alpha, beta))
+    {
+        return value;
+    }
+    let mut value = i32::MIN;
+    let mut window = alpha;
+    for cell in moves {
+        let mut next = *search.board;
+        let mut position = *search.mouse;
+        apply_move(&mut next, &mut position, 1, cell);
+        let reply = Search {
+            board: &next,
+            mouse: &position,
+            key: zobrist_after(search.key, search.mouse, 1, cell),
+            depth: search.depth,
+        };
+        value = value.max(walls_reply(reply, window, beta, table));
+        if value >= beta {
+            break;
+        }
+        window = window.max(value);
+    }
+    table.insert(search.key, Score::new(value, search.depth, alpha, beta));
+    value
+}
+
+// Cat de bine sta soarecele cand urmeaza zidurile: drumurile independente conteaza mai mult
+// decat distanta, ca sa nu intre intr-o fundatura doar pentru ca e mai aproape de margine.
+fn mouse_outlook(board: &Board, mouse: &Position) -> i32 {
+    match escape_distance(board, mouse.x, mouse.y) {
+        None => -WIN_SCORE / 2,
+        Some((1, exits)) if exits > 1 => WIN_SCORE,
+        Some((distance, exits)) => {
+            disjoint_routes(board, mouse) as i32 * ROUTE_SCORE - distance as i32 * DISTANCE_SCORE
+                + exits.min(8) as i32 * 2
+        }
+    }
+}
+
+// Zidurile care merita incercate: langa soarece si pe drumurile cele mai scurte, cele apropiate primele.
+fn blocking_cells(board: &Board, mouse: &Position) -> Vec<(usize, usize)> {
+    let mut cells: Vec<(usize, usize)> = neighbours(mouse.x, mouse.y)
+        .into_iter()
+        .filter(|&(x, y)| board[x][y] == 0)
+        .collect();
+    if let Some(routes) = escape_routes(board, mouse.x, mouse.y) {
+        for (_, cell) in routes.steps {
+            if !cells.contains(&cell) {
+                cells.push(cell);
+            }
+        }
+    }
+    cells.sort_by_key(|&cell| hex_distance((mouse.x, mouse.y), cell));
+    cells
+}
+
+// Cate drumuri spre margine fara celule comune are soarecele, adica cate ziduri mai trebuie
+// puse ca sa fie inchis. Flux maxim in care fiecare celula libera are capacitatea unu.
+pub fn disjoint_routes(board: &Board, mouse: &Position) -> usize {
+    let cells = BOARD_SIZE * BOARD_SIZE;
+    let sink = 2 * cells;
+    let mut graph = FlowGraph::new(sink + 1);
+    for x in 0..BOARD_SIZE {
+        for y in 0..BOARD_SIZE {
+            let cell = x * BOARD_SIZE + y;
+            let free = board[x][y] == 0;
+            if !free && (x, y) != (mouse.x, mouse.y) {
+                continue;
+            }
+            if free {
+                graph.add(2 * cell, 2 * cell + 1);
+            }
+            if is_border(x, y) {
+                if free {
+                    graph.add(2 * cell + 1, sink);
+                }
+                continue;
+            }
+            for (nx, ny) in neighbours(x, y) {
+                if board[nx][ny] == 0 {
+                    graph.add(2 * cell + 1, 2 * (nx * BOARD_SIZE + ny));
+                }
+            }
+        }
+    }
+    let source = 2 * (mouse.x * BOARD_SIZE + mouse.y) + 1;
+    let mut routes = 0;
+    while graph.augment(source, sink) {
+        routes += 1;
+    }
+    routes
+}
+
+// Muchiile vin in perechi: muchia e si inversa ei e ^ 1.
+struct FlowGraph {
+    edges: Vec<Vec<usize>>,
+    to: Vec<usize>,
+    capacity: Vec<u8>,
+}
+
+impl FlowGraph {
+    fn new(nodes: usize) -> Self {
+        FlowGraph {
+            edges: vec![Vec::new(); nodes],
+            to: Vec::new(),
+            capacity: Vec::new(),
+        }
+    }
+
+    fn add(&mut self, from: usize, to: usize) {
+        self.edges[from].push(self.to.len());
+        self.to.push(to);
+        self.capacity.push(1);
+        self.edges[to].push(self.to.len());
+        self.to.push(from);
+        self.capacity.push(0);
+    }
+
+    // Cauta un drum de crestere; intoarce false cand fluxul e deja maxim.
+    fn augment(&mut self, source: usize, sink: usize) -> bool {
+        let mut through = vec![usize::MAX; self.edges.len()];
+        let mut seen = vec![false; self.edges.len()];
+        let mut queue = VecDeque::from([source]);
+        seen[source] = true;
+        while let Some(node) = queue.pop_front() {
+            if node == sink {
+                break;
+            }
+            for &edge in self.edges[node].iter() {
+                let next = self.to[edge];
+                if self.capacity[edge] > 0 && !seen[next] {
+                    seen[next] = true;
+                    through[next] = edge;
+                    queue.push_back(next);
+                }
+            }
+        }
+        if !seen[sink] {
+            return false;
+        }
+        let mut node = sink;
+        while node != source {
+            let edge = through[node];
+            self.capacity[edge] -= 1;
+            self.capacity[edge ^ 1] += 1;
+            node = self.to[edge ^ 1];
+        }
+        true
+    }
+}
+
+pub fn computer_wall_move(board: &Board, mouse: &Position) -> Option<(usize, usize)> {
+    let mut best_cell: Option<(usize, usize)> = None;
+    let mut best_score = (0, 0, 0);
+
+    for row in 0..BOARD_SIZE {
+        for col in 0..BOARD_SIZE {
+            if board[row][col] != 0 {
+                continue;
+            }
+            let mut next = *board;
+            next[row][col] = 2;
+
+            // Zidul bun: tine soarecele cat mai departe de margine, cu cat mai
+            // putine iesiri, si cat mai aproape de el.
+            let score = match escape_distance(&next, mouse.x, mouse.y) {
+                None => (usize::MAX, 0, 0),
+                Some((distance, exits)) => (
+                    distance,
+                    BOARD_SIZE * BOARD_SIZE - exits,
+                    BOARD_SIZE * 2 - hex_distance((mouse.x, mouse.y), (row, col)),
+                ),
+            };
+            if best_cell.is_none() || score > best_score {
+                best_cell = Some((row, col));
+                best_score = score;
+            }
+        }
+    }
+
+    best_cell
+}
+
+pub fn escape_distance(board: &Board, start_x: usize, start_y: usize) -> Option<(usize, usize)> {
+    let mut queue = VecDeque::new();
+    let mut visited = [[false; BOARD_SIZE]; BOARD_SIZE];
+    let mut found: Option<(usize, usize)> = None;
+
+    queue.push_back((start_x, start_y, 0));
+    visited[start_x][start_y] = true;
+
+    while let Some((x, y, distance)) = queue.pop_front() {
+        if let Some((best, _)) = found {
+            if distance > best {
+                break;
+            }
+        }
+        if distance > 0 && is_border(x, y) {
+            found = Some(match found {
+                Some((best, exits)) => (best, exits + 1),
+                None => (distance, 1),
+            });
+            continue;
+        }
+        for (nx, ny) in neighbours(x, y) {
+            if !visited[nx][ny] && board[nx][ny] == 0 {
+                visited[nx][ny] = true;
+                queue.push_back((nx, ny, distance + 1));
+            }
+        }
+    }
+
+    found
+}
+
+// Toate drumurile cele mai scurte spre margine: pasii (de la, la) si distanta lor.
+pub struct EscapeRoutes {
+    pub distance: usize,
+    pub exits: Vec<(usize, usize)>,
+    pub steps: Vec<((usize, usize), (usize, usize))>,
+}
+
+pub fn escape_routes(board: &Board, start_x: usize, start_y: usize) -> Option<EscapeRoutes> {
+    let mut distances = [[usize::MAX; BOARD_SIZE]; BOARD_SIZE];
+    let mut queue = VecDeque::new();
+    distances[start_x][start_y] = 0;
+    queue.push_back((start_x, start_y));
+
+    let mut exits: Vec<(usize, usize)> = Vec::new();
+    while let Some((x, y)) = queue.pop_front() {
+        let distance = distances[x][y];
+        if exits
+            .first()
+            .is_some_and(|&(ex, ey)| distance > distances[ex][ey])
+        {
+            break;
+        }
+        if distance > 0 && is_border(x, y) {
+            exits.push((x, y));
+            continue;
+        }
+        for (nx, ny) in neighbours(x, y) {
+            if distances[nx][ny] == usize::MAX && board[nx][ny] == 0 {
+                distances[nx][ny] = distance + 1;
+                queue.push_back((nx, ny));
+            }
+        }
+    }
+    let &(ex, ey) = exits.first()?;
+    let distance = distances[ex][ey];
+
+    // Mergem inapoi de la iesiri, pastrand doar pasii care scad distanta cu unu.
+    let mut on_route = [[false; BOARD_SIZE]; BOARD_SIZE];
+    let mut steps = Vec::new();
+    let mut frontier = exits.clone();
+    for &(x, y) in frontier.iter() {
+        on_route[x][y] = true;
+    }
+    while !frontier.is_empty() {
+        let mut previous = Vec::new();
+        for &(x, y) in frontier.iter() {
+            for (px, py) in neighbours(x, y) {
+                if distances[px][py] != usize::MAX && distances[px][py] + 1 == distances[x][y] {
+                    steps.push(((px, py), (x, y)));
+                    if !on_route[px][py] {
+                        on_route[px][py] = true;
+                        previous.push((px, py));
+                    }
+                }
+            }
+        }
+        frontier = previous;
+    }
+
+    Some(EscapeRoutes {
+        distance,
+        exits,
+        steps,
+    })
+}
+
+pub fn hex_distance(a: (usize, usize), b: (usize, usize)) -> usize {
+    let to_cube = |(row, col): (usize, usize)| {
+        let q = col as isize - (row as isize - (row as isize & 1)) / 2;
+        let r = row as isize;
+        (q, r, -q - r)
+    };
+    let (aq, ar, as_) = to_cube(a);
+    let (bq, br, bs) = to_cube(b);
+    ((aq - bq).abs() + (ar - br).abs() + (as_ - bs).abs()) as usize / 2
+}
+
+pub fn find_shortest_path_to_border(
+    board: &Board,
+    start_x: usize,
+    start_y: usize,
+) -> Option<(usize, usize)> {
+    let mut queue = VecDeque::new();
+    let mut visited = [[false; BOARD_SIZE]; BOARD_SIZE];
+
+    queue.push_back((start_x, start_y, None));
+    visited[start_x][start_y] = true;
+
+    let distance_to_border = |x: usize, y: usize| -> i32 {
+        let last = (BOARD_SIZE - 1) as i32;
+        cmp::min(
+            cmp::min(x as i32, last - x as i32),
+            cmp::min(y as i32, last - y as i32),
+        )
+    };
+
+    let mut best_candidate: Option<((usize, usize), i32)> = None;
+
+    while let Some((x, y, first_move)) = queue.pop_front() {
+        if is_border(x, y) {
+            if let Some(fm) = first_move {
+                return Some(fm);
+            }
+        }
+
+        if let Some(fm) = first_move {
+            let d = distance_to_border(x, y);
+            if best_candidate.is_none() || d < best_candidate.unwrap().1 {
+                best_candidate = Some((fm, d));
+            }
+        }
+
+        for (nx, ny) in neighbours(x, y) {
+            if !visited[nx][ny] && board[nx][ny] == 0 {
+                visited[nx][ny] = true;
+                let new_first_move = first_move.or(Some((nx, ny)));
+                queue.push_back((nx, ny, new_first_move));
+            }
+        }
+    }
+
+    if let Some((cand, _)) = best_candidate {
+        if cand != (start_x, start_y) {
+            return Some(cand);
+        }
+    }
+
+    neighbours(start_x, start_y)
+        .into_iter()
+        .find(|&(nx, ny)| board[nx][ny] == 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::new_board;
+
+    const MOUSE: (usize, usize) = (5, 3);
+    // Un tunel de o celula latime spre marginea din stanga: cel mai scurt drum, dar un singur
+    // zid pe (5, 1) il transforma in fundatura.
+    const TUNNEL: [(usize, usize); 3] = [(5, 2), (5, 1), (5, 0)];
+
+    fn tunnel_board() -> (Board, Position) {
+        let mut walls = Vec::new();
+        for &(x, y) in TUNNEL[..2].iter() {
+            for cell in neighbours(x, y) {
+                if cell != MOUSE && !TUNNEL.contains(&cell) && !walls.contains(&cell) {
+                    walls.push(cell);
+                }
+            }
+        }
+        let walls: Vec<(u8, u8)> = walls.iter().map(|&(x, y)| (x as u8, y as u8)).collect();
+        (new_board(MOUSE, &walls), Position::new(MOUSE.0, MOUSE.1))
+    }
+
+    fn with_walls(mouse: (usize, usize), walls: &[(usize, usize)]) -> (Board, Position) {
+        let walls: Vec<(u8, u8)> = walls.iter().map(|&(x, y)| (x as u8, y as u8)).collect();
+        (new_board(mouse, &walls), Position::new(mouse.0, mouse.1))
+    }
+
+    #[test]
+    fn lookahead_stays_out_of_the_dead_end() {
+        let (board, mouse) = tunnel_board();
+        // Cautarea simpla intra in tunel, pentru ca e cel mai scurt drum.
+        assert_eq!(computer_mouse_move(&board, &mouse), Some(TUNNEL[0]));
+
+        let mut table = TranspositionTable::new(LOOKAHEAD_TABLE_BITS);
+        let chosen = lookahead_mouse_move(&board, &mouse, LOOKAHEAD_DEPTH, &mut table).unwrap();
+        assert_ne!(chosen, TUNNEL[0]);
+        assert!(legal_moves(&board, &mouse, 1).contains(&chosen));
+    }
+
+    #[test]
+    fn lookahead_takes_an_exit_next_to_the_mouse() {
+        let (board, mouse) = with_walls((5, 1), &[]);
+        let mut table = TranspositionTable::new(LOOKAHEAD_TABLE_BITS);
+        let chosen = lookahead_mouse_move(&board, &mouse, LOOKAHEAD_DEPTH, &mut table).unwrap();
+        assert!(is_border(chosen.0, chosen.1));
+    }
+
+    #[test]
+    fn disjoint_routes_counts_the_walls_still_needed() {
+        let center = (5, 5);
+        let (board, mouse) = with_walls(center, &[]);
+        assert_eq!(disjoint_routes(&board, &mouse), 6);
+
+        let around = neighbours(center.0, center.1);
+        let (board, mouse) = with_walls(center, &around[..2]);
+        assert_eq!(disjoint_routes(&board, &mouse), 4);
+        let (board, mouse) = with_walls(center, &around[1..]);
+        assert_eq!(disjoint_routes(&board, &mouse), 1);
+        let (board, mouse) = with_walls(center, &around);
+        assert_eq!(disjoint_routes(&board, &mouse), 0);
+
+        // Tunelul are o singura celula spre stanga, restul tablei ramane deschis.
+        let (board, mouse) = tunnel_board();
+        let open = neighbours(MOUSE.0, MOUSE.1)
+            .into_iter()
+            .filter(|&(x, y)| board[x][y] == 0)
+            .count();
+        assert_eq!(disjoint_routes(&board, &mouse), open);
+    }
+
+    #[test]
+    fn disjoint_routes_share_a_single_gap() {
+        // Un inel de ziduri in jurul soarecelui, cu o singura celula lipsa: un drum, oricat de larg
+        // ar fi interiorul.
+        let center = (5, 5);
+        let mut ring: Vec<(usize, usize)> = (0..BOARD_SIZE)
+            .flat_map(|x| (0..BOARD_SIZE).map(move |y| (x, y)))
+            .filter(|&cell| hex_distance(center, cell) == 3)
+            .collect();
+        let (board, mouse) = with_walls(center, &ring);
+        assert_eq!(disjoint_routes(&board, &mouse), 0);
+
+        ring.remove(0);
+        let (board, mouse) = with_walls(center, &ring);
+        assert_eq!(disjoint_routes(&board, &mouse), 1);
+
+        ring.remove(ring.len() / 2);
+        let (board, mouse) = with_walls(center, &ring);
+        assert_eq!(disjoint_routes(&board, &mouse), 2);
+    }
+}
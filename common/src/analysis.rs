@@ -0,0 +1,241 @@
+use std::fmt;
+
+use crate::ai::{computer_move, evaluate};
+use crate::rules::{apply_move, Board};
+use crate::Position;
+
+// Mutarile unei partide, pornind de la pozitia initiala; rolurile alterneaza de la `first`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GameRecord {
+    pub board: Board,
+    pub mouse: Position,
+    pub first: u8,
+    pub moves: Vec<(usize, usize)>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Ply {
+    pub board: Board,
+    pub mouse: Position,
+    pub role: u8,
+}
+
+impl GameRecord {
+    pub fn new(board: Board, mouse: Position, first: u8) -> Self {
+        GameRecord {
+            board,
+            mouse,
+            first,
+            moves: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, cell: (usize, usize)) {
+        self.moves.push(cell);
+    }
+
+    // Pozitiile dinaintea fiecarei mutari, plus pozitia finala.
+    pub fn plies(&self) -> Vec<Ply> {
+        let mut ply = Ply {
+            board: self.board,
+            mouse: self.mouse,
+            role: self.first,
+        };
+        let mut plies = vec![ply];
+        for &cell in self.moves.iter() {
+            apply_move(&mut ply.board, &mut ply.mouse, ply.role, cell);
+            ply.role = 3 - ply.role;
+            plies.push(ply);
+        }
+        plies
+    }
+
+    // Dupa o anulare sau o reconectare, taie mutarile de dupa pozitia primita.
+    // Intoarce false daca pozitia nu apare in partida.
+    pub fn rewind_to(&mut self, board: &Board, role: u8) -> bool {
+        let found = self
+            .plies()
+            .iter()
+            .rposition(|ply| ply.board == *board && ply.role == role);
+        match found {
+            Some(index) => {
+                self.moves.truncate(index);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Verdict {
+    Best,
+    Good,
+    Inaccuracy,
+    Blunder,
+}
+
+impl Verdict {
+    fn from_loss(loss: i32) -> Self {
+        match loss {
+            0 => Verdict::Best,
+            1..=20 => Verdict::Good,
+            21..=80 => Verdict::Inaccuracy,
+            _ => Verdict::Blunder,
+        }
+    }
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Verdict::Best => write!(f, "cea mai buna"),
+            Verdict::Good => write!(f, "buna"),
+            Verdict::Inaccuracy => write!(f, "imprecizie"),
+            Verdict::Blunder => write!(f, "greseala"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Review {
+    pub role: u8,
+    pub played: (usize, usize),
+    pub best: Option<(usize, usize)>,
+    pub loss: i32,
+    pub verdict: Verdict,
+}
+
+// Compara fiecare mutare cu cea aleasa de calculator, dupa evaluarea pozitiei rezultate.
+pub fn analyse(record: &GameRecord) -> Vec<Review> {
+    let plies = record.plies();
+    record
+        .moves
+        .iter()
+        .zip(plies.iter())
+        .map(|(&played, ply)| {
+            let score = |cell: (usize, usize)| {
+                let (mut board, mut mouse) = (ply.board, ply.mouse);
+                apply_move(&mut board, &mut mouse, ply.role, cell);
+                let score = evaluate(&board, &mouse, 3 - ply.role);
+                if ply.role == 1 {
+                    score
+                } else {
+                    -score
+                }
+            };
+            let best = computer_move(&ply.board, &ply.mouse, ply.role);
+            let loss = match best {
+                Some(best) if best != played => (score(best) - score(played)).max(0),
+                _ => 0,
+            };
+            Review {
+                role: ply.role,
+                played,
+                best,
+                loss,
+                verdict: Verdict::from_loss(loss),
+            }
+        })
+        .collect()
+}
+
+// Precizia unui rol, in procente: fiecare mutare pierde cat a pierdut fata de calculator, cel mult 100.
+pub fn accuracy(reviews: &[Review], role: u8) -> Option<u8> {
+    let losses: Vec<i32> = reviews
+        .iter()
+        .filter(|review| review.role == role)
+        .map(|review| review.loss.min(100))
+        .collect();
+    if losses.is_empty() {
+        return None;
+    }
+    let total: i32 = losses.iter().map(|loss| 100 - loss).sum();
+    Some((total / losses.len() as i32) as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::{is_border, new_board};
+
+    fn start() -> GameRecord {
+        GameRecord::new(new_board((5, 5), &[(0, 0), (3, 7)]), Position::new(5, 5), 1)
+    }
+
+    #[test]
+    fn rewind_to_a_repeated_position_keeps_the_last_occurrence() {
+        // Un zid pus peste unul existent nu schimba tabla, asa ca pozitia de start revine.
+        let mut record = start();
+        let board = record.board;
+        for cell in [(5, 6), (0, 0), (5, 5), (0, 0)] {
+            record.push(cell);
+        }
+        assert_eq!(record.plies()[4].board, board);
+
+        assert!(record.rewind_to(&board, 1));
+        assert_eq!(record.moves.len(), 4);
+    }
+
+    #[test]
+    fn rewind_to_an_unknown_position_changes_nothing() {
+        let mut record = start();
+        for cell in [(5, 6), (4, 4), (5, 7)] {
+            record.push(cell);
+        }
+        let mut other = record.plies()[2].board;
+        other[9][9] = 2;
+        assert!(!record.rewind_to(&other, 1));
+        assert_eq!(record.moves.len(), 3);
+
+        let board = record.plies()[2].board;
+        assert!(!record.rewind_to(&board, 2));
+        assert!(record.rewind_to(&board, 1));
+        assert_eq!(record.moves, vec![(5, 6), (4, 4)]);
+    }
+
+    #[test]
+    fn playing_the_computer_moves_is_perfectly_accurate() {
+        let mut record = start();
+        let mut ply = record.plies()[0];
+        while !is_border(ply.mouse.x, ply.mouse.y) && record.moves.len() < 40 {
+            let Some(cell) = computer_move(&ply.board, &ply.mouse, ply.role) else {
+                break;
+            };
+            record.push(cell);
+            apply_move(&mut ply.board, &mut ply.mouse, ply.role, cell);
+            ply.role = 3 - ply.role;
+        }
+        assert!(record.moves.len() >= 2);
+
+        let reviews = analyse(&record);
+        assert_eq!(reviews.len(), record.moves.len());
+        for review in reviews.iter() {
+            assert_eq!(review.best, Some(review.played));
+            assert_eq!((review.loss, review.verdict), (0, Verdict::Best));
+        }
+        assert_eq!(accuracy(&reviews, 1), Some(100));
+        assert_eq!(accuracy(&reviews, 2), Some(100));
+    }
+
+    #[test]
+    fn verdicts_and_accuracy_follow_the_loss() {
+        assert_eq!(Verdict::from_loss(0), Verdict::Best);
+        assert_eq!(Verdict::from_loss(20), Verdict::Good);
+        assert_eq!(Verdict::from_loss(21), Verdict::Inaccuracy);
+        assert_eq!(Verdict::from_loss(80), Verdict::Inaccuracy);
+        assert_eq!(Verdict::from_loss(81), Verdict::Blunder);
+
+        let review = |role: u8, loss: i32| Review {
+            role,
+            played: (0, 0),
+            best: None,
+            loss,
+            verdict: Verdict::from_loss(loss),
+        };
+        let reviews = [review(1, 0), review(1, 500), review(2, 30)];
+        assert_eq!(accuracy(&reviews, 1), Some(50));
+        assert_eq!(accuracy(&reviews, 2), Some(70));
+        assert_eq!(accuracy(&reviews[..1], 2), None);
+    }
+}
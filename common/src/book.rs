@@ -0,0 +1,268 @@
+use std::{collections::HashMap, fs, path::Path, sync::Arc};
+
+use crate::engine::{Budget, Strategy};
+use crate::rules::{apply_move, check_any_left_move, is_border, legal_moves, Board, BOARD_SIZE};
+use crate::symmetry::Canonical;
+use crate::{AppError, Position};
+
+const MAGIC: &[u8; 4] = b"TTMB";
+const VERSION: u8 = 1;
+// Cheia pe 8 octeti, apoi rolul si celula mutarii.
+const ENTRY_SIZE: usize = 11;
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+// Cheia pozitiei: FNV-1a peste forma canonica si cine muta. Nu folosim cheia Zobrist
+// pentru ca aceasta ajunge in fisier si trebuie sa ramana aceeasi de la o versiune la alta.
+pub fn position_key(canonical: &Canonical, to_move: u8) -> u64 {
+    let mut hash = FNV_OFFSET;
+    for &cell in canonical.board.iter().flatten().chain([to_move].iter()) {
+        hash ^= cell as u64;
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+    hash
+}
+
+// Mutarile pentru primele pozitii din partida, calculate dinainte de un motor.
+#[derive(Debug, Default)]
+pub struct OpeningBook {
+    moves: HashMap<u64, (u8, (u8, u8))>,
+}
+
+impl OpeningBook {
+    pub fn new() -> Self {
+        OpeningBook::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.moves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.moves.is_empty()
+    }
+
+    // Cate pozitii are cartea pentru fiecare rol (soarece, ziduri).
+    pub fn positions(&self) -> (usize, usize) {
+        let mouse = self.moves.values().filter(|&&(role, _)| role == 1).count();
+        (mouse, self.moves.len() - mouse)
+    }
+
+    pub fn insert(&mut self, board: &Board, mouse: &Position, to_move: u8, cell: (usize, usize)) {
+        let canonical = Canonical::new(board, mouse, BOARD_SIZE);
+        let key = position_key(&canonical, to_move);
+        let (x, y) = canonical.to_canonical(cell);
+        self.moves
+            .entry(key)
+            .or_insert((to_move, (x as u8, y as u8)));
+    }
+
+    // Mutarea din carte, intoarsa pe tabla reala; o cheie care se potriveste din intamplare
+    // cu alta pozitie da de obicei o mutare imposibila, pe care o ignoram.
+    pub fn probe(&self, board: &Board, mouse: &Position, to_move: u8) -> Option<(usize, usize)> {
+        let canonical = Canonical::new(board, mouse, BOARD_SIZE);
+        let &(role, (x, y)) = self.moves.get(&position_key(&canonical, to_move))?;
+        let cell = canonical.from_canonical((x as usize, y as usize));
+        (role == to_move && legal_moves(board, mouse, to_move).contains(&cell)).then_some(cell)
+    }
+
+    // Adauga pozitia si urmarile ei pe `plies` mutari. Pastram raspunsul motorului peste tot,
+    // dar mergem mai departe pe toate mutarile soarecelui si doar pe zidul ales, altfel cartea
+    // ar creste cu o suta de ziduri la fiecare pas.
+    pub fn extend(
+        &mut self,
+        board: &Board,
+        mouse: &Position,
+        to_move: u8,
+        plies: usize,
+        strategy: &mut dyn Strategy,
+    ) {
+        if plies == 0
+            || is_border(mouse.x, mouse.y)
+            || (to_move == 1 && !check_any_left_move(board, mouse))
+        {
+            return;
+        }
+        let best = match self.probe(board, mouse, to_move) {
+            Some(cell) => cell,
+            None => match strategy.choose(board, mouse, to_move) {
+                Some(cell) => cell,
+                None => return,
+            },
+        };
+        self.insert(board, mouse, to_move, best);
+        let replies = if to_move == 1 {
+            legal_moves(board, mouse, 1)
+        } else {
+            vec![best]
+        };
+        for cell in replies {
+            let mut next = *board;
+            let mut next_mouse = *mouse;
+            apply_move(&mut next, &mut next_mouse, to_move, cell);
+            self.extend(&next, &next_mouse, 3 - to_move, plies - 1, strategy);
+        }
+    }
+
+    pub fn merge(&mut self, other: OpeningBook) {
+        for (key, entry) in other.moves {
+            self.moves.entry(key).or_insert(entry);
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), AppError> {
+        let mut entries: Vec<_> = self.moves.iter().collect();
+        entries.sort_by_key(|&(&key, _)| key);
+        let mut bytes = Vec::with_capacity(entries.len() * ENTRY_SIZE + 5);
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        for (key, &(role, (x, y))) in entries {
+            bytes.extend_from_slice(&key.to_be_bytes());
+            bytes.extend_from_slice(&[role, x, y]);
+        }
+        fs::write(path, bytes).map_err(AppError::IOError)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, AppError> {
+        let bytes = fs::read(path).map_err(AppError::IOError)?;
+        let invalid =
+            |reason: &str| AppError::ConfigError(format!("{}: {}", path.display(), reason));
+        if bytes.len() < 5 || &bytes[..4] != MAGIC {
+            return Err(invalid("nu este o carte de deschideri"));
+        }
+        if bytes[4] != VERSION {
+            return Err(invalid("versiune necunoscuta"));
+        }
+        let entries = &bytes[5..];
+        if entries.len() % ENTRY_SIZE != 0 {
+            return Err(invalid("fisier incomplet"));
+        }
+        let mut book = OpeningBook::new();
+        for entry in entries.chunks_exact(ENTRY_SIZE) {
+            let key = u64::from_be_bytes(entry[..8].try_into().unwrap());
+            let (role, x, y) = (entry[8], entry[9], entry[10]);
+            if !(1..=2).contains(&role) || x as usize >= BOARD_SIZE || y as usize >= BOARD_SIZE {
+                return Err(invalid("mutare invalida in carte"));
+            }
+            book.moves.insert(key, (role, (x, y)));
+        }
+        Ok(book)
+    }
+}
+
+// O strategie care joaca din carte cat timp poate si cauta singura dupa aceea.
+pub struct Booked {
+    book: Arc<OpeningBook>,
+    inner: Box<dyn Strategy>,
+}
+
+impl Booked {
+    pub fn new(book: Arc<OpeningBook>, inner: Box<dyn Strategy>) -> Self {
+        Booked { book, inner }
+    }
+}
+
+impl Strategy for Booked {
+    fn name(&self) -> String {
+        format!("{}+carte", self.inner.name())
+    }
+
+    fn set_budget(&mut self, budget: Budget) {
+        self.inner.set_budget(budget);
+    }
+
+    fn choose(&mut self, board: &Board, mouse: &Position, role: u8) -> Option<(usize, usize)> {
+        self.book
+            .probe(board, mouse, role)
+            .or_else(|| self.inner.choose(board, mouse, role))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::Heuristic;
+    use crate::rules::new_board;
+    use crate::symmetry::Symmetry;
+
+    fn position() -> (Board, Position) {
+        (
+            new_board((3, 4), &[(2, 4), (7, 7), (9, 1)]),
+            Position::new(3, 4),
+        )
+    }
+
+    fn mirrored(board: &Board, mouse: &Position) -> (Board, Position) {
+        let (x, y) = Symmetry::FlipRows.apply((mouse.x, mouse.y), BOARD_SIZE);
+        (
+            Symmetry::FlipRows.board(board, BOARD_SIZE),
+            Position::new(x, y),
+        )
+    }
+
+    #[test]
+    fn probe_finds_the_mirrored_position() {
+        let (board, mouse) = position();
+        let (image, image_mouse) = mirrored(&board, &mouse);
+        assert_ne!(image, board);
+
+        let mut book = OpeningBook::new();
+        book.insert(&board, &mouse, 1, (3, 3));
+        book.insert(&board, &mouse, 2, (4, 4));
+        assert_eq!(book.positions(), (1, 1));
+
+        assert_eq!(book.probe(&board, &mouse, 1), Some((3, 3)));
+        let flip = |cell| Symmetry::FlipRows.apply(cell, BOARD_SIZE);
+        assert_eq!(book.probe(&image, &image_mouse, 1), Some(flip((3, 3))));
+        assert_eq!(book.probe(&image, &image_mouse, 2), Some(flip((4, 4))));
+
+        let (other, other_mouse) = (new_board((5, 5), &[]), Position::new(5, 5));
+        assert_eq!(book.probe(&other, &other_mouse, 1), None);
+    }
+
+    #[test]
+    fn merge_keeps_existing_moves_and_adds_new_positions() {
+        let (board, mouse) = position();
+        let mut book = OpeningBook::new();
+        book.insert(&board, &mouse, 1, (3, 3));
+
+        let mut other = OpeningBook::new();
+        other.insert(&board, &mouse, 1, (3, 5));
+        let (image, image_mouse) = mirrored(&board, &mouse);
+        other.insert(&image, &image_mouse, 2, (0, 0));
+        let (start, start_mouse) = (new_board((5, 5), &[]), Position::new(5, 5));
+        other.insert(&start, &start_mouse, 1, (5, 4));
+
+        book.merge(other);
+        assert_eq!(book.len(), 3);
+        assert_eq!(book.probe(&board, &mouse, 1), Some((3, 3)));
+        assert_eq!(
+            book.probe(&board, &mouse, 2),
+            Some(Symmetry::FlipRows.apply((0, 0), BOARD_SIZE))
+        );
+        assert_eq!(book.probe(&start, &start_mouse, 1), Some((5, 4)));
+    }
+
+    #[test]
+    fn file_round_trip() {
+        let (board, mouse) = position();
+        let mut book = OpeningBook::new();
+        book.extend(&board, &mouse, 1, 3, &mut Heuristic);
+        assert!(book.len() > 1);
+
+        let path = std::env::temp_dir().join(format!("ttmb-{}.bin", std::process::id()));
+        book.save(&path).unwrap();
+        let loaded = OpeningBook::load(&path).unwrap();
+        assert_eq!(loaded.moves, book.moves);
+
+        let bytes = fs::read(&path).unwrap();
+        assert_eq!(bytes.len(), 5 + book.len() * ENTRY_SIZE);
+        fs::write(&path, &bytes[..bytes.len() - 1]).unwrap();
+        assert!(OpeningBook::load(&path).is_err());
+        fs::write(&path, [b"TTMX".as_slice(), &bytes[4..]].concat()).unwrap();
+        assert!(OpeningBook::load(&path).is_err());
+        fs::write(&path, [&bytes[..4], &[VERSION + 1], &bytes[5..]].concat()).unwrap();
+        assert!(OpeningBook::load(&path).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+}
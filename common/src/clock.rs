@@ -0,0 +1,191 @@
+use std::{fmt, time::Duration};
+
+use crate::AppError;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum TimeControl {
+    #[default]
+    Unlimited,
+    PerMove(Duration),
+    Bank {
+        initial: Duration,
+        increment: Duration,
+    },
+}
+
+impl TimeControl {
+    pub fn parse(spec: &str) -> Result<Self, AppError> {
+        let invalid = || AppError::ConfigError(format!("control de timp invalid: '{}'", spec));
+        let seconds = |value: &str| {
+            value
+                .trim()
+                .parse::<u64>()
+                .map(Duration::from_secs)
+                .map_err(|_| invalid())
+        };
+
+        let time_control = match spec.split_once(':') {
+            None if spec == "nelimitat" => TimeControl::Unlimited,
+            Some(("mutare", value)) => TimeControl::PerMove(seconds(value)?),
+            Some(("banca", value)) => {
+                let (initial, increment) = value.split_once('+').unwrap_or((value, "0"));
+                TimeControl::Bank {
+                    initial: seconds(initial)?,
+                    increment: seconds(increment)?,
+                }
+            }
+            _ => return Err(invalid()),
+        };
+        time_control.validate()?;
+        Ok(time_control)
+    }
+
+    pub fn validate(&self) -> Result<(), AppError> {
+        match *self {
+            TimeControl::Unlimited => Ok(()),
+            TimeControl::PerMove(per_move) if !(5..=600).contains(&per_move.as_secs()) => {
+                Err(AppError::ConfigError(String::from(
+                    "timpul pe mutare trebuie sa fie intre 5 si 600 de secunde",
+                )))
+            }
+            TimeControl::Bank { initial, .. } if !(10..=7200).contains(&initial.as_secs()) => {
+                Err(AppError::ConfigError(String::from(
+                    "banca de timp trebuie sa fie intre 10 si 7200 de secunde",
+                )))
+            }
+            TimeControl::Bank { increment, .. } if increment.as_secs() > 60 => {
+                Err(AppError::ConfigError(String::from(
+                    "incrementul trebuie sa fie de cel mult 60 de secunde",
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    // Timpul fiecarui jucator la inceputul jocului.
+    pub fn initial(&self) -> Option<Duration> {
+        match *self {
+            TimeControl::Unlimited => None,
+            TimeControl::PerMove(per_move) => Some(per_move),
+            TimeControl::Bank { initial, .. } => Some(initial),
+        }
+    }
+
+    // Timpul ramas dupa o mutare facuta in `used`.
+    pub fn after_move(&self, remaining: Duration, used: Duration) -> Duration {
+        match *self {
+            TimeControl::Unlimited => remaining,
+            TimeControl::PerMove(per_move) => per_move,
+            TimeControl::Bank { increment, .. } => remaining.saturating_sub(used) + increment,
+        }
+    }
+}
+
+impl fmt::Display for TimeControl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TimeControl::Unlimited => write!(f, "nelimitat"),
+            TimeControl::PerMove(per_move) => write!(f, "mutare:{}", per_move.as_secs()),
+            TimeControl::Bank { initial, increment } => {
+                write!(f, "banca:{}+{}", initial.as_secs(), increment.as_secs())
+            }
+        }
+    }
+}
+
+pub fn format_clock(remaining: Duration) -> String {
+    let seconds = remaining.as_secs();
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seconds(seconds: u64) -> Duration {
+        Duration::from_secs(seconds)
+    }
+
+    #[test]
+    fn parse_reads_every_time_control() {
+        assert_eq!(
+            TimeControl::parse("nelimitat").unwrap(),
+            TimeControl::Unlimited
+        );
+        assert_eq!(
+            TimeControl::parse("mutare:30").unwrap(),
+            TimeControl::PerMove(seconds(30))
+        );
+        assert_eq!(
+            TimeControl::parse("banca:300+5").unwrap(),
+            TimeControl::Bank {
+                initial: seconds(300),
+                increment: seconds(5),
+            }
+        );
+        assert_eq!(
+            TimeControl::parse("banca:600").unwrap(),
+            TimeControl::Bank {
+                initial: seconds(600),
+                increment: Duration::ZERO,
+            }
+        );
+        for time_control in ["nelimitat", "mutare:45", "banca:120+10"] {
+            assert_eq!(
+                TimeControl::parse(time_control).unwrap().to_string(),
+                time_control
+            );
+        }
+    }
+
+    #[test]
+    fn parse_rejects_invalid_specs() {
+        for spec in [
+            "",
+            "rapid",
+            "mutare",
+            "mutare:",
+            "mutare:abc",
+            "mutare:-5",
+            "mutare:4",
+            "mutare:601",
+            "banca:9",
+            "banca:7201",
+            "banca:300+61",
+            "banca:300+",
+            "banca:+5",
+            "ceas:30",
+        ] {
+            assert!(TimeControl::parse(spec).is_err(), "{}", spec);
+        }
+    }
+
+    #[test]
+    fn after_move_adds_the_increment_or_resets_the_move_time() {
+        let bank = TimeControl::Bank {
+            initial: seconds(60),
+            increment: seconds(5),
+        };
+        assert_eq!(bank.initial(), Some(seconds(60)));
+        assert_eq!(bank.after_move(seconds(60), seconds(12)), seconds(53));
+        // Timpul nu scade sub zero, dar incrementul se adauga oricum.
+        assert_eq!(bank.after_move(seconds(3), seconds(10)), seconds(5));
+
+        let per_move = TimeControl::PerMove(seconds(30));
+        assert_eq!(per_move.initial(), Some(seconds(30)));
+        assert_eq!(per_move.after_move(seconds(30), seconds(29)), seconds(30));
+
+        assert_eq!(TimeControl::Unlimited.initial(), None);
+        assert_eq!(
+            TimeControl::Unlimited.after_move(seconds(7), seconds(100)),
+            seconds(7)
+        );
+    }
+
+    #[test]
+    fn format_clock_shows_minutes_and_seconds() {
+        assert_eq!(format_clock(seconds(0)), "0:00");
+        assert_eq!(format_clock(Duration::from_millis(65_900)), "1:05");
+        assert_eq!(format_clock(seconds(7200)), "120:00");
+    }
+}
@@ -0,0 +1,369 @@
+use std::{
+    io::{BufRead, Write},
+    time::{Duration, Instant},
+};
+
+use crate::ai::{
+    computer_move, computer_wall_move, lookahead_mouse_move, LOOKAHEAD_DEPTH, LOOKAHEAD_TABLE_BITS,
+};
+use crate::mcts::Mcts;
+use crate::rules::{apply_move, legal_moves, Board, BOARD_SIZE};
+use crate::transposition::{Score, TranspositionTable};
+use crate::{AppError, Position};
+
+// Cat are voie sa caute un motor: numar de iteratii, timp, sau amandoua (se opreste la prima limita).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Budget {
+    pub iterations: Option<u32>,
+    pub time: Option<Duration>,
+}
+
+impl Budget {
+    pub fn iterations(iterations: u32) -> Self {
+        Budget {
+            iterations: Some(iterations),
+            time: None,
+        }
+    }
+
+    pub fn time(time: Duration) -> Self {
+        Budget {
+            iterations: None,
+            time: Some(time),
+        }
+    }
+
+    pub fn deadline(&self) -> Option<Instant> {
+        self.time.map(|time| Instant::now() + time)
+    }
+}
+
+impl Default for Budget {
+    fn default() -> Self {
+        Budget::iterations(2000)
+    }
+}
+
+// Orice algoritm care alege o mutare pentru rolul dat (1 soarecele, 2 zidurile).
+pub trait Strategy: Send {
+    fn name(&self) -> String;
+    fn choose(&mut self, board: &Board, mouse: &Position, role: u8) -> Option<(usize, usize)>;
+    fn set_budget(&mut self, _budget: Budget) {}
+}
+
+pub struct Heuristic;
+
+impl Strategy for Heuristic {
+    fn name(&self) -> String {
+        String::from("euristic")
+    }
+
+    fn choose(&mut self, board: &Board, mouse: &Position, role: u8) -> Option<(usize, usize)> {
+        computer_move(board, mouse, role)
+    }
+}
+
+// Soarecele anticipeaza zidurile adversarului; zidurile raman cele euristice.
+// Tabela ramane de la o mutare la alta, pentru ca pozitiile se repeta intre cautari.
+pub struct Lookahead {
+    pub depth: usize,
+    table: TranspositionTable<Score>,
+}
+
+impl Lookahead {
+    pub fn new(depth: usize) -> Self {
+        Lookahead {
+            depth,
+            table: TranspositionTable::new(LOOKAHEAD_TABLE_BITS),
+        }
+    }
+}
+
+impl Strategy for Lookahead {
+    fn name(&self) -> String {
+        String::from("anticipare")
+    }
+
+    fn choose(&mut self, board: &Board, mouse: &Position, role: u8) -> Option<(usize, usize)> {
+        if role == 1 {
+            lookahead_mouse_move(board, mouse, self.depth, &mut self.table)
+        } else {
+            computer_wall_move(board, mouse)
+        }
+    }
+}
+
+pub fn strategy(name: &str, budget: Budget, threads: usize) -> Result<Box<dyn Strategy>, AppError> {
+    match name.trim() {
+        "euristic" => Ok(Box::new(Heuristic)),
+        "anticipare" => Ok(Box::new(Lookahead::new(LOOKAHEAD_DEPTH))),
+        "mcts" => Ok(Box::new(Mcts::new(budget, threads))),
+        _ => Err(AppError::ConfigError(format!(
+            "motor necunoscut '{}' (euristic, anticipare, mcts)",
+            name
+        ))),
+    }
+}
+
+// "mcts:5000" inseamna mcts cu 5000 de iteratii; fara numar folosim bugetul implicit.
+pub fn strategy_from_spec(spec: &str, threads: usize) -> Result<Box<dyn Strategy>, AppError> {
+    let (name, budget) = match spec.split_once(':') {
+        Some((name, iterations)) => {
+            let iterations = iterations.parse().map_err(|_| {
+                AppError::ConfigError(format!("numar de iteratii invalid in '{}'", spec))
+            })?;
+            (name, Budget::iterations(iterations))
+        }
+        None => (spec, Budget::default()),
+    };
+    strategy(name, budget, threads)
+}
+
+// Un motor care tine singur partida: afla mutarile adversarului si muta cand ii vine randul.
+pub trait Engine: Send {
+    fn name(&self) -> String;
+    fn new_game(&mut self, board: &Board, mouse: &Position, role: u8) -> Result<(), AppError>;
+    fn opponent_move(&mut self, cell: (usize, usize)) -> Result<(), AppError>;
+    fn pick_move(&mut self, budget: Budget) -> Result<Option<(usize, usize)>, AppError>;
+}
+
+// Orice strategie din proces devine motor; tabla o tinem noi.
+pub struct Local {
+    strategy: Box<dyn Strategy>,
+    board: Board,
+    mouse: Position,
+    role: u8,
+}
+
+impl Local {
+    pub fn new(strategy: Box<dyn Strategy>) -> Self {
+        Local {
+            strategy,
+            board: [[0; BOARD_SIZE]; BOARD_SIZE],
+            mouse: Position::new(0, 0),
+            role: 1,
+        }
+    }
+}
+
+impl Engine for Local {
+    fn name(&self) -> String {
+        self.strategy.name()
+    }
+
+    fn new_game(&mut self, board: &Board, mouse: &Position, role: u8) -> Result<(), AppError> {
+        self.board = *board;
+        self.mouse = *mouse;
+        self.role = role;
+        Ok(())
+    }
+
+    fn opponent_move(&mut self, cell: (usize, usize)) -> Result<(), AppError> {
+        let role = 3 - self.role;
+        if !legal_moves(&self.board, &self.mouse, role).contains(&cell) {
+            return Err(AppError::InvalidMove(format!(
+                "mutarea adversarului {},{} nu este valida",
+                cell.0, cell.1
+            )));
+        }
+        apply_move(&mut self.board, &mut self.mouse, role, cell);
+        Ok(())
+    }
+
+    fn pick_move(&mut self, budget: Budget) -> Result<Option<(usize, usize)>, AppError> {
+        self.strategy.set_budget(budget);
+        let cell = self.strategy.choose(&self.board, &self.mouse, self.role);
+        if let Some(cell) = cell {
+            apply_move(&mut self.board, &mut self.mouse, self.role, cell);
+        }
+        Ok(cell)
+    }
+}
+
+// Protocolul text pentru motoare externe, cate o comanda pe linie (ca UCI la sah):
+//   ttm                        -> motorul raspunde "id name <nume>" si apoi "ttmok"
+//   newgame <rol> <tabla>      rolul motorului (mouse/walls), tabla ca 121 de cifre 0/1/2 pe randuri
+//   opponent <rand>,<coloana>  mutarea adversarului
+//   go <milisecunde>           -> motorul raspunde "move <rand>,<coloana>" sau "move none"
+//   quit
+// Liniile "info ..." trimise de motor sunt ignorate. Motorul sare peste comenzile pe care nu
+// le cunoaste, ca sa putem adauga comenzi noi fara sa stricam motoarele existente.
+pub const HANDSHAKE: &str = "ttm";
+pub const HANDSHAKE_OK: &str = "ttmok";
+
+pub fn role_name(role: u8) -> &'static str {
+    if role == 1 {
+        "mouse"
+    } else {
+        "walls"
+    }
+}
+
+pub fn parse_role(text: &str) -> Option<u8> {
+    match text {
+        "mouse" => Some(1),
+        "walls" => Some(2),
+        _ => None,
+    }
+}
+
+pub fn board_to_text(board: &Board) -> String {
+    board
+        .iter()
+        .flatten()
+        .map(|&cell| char::from(b'0' + cell))
+        .collect()
+}
+
+pub fn board_from_text(text: &str) -> Option<(Board, Position)> {
+    if text.len() != BOARD_SIZE * BOARD_SIZE {
+        return None;
+    }
+    let mut board = [[0; BOARD_SIZE]; BOARD_SIZE];
+    let mut mouse = None;
+    for (index, byte) in text.bytes().enumerate() {
+        let (x, y) = (index / BOARD_SIZE, index % BOARD_SIZE);
+        board[x][y] = match byte {
+            b'0' => 0,
+            b'1' if mouse.is_none() => {
+                mouse = Some(Position::new(x, y));
+                1
+            }
+            b'2' => 2,
+            _ => return None,
+        };
+    }
+    mouse.map(|mouse| (board, mouse))
+}
+
+pub fn cell_to_text((x, y): (usize, usize)) -> String {
+    format!("{},{}", x, y)
+}
+
+pub fn parse_cell(text: &str) -> Option<(usize, usize)> {
+    let (x, y) = text.split_once(',')?;
+    let cell = (x.trim().parse().ok()?, y.trim().parse().ok()?);
+    (cell.0 < BOARD_SIZE && cell.1 < BOARD_SIZE).then_some(cell)
+}
+
+// Partea motorului din protocol: citeste comenzi pana la "quit" sau pana se inchide intrarea.
+pub fn serve(
+    engine: &mut dyn Engine,
+    input: impl BufRead,
+    mut output: impl Write,
+) -> Result<(), AppError> {
+    let mut reply = |line: String| {
+        writeln!(output, "{}", line)
+            .and_then(|_| output.flush())
+            .map_err(|e| AppError::WriteError(e.to_string()))
+    };
+    for line in input.lines() {
+        let line = line.map_err(|e| AppError::ReadError(e.to_string()))?;
+        let mut words = line.split_whitespace();
+        let invalid = || AppError::UnexpectedResponse(format!("comanda invalida: '{}'", line));
+        match words.next() {
+            Some(HANDSHAKE) => {
+                reply(format!("id name {}", engine.name()))?;
+                reply(String::from(HANDSHAKE_OK))?;
+            }
+            Some("newgame") => {
+                let role = words.next().and_then(parse_role).ok_or_else(invalid)?;
+                let (board, mouse) = words.next().and_then(board_from_text).ok_or_else(invalid)?;
+                engine.new_game(&board, &mouse, role)?;
+            }
+            Some("opponent") => {
+                let cell = words.next().and_then(parse_cell).ok_or_else(invalid)?;
+                engine.opponent_move(cell)?;
+            }
+            Some("go") => {
+                let millis: u64 = words
+                    .next()
+                    .and_then(|w| w.parse().ok())
+                    .ok_or_else(invalid)?;
+                let cell = engine.pick_move(Budget::time(Duration::from_millis(millis)))?;
+                reply(match cell {
+                    Some(cell) => format!("move {}", cell_to_text(cell)),
+                    None => String::from("move none"),
+                })?;
+            }
+            Some("quit") => break,
+            Some(_) | None => {}
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::new_board;
+
+    fn position() -> (Board, Position) {
+        (
+            new_board((5, 5), &[(0, 0), (4, 4), (10, 10), (5, 6)]),
+            Position::new(5, 5),
+        )
+    }
+
+    #[test]
+    fn board_text_round_trip() {
+        let (board, mouse) = position();
+        let text = board_to_text(&board);
+        assert_eq!(text.len(), BOARD_SIZE * BOARD_SIZE);
+        assert_eq!(board_from_text(&text), Some((board, mouse)));
+
+        assert_eq!(board_from_text(&text[1..]), None);
+        assert_eq!(board_from_text(&text.replace('1', "0")), None);
+        assert_eq!(board_from_text(&text.replacen('0', "1", 1)), None);
+        assert_eq!(board_from_text(&text.replacen('0', "3", 1)), None);
+    }
+
+    #[test]
+    fn cell_text_round_trip() {
+        assert_eq!(parse_cell(&cell_to_text((3, 10))), Some((3, 10)));
+        assert_eq!(parse_cell("3, 4"), Some((3, 4)));
+        assert_eq!(parse_cell("3,11"), None);
+        assert_eq!(parse_cell("3"), None);
+    }
+
+    #[test]
+    fn serve_answers_a_session_and_skips_unknown_commands() {
+        let (board, mouse) = position();
+        let first = computer_move(&board, &mouse, 1).unwrap();
+        let (mut after, mut moved) = (board, mouse);
+        apply_move(&mut after, &mut moved, 1, first);
+        let wall = computer_move(&after, &moved, 2).unwrap();
+        apply_move(&mut after, &mut moved, 2, wall);
+        let second = computer_move(&after, &moved, 1).unwrap();
+
+        let input = format!(
+            "ttm\nnewgame mouse {}\nponder on\n\ngo 50\nopponent {}\ngo 50\nquit\ngo 50\n",
+            board_to_text(&board),
+            cell_to_text(wall)
+        );
+        let mut output = Vec::new();
+        let mut engine = Local::new(Box::new(Heuristic));
+        serve(&mut engine, input.as_bytes(), &mut output).unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(
+            lines,
+            vec![
+                "id name euristic".to_string(),
+                HANDSHAKE_OK.to_string(),
+                format!("move {}", cell_to_text(first)),
+                format!("move {}", cell_to_text(second)),
+            ]
+        );
+    }
+
+    #[test]
+    fn serve_rejects_malformed_known_commands() {
+        let mut engine = Local::new(Box::new(Heuristic));
+        let result = serve(&mut engine, &b"newgame mouse 012\n"[..], Vec::new());
+        assert!(result.is_err());
+        let result = serve(&mut engine, &b"go repede\n"[..], Vec::new());
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,146 @@
+use std::{
+    io::{BufRead, BufReader, Write},
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::mpsc::{self, Receiver, RecvTimeoutError},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::engine::{
+    board_to_text, cell_to_text, parse_cell, role_name, Budget, Engine, HANDSHAKE, HANDSHAKE_OK,
+};
+use crate::rules::Board;
+use crate::{AppError, Position};
+
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(3);
+// Cat ii mai dam motorului peste timpul cerut, pentru pornirea procesului si citire.
+const MOVE_GRACE: Duration = Duration::from_secs(2);
+const DEFAULT_MOVE_TIME: Duration = Duration::from_secs(1);
+
+// Un motor care ruleaza ca proces separat si vorbeste protocolul din engine.rs.
+pub struct External {
+    name: String,
+    child: Child,
+    stdin: ChildStdin,
+    lines: Receiver<String>,
+}
+
+impl External {
+    pub fn spawn(command: &str) -> Result<Self, AppError> {
+        let mut words = command.split_whitespace();
+        let program = words
+            .next()
+            .ok_or_else(|| AppError::ConfigError(String::from("comanda motorului este goala")))?;
+        let mut child = Command::new(program)
+            .args(words)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|e| {
+                AppError::ConfigError(format!("nu s-a putut porni '{}': {}", program, e))
+            })?;
+        let stdin = child.stdin.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+
+        // Citim pe un fir separat ca sa putem renunta la un motor care nu mai raspunde.
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else { break };
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut engine = External {
+            name: String::from(program),
+            child,
+            stdin,
+            lines,
+        };
+        engine.send(HANDSHAKE)?;
+        let deadline = Instant::now() + STARTUP_TIMEOUT;
+        loop {
+            let line = engine.read_line(deadline)?;
+            if let Some(name) = line.strip_prefix("id name ") {
+                engine.name = String::from(name.trim());
+            } else if line.trim() == HANDSHAKE_OK {
+                return Ok(engine);
+            }
+        }
+    }
+
+    fn send(&mut self, line: &str) -> Result<(), AppError> {
+        writeln!(self.stdin, "{}", line)
+            .and_then(|_| self.stdin.flush())
+            .map_err(|e| AppError::WriteError(format!("motorul {}: {}", self.name, e)))
+    }
+
+    fn read_line(&self, deadline: Instant) -> Result<String, AppError> {
+        let wait = deadline.saturating_duration_since(Instant::now());
+        match self.lines.recv_timeout(wait) {
+            Ok(line) => Ok(line),
+            Err(RecvTimeoutError::Timeout) => Err(AppError::ReadError(format!(
+                "motorul {} nu a raspuns la timp",
+                self.name
+            ))),
+            Err(RecvTimeoutError::Disconnected) => Err(AppError::ReadError(format!(
+                "motorul {} s-a oprit",
+                self.name
+            ))),
+        }
+    }
+}
+
+impl Engine for External {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn new_game(&mut self, board: &Board, _mouse: &Position, role: u8) -> Result<(), AppError> {
+        self.send(&format!(
+            "newgame {} {}",
+            role_name(role),
+            board_to_text(board)
+        ))
+    }
+
+    fn opponent_move(&mut self, cell: (usize, usize)) -> Result<(), AppError> {
+        self.send(&format!("opponent {}", cell_to_text(cell)))
+    }
+
+    fn pick_move(&mut self, budget: Budget) -> Result<Option<(usize, usize)>, AppError> {
+        let time = budget.time.unwrap_or(DEFAULT_MOVE_TIME);
+        self.send(&format!("go {}", time.as_millis()))?;
+        let deadline = Instant::now() + time + MOVE_GRACE;
+        loop {
+            let line = self.read_line(deadline)?;
+            let Some(reply) = line.strip_prefix("move ") else {
+                continue;
+            };
+            let reply = reply.trim();
+            if reply == "none" {
+                return Ok(None);
+            }
+            return parse_cell(reply).map(Some).ok_or_else(|| {
+                AppError::UnexpectedResponse(format!("motorul {}: '{}'", self.name, line))
+            });
+        }
+    }
+}
+
+impl Drop for External {
+    fn drop(&mut self) {
+        let _ = self.send("quit");
+        let deadline = Instant::now() + Duration::from_millis(500);
+        while Instant::now() < deadline {
+            if let Ok(Some(_)) = self.child.try_wait() {
+                return;
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
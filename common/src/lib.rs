@@ -4,6 +4,21 @@ use std::{
     net::TcpStream,
 };
 
+pub mod ai;
+pub mod analysis;
+pub mod book;
+pub mod clock;
+pub mod engine;
+pub mod external;
+pub mod mcts;
+pub mod protocol;
+pub mod rules;
+pub mod symmetry;
+pub mod tablebase;
+pub mod tournament;
+pub mod transposition;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Position {
     pub x: usize,
     pub y: usize,
@@ -57,6 +72,7 @@ pub enum AppError {
     IOError(std::io::Error),
     UTF8Error(String),
     GraphicsError(String),
+    ConfigError(String),
 }
 
 impl fmt::Display for AppError {
@@ -73,6 +89,7 @@ impl fmt::Display for AppError {
             AppError::IOError(err) => write!(f, "IO error: {}", err),
             AppError::UTF8Error(msg) => write!(f, "UTF-8 error: {}", msg),
             AppError::GraphicsError(msg) => write!(f, "Egui error: {}", msg),
+            AppError::ConfigError(msg) => write!(f, "Config error: {}", msg),
         }
     }
 }
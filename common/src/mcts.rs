@@ -0,0 +1,356 @@
+use rand::{seq::SliceRandom, Rng};
+use std::{collections::HashMap, thread, time::Instant};
+
+use crate::ai::{escape_routes, evaluate, hex_distance};
+use crate::engine::{Budget, Strategy};
+use crate::rules::{
+    apply_move, check_any_left_move, is_border, legal_moves, neighbours, zobrist, zobrist_after,
+    Board, BOARD_SIZE,
+};
+use crate::transposition::TranspositionTable;
+use crate::Position;
+
+const EXPLORATION: f64 = 1.4;
+const WALL_RADIUS: usize = 2;
+const ROLLOUT_LIMIT: usize = 200;
+const GREEDY_MOUSE: f64 = 0.8;
+const BLOCKING_WALL: f64 = 0.7;
+const TABLE_BITS: u32 = 14;
+
+type Candidates = TranspositionTable<Vec<(usize, usize)>>;
+
+#[derive(Clone, Copy)]
+struct State {
+    board: Board,
+    mouse: Position,
+    to_move: u8,
+    key: u64,
+}
+
+impl State {
+    fn winner(&self) -> Option<u8> {
+        if is_border(self.mouse.x, self.mouse.y) {
+            return Some(1);
+        }
+        if self.to_move == 1 && !check_any_left_move(&self.board, &self.mouse) {
+            return Some(2);
+        }
+        None
+    }
+
+    // Zidurile departe de soarece si de drumurile lui de scapare nu conteaza, asa ca nu le cautam.
+    // Lista cere un BFS, asa ca o pastram pentru pozitiile la care arborele ajunge pe mai multe cai.
+    fn moves(&self, table: &mut Candidates) -> Vec<(usize, usize)> {
+        if self.to_move == 1 {
+            return legal_moves(&self.board, &self.mouse, 1);
+        }
+        if let Some(moves) = table.get(self.key) {
+            return moves.clone();
+        }
+        let mouse = (self.mouse.x, self.mouse.y);
+        let mut moves: Vec<(usize, usize)> = legal_moves(&self.board, &self.mouse, 2)
+            .into_iter()
+            .filter(|&cell| hex_distance(mouse, cell) <= WALL_RADIUS)
+            .collect();
+        if let Some(routes) = escape_routes(&self.board, mouse.0, mouse.1) {
+            for (_, cell) in routes.steps {
+                if !moves.contains(&cell) {
+                    moves.push(cell);
+                }
+            }
+        }
+        if moves.is_empty() {
+            moves = legal_moves(&self.board, &self.mouse, 2);
+        }
+        table.insert(self.key, moves.clone());
+        moves
+    }
+
+    fn play(&mut self, cell: (usize, usize)) {
+        self.key = zobrist_after(self.key, &self.mouse, self.to_move, cell);
+        apply_move(&mut self.board, &mut self.mouse, self.to_move, cell);
+        self.to_move = 3 - self.to_move;
+    }
+
+    // Joc rapid pana la final: soarecele fuge spre margine, zidurile il blocheaza.
+    fn rollout(mut self, rng: &mut impl Rng) -> u8 {
+        for _ in 0..ROLLOUT_LIMIT {
+            if let Some(winner) = self.winner() {
+                return winner;
+            }
+            let free: Vec<(usize, usize)> = neighbours(self.mouse.x, self.mouse.y)
+                .into_iter()
+                .filter(|&(x, y)| self.board[x][y] == 0)
+                .collect();
+            let closest = free.iter().map(|&cell| border_distance(cell)).min();
+            let greedy: Vec<(usize, usize)> = free
+                .iter()
+                .copied()
+                .filter(|&cell| Some(border_distance(cell)) == closest)
+                .collect();
+            let cell = if self.to_move == 1 {
+                if rng.gen_bool(GREEDY_MOUSE) {
+                    *greedy.choose(rng).unwrap()
+                } else {
+                    *free.choose(rng).unwrap()
+                }
+            } else if !greedy.is_empty() && rng.gen_bool(BLOCKING_WALL) {
+                *greedy.choose(rng).unwrap()
+            } else {
+                match random_free_cell(&self.board, rng) {
+                    Some(cell) => cell,
+                    None => return 2,
+                }
+            };
+            self.play(cell);
+        }
+        if evaluate(&self.board, &self.mouse, self.to_move) > 0 {
+            1
+        } else {
+            2
+        }
+    }
+}
+
+fn border_distance((x, y): (usize, usize)) -> usize {
+    let last = BOARD_SIZE - 1;
+    x.min(y).min(last - x).min(last - y)
+}
+
+fn random_free_cell(board: &Board, rng: &mut impl Rng) -> Option<(usize, usize)> {
+    for _ in 0..20 {
+        let cell = (rng.gen_range(0..BOARD_SIZE), rng.gen_range(0..BOARD_SIZE));
+        if board[cell.0][cell.1] == 0 {
+            return Some(cell);
+        }
+    }
+    (0..BOARD_SIZE)
+        .flat_map(|x| (0..BOARD_SIZE).map(move |y| (x, y)))
+        .filter(|&(x, y)| board[x][y] == 0)
+        .collect::<Vec<_>>()
+        .choose(rng)
+        .copied()
+}
+
+struct Node {
+    cell: (usize, usize),
+    mover: u8,
+    children: Vec<usize>,
+    untried: Vec<(usize, usize)>,
+    visits: u32,
+    wins: f64,
+}
+
+pub struct Mcts {
+    pub budget: Budget,
+    pub threads: usize,
+    pub transpositions: bool,
+}
+
+impl Mcts {
+    pub fn new(budget: Budget, threads: usize) -> Self {
+        Mcts {
+            budget,
+            threads: threads.max(1),
+            transpositions: true,
+        }
+    }
+
+    // Un arbore UCT; intoarce vizitele si castigurile fiecarei mutari de la radacina.
+    fn search(
+        root: State,
+        iterations: Option<u32>,
+        deadline: Option<Instant>,
+        mut table: Candidates,
+    ) -> Vec<Node> {
+        let mut rng = rand::thread_rng();
+        let mut nodes = vec![Node {
+            cell: (0, 0),
+            mover: 3 - root.to_move,
+            children: Vec::new(),
+            untried: root.moves(&mut table),
+            visits: 0,
+            wins: 0.0,
+        }];
+        let mut done = 0;
+        while iterations.is_none_or(|limit| done < limit)
+            && deadline.is_none_or(|deadline| Instant::now() < deadline)
+        {
+            done += 1;
+            let mut state = root;
+            let mut path = vec![0];
+            let mut current = 0;
+            while nodes[current].untried.is_empty() && !nodes[current].children.is_empty() {
+                let parent_visits = (nodes[current].visits as f64).ln();
+                current = *nodes[current]
+                    .children
+                    .iter()
+                    .max_by(|&&a, &&b| {
+                        let uct = |node: &Node| {
+                            node.wins / node.visits as f64
+                                + EXPLORATION * (parent_visits / node.visits as f64).sqrt()
+                        };
+                        uct(&nodes[a]).total_cmp(&uct(&nodes[b]))
+                    })
+                    .unwrap();
+                state.play(nodes[current].cell);
+                path.push(current);
+            }
+            if state.winner().is_none() && !nodes[current].untried.is_empty() {
+                let index = rng.gen_range(0..nodes[current].untried.len());
+                let cell = nodes[current].untried.swap_remove(index);
+                let mover = state.to_move;
+                state.play(cell);
+                nodes.push(Node {
+                    cell,
+                    mover,
+                    children: Vec::new(),
+                    untried: if state.winner().is_none() {
+                        state.moves(&mut table)
+                    } else {
+                        Vec::new()
+                    },
+                    visits: 0,
+                    wins: 0.0,
+                });
+                let child = nodes.len() - 1;
+                nodes[current].children.push(child);
+                path.push(child);
+            }
+            let winner = state.rollout(&mut rng);
+            for &index in path.iter() {
+                nodes[index].visits += 1;
+                if nodes[index].mover == winner {
+                    nodes[index].wins += 1.0;
+                }
+            }
+        }
+        let children = std::mem::take(&mut nodes[0].children);
+        let mut root_children = Vec::with_capacity(children.len());
+        for index in children {
+            root_children.push(std::mem::replace(
+                &mut nodes[index],
+                Node {
+                    cell: (0, 0),
+                    mover: 0,
+                    children: Vec::new(),
+                    untried: Vec::new(),
+                    visits: 0,
+                    wins: 0.0,
+                },
+            ));
+        }
+        root_children
+    }
+}
+
+impl Strategy for Mcts {
+    fn name(&self) -> String {
+        String::from("mcts")
+    }
+
+    fn set_budget(&mut self, budget: Budget) {
+        self.budget = budget;
+    }
+
+    fn choose(&mut self, board: &Board, mouse: &Position, role: u8) -> Option<(usize, usize)> {
+        let root = State {
+            board: *board,
+            mouse: *mouse,
+            to_move: role,
+            key: zobrist(board, role),
+        };
+        let moves = root.moves(&mut TranspositionTable::disabled());
+        if moves.len() <= 1 {
+            return moves.first().copied();
+        }
+        if role == 1 {
+            if let Some(&exit) = moves.iter().find(|&&(x, y)| is_border(x, y)) {
+                return Some(exit);
+            }
+        }
+
+        // Fiecare fir are arborele lui; la final adunam vizitele de la radacina.
+        let deadline = self.budget.deadline();
+        let iterations = self
+            .budget
+            .iterations
+            .map(|iterations| iterations.div_ceil(self.threads as u32));
+        let transpositions = self.transpositions;
+        let results: Vec<Vec<Node>> = thread::scope(|scope| {
+            let searches: Vec<_> = (0..self.threads)
+                .map(|_| {
+                    scope.spawn(move || {
+                        let table = if transpositions {
+                            TranspositionTable::new(TABLE_BITS)
+                        } else {
+                            TranspositionTable::disabled()
+                        };
+                        Mcts::search(root, iterations, deadline, table)
+                    })
+                })
+                .collect();
+            searches
+                .into_iter()
+                .filter_map(|search| search.join().ok())
+                .collect()
+        });
+        let mut visits: HashMap<(usize, usize), u32> = HashMap::new();
+        for node in results.into_iter().flatten() {
+            *visits.entry(node.cell).or_default() += node.visits;
+        }
+        visits
+            .into_iter()
+            .max_by_key(|&(cell, visits)| (visits, std::cmp::Reverse(cell)))
+            .map(|(cell, _)| cell)
+            .or_else(|| moves.first().copied())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::new_board;
+
+    fn position(mouse: (usize, usize), walls: &[(usize, usize)]) -> (Board, Position) {
+        let walls: Vec<(u8, u8)> = walls.iter().map(|&(x, y)| (x as u8, y as u8)).collect();
+        (new_board(mouse, &walls), Position::new(mouse.0, mouse.1))
+    }
+
+    #[test]
+    fn fixed_budget_gives_a_legal_move_for_both_roles() {
+        let (board, mouse) = position((5, 5), &[(4, 4), (6, 6), (2, 8)]);
+        for threads in [1, 2] {
+            let mut mcts = Mcts::new(Budget::iterations(300), threads);
+            for role in [1, 2] {
+                let cell = mcts.choose(&board, &mouse, role).unwrap();
+                assert!(legal_moves(&board, &mouse, role).contains(&cell));
+            }
+        }
+    }
+
+    #[test]
+    fn mouse_takes_an_exit_next_to_it() {
+        // Pe randul impar 1, vecinii de sus sunt (0, 5) si (0, 6); doar al doilea e liber.
+        let (board, mouse) = position((1, 5), &[(0, 5)]);
+        let mut mcts = Mcts::new(Budget::iterations(300), 1);
+        assert_eq!(mcts.choose(&board, &mouse, 1), Some((0, 6)));
+    }
+
+    #[test]
+    fn walls_block_the_only_exit() {
+        let (board, mouse) = position((1, 5), &[(0, 5), (1, 4), (1, 6), (2, 5), (2, 6)]);
+        assert_eq!(legal_moves(&board, &mouse, 1), vec![(0, 6)]);
+        let mut mcts = Mcts::new(Budget::iterations(2000), 1);
+        assert_eq!(mcts.choose(&board, &mouse, 2), Some((0, 6)));
+    }
+
+    #[test]
+    fn walls_block_a_single_corridor() {
+        // Soarecele are un singur vecin liber, iar de acolo are doua iesiri: doar el poate fi blocat.
+        let (board, mouse) = position((2, 5), &[(1, 5), (2, 4), (2, 6), (3, 4), (3, 5)]);
+        assert_eq!(legal_moves(&board, &mouse, 1), vec![(1, 4)]);
+        let mut mcts = Mcts::new(Budget::iterations(2000), 1);
+        assert_eq!(mcts.choose(&board, &mouse, 2), Some((1, 4)));
+    }
+}
@@ -0,0 +1,657 @@
+use std::io::Read;
+
+use crate::clock::TimeControl;
+use crate::rules::{Board, Variant, BOARD_SIZE};
+use crate::tournament::TournamentView;
+use crate::AppError;
+
+pub const SNAPSHOT_LEN: usize = 4 + BOARD_SIZE * BOARD_SIZE;
+pub const MAX_NAME_LEN: usize = 16;
+pub const MAX_CHAT_LEN: usize = 200;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Snapshot {
+    pub player: u8,
+    pub turn: u8,
+    pub mouse: (u8, u8),
+    pub board: Board,
+}
+
+pub const MAX_MATCH_GAMES: u8 = 15;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RoomOptions {
+    pub time_control: TimeControl,
+    pub games: u8,
+    pub variant: Variant,
+}
+
+impl Default for RoomOptions {
+    fn default() -> Self {
+        RoomOptions {
+            time_control: TimeControl::default(),
+            games: 1,
+            variant: Variant::default(),
+        }
+    }
+}
+
+impl RoomOptions {
+    pub fn parse(text: &str) -> Result<Self, AppError> {
+        let mut options = RoomOptions::default();
+        for pair in text
+            .split(';')
+            .map(str::trim)
+            .filter(|pair| !pair.is_empty())
+        {
+            match pair.split_once('=') {
+                Some(("timp", value)) => options.time_control = TimeControl::parse(value)?,
+                Some(("meci", value)) => options.games = parse_games(value)?,
+                Some(("reguli", value)) => options.variant = Variant::parse(value)?,
+                _ => {
+                    return Err(AppError::ConfigError(format!(
+                        "optiune necunoscuta: '{}'",
+                        pair
+                    )))
+                }
+            }
+        }
+        Ok(options)
+    }
+
+    pub fn encode(&self) -> String {
+        let mut pairs = Vec::new();
+        if self.time_control != TimeControl::Unlimited {
+            pairs.push(format!("timp={}", self.time_control));
+        }
+        if self.games > 1 {
+            pairs.push(format!("meci={}", self.games));
+        }
+        if !self.variant.is_classic() {
+            pairs.push(format!("reguli={}", self.variant));
+        }
+        pairs.join(";")
+    }
+}
+
+pub fn parse_games(value: &str) -> Result<u8, AppError> {
+    match value.trim().parse::<u8>() {
+        Ok(games) if (1..=MAX_MATCH_GAMES).contains(&games) => Ok(games),
+        _ => Err(AppError::ConfigError(format!(
+            "numarul de jocuri din meci trebuie sa fie intre 1 si {}",
+            MAX_MATCH_GAMES
+        ))),
+    }
+}
+
+// Starea unui meci; scorul si castigatorul sunt din perspectiva celui care primeste mesajul.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MatchState {
+    pub game: u8,
+    pub games: u8,
+    pub score: [u8; 2],
+    pub finished: bool,
+    pub winner: u8,
+    pub tiebreak: bool,
+}
+
+// Primul mesaj al clientului: codul camerei, urmat optional de nume, bilet si optiuni.
+#[derive(Clone, Debug, PartialEq)]
+pub struct JoinRequest {
+    pub code: i32,
+    pub name: String,
+    pub ticket: String,
+    pub options: RoomOptions,
+}
+
+impl JoinRequest {
+    pub fn parse(text: &str) -> Result<Self, AppError> {
+        let (code, rest) = text.split_once(';').unwrap_or((text, ""));
+        let code = code.trim().parse::<i32>().map_err(|_| {
+            AppError::UnexpectedResponse(format!("cod de camera invalid: '{}'", code))
+        })?;
+
+        let mut name = String::new();
+        let mut ticket = String::new();
+        let mut options = Vec::new();
+        for pair in rest.split(';') {
+            match pair.trim().split_once('=') {
+                Some(("nume", value)) => name = clean_name(value),
+                Some(("bilet", value)) => ticket = value.trim().to_string(),
+                _ => options.push(pair),
+            }
+        }
+        Ok(JoinRequest {
+            code,
+            name,
+            ticket,
+            options: RoomOptions::parse(&options.join(";"))?,
+        })
+    }
+
+    pub fn encode(&self) -> String {
+        let mut text = self.code.to_string();
+        let name = clean_name(&self.name);
+        if !name.is_empty() {
+            text.push_str(&format!(";nume={}", name));
+        }
+        if !self.ticket.is_empty() {
+            text.push_str(&format!(";bilet={}", self.ticket));
+        }
+        let options = self.options.encode();
+        if !options.is_empty() {
+            text.push(';');
+            text.push_str(&options);
+        }
+        text
+    }
+}
+
+// Scoate caracterele de control si taie textul la MAX_CHAT_LEN octeti.
+pub fn clean_chat(text: &str) -> String {
+    let mut cleaned = String::new();
+    for c in text.trim().chars().filter(|c| !c.is_control()) {
+        if cleaned.len() + c.len_utf8() > MAX_CHAT_LEN {
+            break;
+        }
+        cleaned.push(c);
+    }
+    cleaned
+}
+
+pub fn clean_name(name: &str) -> String {
+    name.trim()
+        .chars()
+        .filter(|c| !c.is_control() && *c != ';')
+        .take(MAX_NAME_LEN)
+        .collect()
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Message {
+    Ok,
+    Move(u8, u8),
+    Win,
+    Lose,
+    Left,
+    Waiting,
+    Back,
+    Ping(u32),
+    Pong(u32),
+    Token(String),
+    Snapshot(Snapshot),
+    Clock([u32; 2], u8),
+    Name(u8, String),
+    Over(u8),
+    Chat { from: String, text: String },
+    Rematch(bool),
+    Score([u8; 2]),
+    Match(MatchState),
+    Tournament(TournamentView),
+    Assigned(i8, String),
+    StartTournament,
+    Takeback(bool),
+    // Regulile jocului si cate ziduri mai sunt de pus in tura curenta.
+    Rules(Variant, u8),
+}
+
+impl Message {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            Message::Ok => b"ok".to_vec(),
+            Message::Move(row, col) => vec![*row, *col],
+            Message::Win => b"wi".to_vec(),
+            Message::Lose => b"lo".to_vec(),
+            Message::Left => b"ye".to_vec(),
+            Message::Waiting => b"wa".to_vec(),
+            Message::Back => b"ba".to_vec(),
+            Message::Ping(seq) => [b"pi".as_slice(), &seq.to_be_bytes()].concat(),
+            Message::Pong(seq) => [b"po".as_slice(), &seq.to_be_bytes()].concat(),
+            Message::Token(token) => with_text(b"tk", token),
+            Message::Snapshot(snapshot) => {
+                let mut bytes = b"sn".to_vec();
+                bytes.extend_from_slice(&[
+                    snapshot.player,
+                    snapshot.turn,
+                    snapshot.mouse.0,
+                    snapshot.mouse.1,
+                ]);
+                bytes.extend(snapshot.board.iter().flatten());
+                bytes
+            }
+            Message::Clock(remaining, turn) => {
+                let mut bytes = b"cl".to_vec();
+                bytes.extend_from_slice(&remaining[0].to_be_bytes());
+                bytes.extend_from_slice(&remaining[1].to_be_bytes());
+                bytes.push(*turn);
+                bytes
+            }
+            Message::Name(role, name) => [b"nm".as_slice(), &with_text(&[*role], name)].concat(),
+            Message::Over(winner) => vec![b'g', b'o', *winner],
+            Message::Rematch(wanted) => vec![b'r', b'm', *wanted as u8],
+            Message::Takeback(wanted) => vec![b't', b'b', *wanted as u8],
+            Message::Rules(variant, walls_left) => vec![b'r', b'u', variant.to_byte(), *walls_left],
+            Message::Score(score) => vec![b's', b'c', score[0], score[1]],
+            Message::Match(state) => vec![
+                b'm',
+                b'a',
+                state.game,
+                state.games,
+                state.score[0],
+                state.score[1],
+                state.finished as u8,
+                state.winner,
+                state.tiebreak as u8,
+            ],
+            Message::Chat { from, text } => [with_text(b"ch", from), with_text(&[], text)].concat(),
+            Message::Tournament(view) => {
+                let view = view.encode();
+                let mut bytes = b"tv".to_vec();
+                bytes.extend_from_slice(&(view.len() as u16).to_be_bytes());
+                bytes.extend(view);
+                bytes
+            }
+            Message::Assigned(room, ticket) => {
+                [b"ta".as_slice(), &with_text(&[*room as u8], ticket)].concat()
+            }
+            Message::StartTournament => b"ts".to_vec(),
+        }
+    }
+
+    // Intoarce mesajul si cati octeti a folosit, sau None daca nu a sosit tot.
+    pub fn parse(buffer: &[u8]) -> Result<Option<(Message, usize)>, AppError> {
+        if buffer.len() < 2 {
+            return Ok(None);
+        }
+        let payload = &buffer[2..];
+        let message = match (buffer[0], buffer[1]) {
+            (row, col) if (row as usize) < BOARD_SIZE && (col as usize) < BOARD_SIZE => {
+                Message::Move(row, col)
+            }
+            (b'o', b'k') => Message::Ok,
+            (b'w', b'i') => Message::Win,
+            (b'l', b'o') => Message::Lose,
+            (b'y', b'e') => Message::Left,
+            (b'w', b'a') => Message::Waiting,
+            (b'b', b'a') => Message::Back,
+            (b'p', b'i') | (b'p', b'o') => {
+                if payload.len() < 4 {
+                    return Ok(None);
+                }
+                let seq = u32::from_be_bytes([payload[0], payload[1], payload[2], payload[3]]);
+                let message = if buffer[1] == b'i' {
+                    Message::Ping(seq)
+                } else {
+                    Message::Pong(seq)
+                };
+                return Ok(Some((message, 6)));
+            }
+            (b't', b'k') => {
+                return Ok(parse_text(payload).map(|(token, used)| (Message::Token(token), used)))
+            }
+            (b'n', b'm') => {
+                let role = match payload.first() {
+                    Some(role) => *role,
+                    None => return Ok(None),
+                };
+                return Ok(parse_text(&payload[1..])
+                    .map(|(name, used)| (Message::Name(role, name), used + 1)));
+            }
+            (b'c', b'h') => {
+                let (from, used) = match parse_text(payload) {
+                    Some(parsed) => parsed,
+                    None => return Ok(None),
+                };
+                return Ok(parse_text(&buffer[used..])
+                    .map(|(text, rest)| (Message::Chat { from, text }, used + rest - 2)));
+            }
+            (b't', b's') => Message::StartTournament,
+            (b't', b'v') => {
+                if payload.len() < 2 {
+                    return Ok(None);
+                }
+                let len = u16::from_be_bytes([payload[0], payload[1]]) as usize;
+                let view = match payload.get(2..2 + len) {
+                    Some(view) => view,
+                    None => return Ok(None),
+                };
+                return match TournamentView::decode(view) {
+                    Some(view) => Ok(Some((Message::Tournament(view), 4 + len))),
+                    None => Err(AppError::UnexpectedResponse(String::from(
+                        "stare de turneu invalida",
+                    ))),
+                };
+            }
+            (b't', b'a') => {
+                let room = match payload.first() {
+                    Some(room) => *room as i8,
+                    None => return Ok(None),
+                };
+                return Ok(parse_text(&payload[1..])
+                    .map(|(ticket, used)| (Message::Assigned(room, ticket), used + 1)));
+            }
+            (b'r', b'm') => {
+                return Ok(payload
+                    .first()
+                    .map(|wanted| (Message::Rematch(*wanted != 0), 3)));
+            }
+            (b't', b'b') => {
+                return Ok(payload
+                    .first()
+                    .map(|wanted| (Message::Takeback(*wanted != 0), 3)));
+            }
+            (b'r', b'u') => {
+                if payload.len() < 2 {
+                    return Ok(None);
+                }
+                let variant = Variant::from_byte(payload[0]);
+                return Ok(Some((Message::Rules(variant, payload[1]), 4)));
+            }
+            (b'm', b'a') => {
+                if payload.len() < 7 {
+                    return Ok(None);
+                }
+                return Ok(Some((
+                    Message::Match(MatchState {
+                        game: payload[0],
+                        games: payload[1],
+                        score: [payload[2], payload[3]],
+                        finished: payload[4] != 0,
+                        winner: payload[5],
+                        tiebreak: payload[6] != 0,
+                    }),
+                    9,
+                )));
+            }
+            (b's', b'c') => {
+                if payload.len() < 2 {
+                    return Ok(None);
+                }
+                return Ok(Some((Message::Score([payload[0], payload[1]]), 4)));
+            }
+            (b'g', b'o') => {
+                return Ok(payload.first().map(|winner| (Message::Over(*winner), 3)));
+            }
+            (b'c', b'l') => {
+                if payload.len() < 9 {
+                    return Ok(None);
+                }
+                let mouse = u32::from_be_bytes([payload[0], payload[1], payload[2], payload[3]]);
+                let walls = u32::from_be_bytes([payload[4], payload[5], payload[6], payload[7]]);
+                return Ok(Some((Message::Clock([mouse, walls], payload[8]), 11)));
+            }
+            (b's', b'n') => {
+                if payload.len() < SNAPSHOT_LEN {
+                    return Ok(None);
+                }
+                return Ok(Some((
+                    Message::Snapshot(parse_snapshot(&payload[..SNAPSHOT_LEN])),
+                    2 + SNAPSHOT_LEN,
+                )));
+            }
+            (a, b) => {
+                return Err(AppError::UnexpectedResponse(format!(
+                    "mesaj necunoscut: {}, {}",
+                    a, b
+                )))
+            }
+        };
+        Ok(Some((message, 2)))
+    }
+
+    pub fn read_from(stream: &mut impl Read) -> Result<Message, AppError> {
+        let mut buffer = vec![0; 2];
+        read_exact(stream, &mut buffer)?;
+        loop {
+            let needed = message_len(&buffer);
+            if buffer.len() >= needed {
+                return match Message::parse(&buffer)? {
+                    Some((message, _)) => Ok(message),
+                    None => Err(AppError::UnexpectedResponse(String::from(
+                        "mesaj incomplet",
+                    ))),
+                };
+            }
+            let start = buffer.len();
+            buffer.resize(needed, 0);
+            read_exact(stream, &mut buffer[start..])?;
+        }
+    }
+}
+
+fn message_len(buffer: &[u8]) -> usize {
+    match (buffer[0], buffer[1]) {
+        (b't', b'k') if buffer.len() < 3 => 3,
+        (b't', b'k') => 3 + buffer[2] as usize,
+        (b's', b'n') => 2 + SNAPSHOT_LEN,
+        (b'p', b'i') | (b'p', b'o') => 6,
+        (b'c', b'l') => 11,
+        (b'n', b'm') if buffer.len() < 4 => 4,
+        (b'n', b'm') => 4 + buffer[3] as usize,
+        (b'g', b'o') | (b'r', b'm') | (b't', b'b') => 3,
+        (b's', b'c') | (b'r', b'u') => 4,
+        (b'm', b'a') => 9,
+        (b't', b'v') if buffer.len() < 4 => 4,
+        (b't', b'v') => 4 + u16::from_be_bytes([buffer[2], buffer[3]]) as usize,
+        (b't', b'a') if buffer.len() < 4 => 4,
+        (b't', b'a') => 4 + buffer[3] as usize,
+        (b'c', b'h') if buffer.len() < 3 => 3,
+        (b'c', b'h') if buffer.len() < 4 + buffer[2] as usize => 4 + buffer[2] as usize,
+        (b'c', b'h') => 4 + buffer[2] as usize + buffer[3 + buffer[2] as usize] as usize,
+        _ => 2,
+    }
+}
+
+fn with_text(opcode: &[u8], text: &str) -> Vec<u8> {
+    let text = text.as_bytes();
+    let len = text.len().min(u8::MAX as usize);
+    let mut bytes = opcode.to_vec();
+    bytes.push(len as u8);
+    bytes.extend_from_slice(&text[..len]);
+    bytes
+}
+
+fn parse_text(payload: &[u8]) -> Option<(String, usize)> {
+    let len = *payload.first()? as usize;
+    let text = payload.get(1..1 + len)?;
+    Some((String::from_utf8_lossy(text).to_string(), 3 + len))
+}
+
+fn parse_snapshot(payload: &[u8]) -> Snapshot {
+    let mut board: Board = [[0; BOARD_SIZE]; BOARD_SIZE];
+    for (index, cell) in payload[4..].iter().enumerate() {
+        board[index / BOARD_SIZE][index % BOARD_SIZE] = *cell;
+    }
+    Snapshot {
+        player: payload[0],
+        turn: payload[1],
+        mouse: (payload[2], payload[3]),
+        board,
+    }
+}
+
+fn read_exact(stream: &mut impl Read, buffer: &mut [u8]) -> Result<(), AppError> {
+    stream
+        .read_exact(buffer)
+        .map_err(|e| AppError::ReadError(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tournament::{Format, Outcome, Pairing, Standing, Status};
+    use std::time::Duration;
+
+    fn every_message() -> Vec<Message> {
+        let mut board = [[0; BOARD_SIZE]; BOARD_SIZE];
+        board[5][5] = 1;
+        board[0][3] = 2;
+        board[10][10] = 2;
+        let view = TournamentView {
+            id: 3,
+            format: Format::Swiss,
+            status: Status::Running,
+            round: 2,
+            rounds: 5,
+            you: 1,
+            options: RoomOptions::parse("timp=banca:300+5;meci=3").unwrap(),
+            players: vec![
+                Standing {
+                    name: String::from("ana"),
+                    points: 4,
+                    played: 2,
+                    buchholz: 513,
+                },
+                Standing {
+                    name: String::from("bogdan"),
+                    points: 0,
+                    played: 2,
+                    buchholz: 0,
+                },
+            ],
+            pairings: vec![Pairing {
+                first: 0,
+                second: 1,
+                room: 7,
+                outcome: Outcome::Draw,
+            }],
+        };
+        vec![
+            Message::Ok,
+            Message::Move(0, 0),
+            Message::Move(10, 4),
+            Message::Win,
+            Message::Lose,
+            Message::Left,
+            Message::Waiting,
+            Message::Back,
+            Message::Ping(0),
+            Message::Pong(u32::MAX),
+            Message::Token(String::new()),
+            Message::Token("x".repeat(255)),
+            Message::Snapshot(Snapshot {
+                player: 2,
+                turn: 1,
+                mouse: (5, 5),
+                board,
+            }),
+            Message::Clock([90_000, 1], 2),
+            Message::Name(1, String::from("soricel")),
+            Message::Name(2, String::new()),
+            Message::Over(1),
+            Message::Chat {
+                from: String::from("ana"),
+                text: String::from("buna, noroc!"),
+            },
+            Message::Chat {
+                from: String::new(),
+                text: String::new(),
+            },
+            Message::Rematch(true),
+            Message::Rematch(false),
+            Message::Score([3, 12]),
+            Message::Match(MatchState {
+                game: 4,
+                games: 5,
+                score: [2, 1],
+                finished: true,
+                winner: 1,
+                tiebreak: true,
+            }),
+            Message::Tournament(view),
+            Message::Tournament(TournamentView::default()),
+            Message::Assigned(12, String::from("a1b2c3")),
+            Message::Assigned(-1, String::new()),
+            Message::StartTournament,
+            Message::Takeback(true),
+            Message::Takeback(false),
+            Message::Rules(Variant::parse("salt,poarta").unwrap(), 2),
+        ]
+    }
+
+    #[test]
+    fn every_message_parses_back_from_its_bytes() {
+        for message in every_message() {
+            let bytes = message.to_bytes();
+            assert_eq!(
+                Message::parse(&bytes).unwrap(),
+                Some((message.clone(), bytes.len())),
+                "{:?}",
+                message
+            );
+            // Cu alt mesaj lipit dupa, se consuma doar octetii primului.
+            let joined = [bytes.clone(), Message::Ok.to_bytes()].concat();
+            assert_eq!(
+                Message::parse(&joined).unwrap(),
+                Some((message, bytes.len()))
+            );
+        }
+    }
+
+    #[test]
+    fn incomplete_messages_wait_for_more_bytes() {
+        for message in every_message() {
+            let bytes = message.to_bytes();
+            for end in 0..bytes.len() {
+                assert_eq!(
+                    Message::parse(&bytes[..end]).unwrap(),
+                    None,
+                    "{:?} taiat la {}",
+                    message,
+                    end
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn stream_reads_one_message_at_a_time() {
+        let messages = every_message();
+        let bytes: Vec<u8> = messages.iter().flat_map(Message::to_bytes).collect();
+        let mut stream = &bytes[..];
+        for message in messages {
+            assert_eq!(Message::read_from(&mut stream).unwrap(), message);
+        }
+        assert!(stream.is_empty());
+        assert!(Message::read_from(&mut stream).is_err());
+    }
+
+    #[test]
+    fn unknown_opcode_is_an_error() {
+        assert!(Message::parse(b"zz").is_err());
+        assert!(Message::read_from(&mut &b"zz"[..]).is_err());
+    }
+
+    #[test]
+    fn room_options_round_trip() {
+        let options = RoomOptions {
+            time_control: TimeControl::Bank {
+                initial: Duration::from_secs(600),
+                increment: Duration::from_secs(10),
+            },
+            games: 7,
+            variant: Variant::parse("ziduri2,gauri").unwrap(),
+        };
+        assert_eq!(RoomOptions::parse(&options.encode()).unwrap(), options);
+        assert_eq!(RoomOptions::default().encode(), "");
+        assert_eq!(RoomOptions::parse("").unwrap(), RoomOptions::default());
+        assert!(RoomOptions::parse("meci=0").is_err());
+        assert!(RoomOptions::parse("culoare=rosu").is_err());
+    }
+
+    #[test]
+    fn join_request_round_trip() {
+        let request = JoinRequest {
+            code: 4,
+            name: String::from("ana"),
+            ticket: String::from("f00d"),
+            options: RoomOptions::parse("timp=mutare:30;reguli=salt").unwrap(),
+        };
+        assert_eq!(JoinRequest::parse(&request.encode()).unwrap(), request);
+
+        let bare = JoinRequest::parse("-1").unwrap();
+        assert_eq!(bare.code, -1);
+        assert_eq!(bare.encode(), "-1");
+        assert!(JoinRequest::parse("camera").is_err());
+    }
+}
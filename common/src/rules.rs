@@ -0,0 +1,609 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::{collections::HashSet, fmt};
+
+use crate::{AppError, Position};
+
+pub const BOARD_SIZE: usize = 11;
+pub const INITIAL_WALLS: usize = 10;
+
+pub type Board = [[u8; BOARD_SIZE]; BOARD_SIZE];
+
+// Celulele din variante: gaurile le poate sari doar soarecele, poarta e singura iesire.
+pub const HOLE: u8 = 3;
+pub const GATE: u8 = 4;
+const HOLES: usize = 6;
+const GATE_WIDTH: usize = 3;
+
+pub fn neighbours(x: usize, y: usize) -> Vec<(usize, usize)> {
+    neighbours_on(x, y, BOARD_SIZE)
+}
+
+// Vecinii pe o tabla de latura `size`; tablele mici folosesc aceeasi geometrie.
+pub fn neighbours_on(x: usize, y: usize, size: usize) -> Vec<(usize, usize)> {
+    let directions: [(isize, isize); 6] = if x.is_multiple_of(2) {
+        [(-1, -1), (-1, 0), (0, -1), (0, 1), (1, -1), (1, 0)]
+    } else {
+        [(-1, 0), (-1, 1), (0, -1), (0, 1), (1, 0), (1, 1)]
+    };
+
+    directions
+        .iter()
+        .map(|&(dx, dy)| (x as isize + dx, y as isize + dy))
+        .filter(|&(nx, ny)| nx >= 0 && ny >= 0 && (nx as usize) < size && (ny as usize) < size)
+        .map(|(nx, ny)| (nx as usize, ny as usize))
+        .collect()
+}
+
+pub fn is_border(x: usize, y: usize) -> bool {
+    is_border_on(x, y, BOARD_SIZE)
+}
+
+pub fn is_border_on(x: usize, y: usize, size: usize) -> bool {
+    x == 0 || y == 0 || x == size - 1 || y == size - 1
+}
+
+pub fn check_move(r: usize, c: usize, mouse_x: u8, mouse_y: u8, board: &Board) -> bool {
+    if r >= BOARD_SIZE || c >= BOARD_SIZE || board[r][c] != 0 {
+        return false;
+    }
+    neighbours(mouse_x as usize, mouse_y as usize).contains(&(r, c))
+}
+
+// Celulele pe care le poate alege rolul la mutare: vecinii liberi pentru soarece, orice celula libera pentru ziduri.
+pub fn legal_moves(board: &Board, mouse: &Position, role: u8) -> Vec<(usize, usize)> {
+    if role == 1 {
+        return neighbours(mouse.x, mouse.y)
+            .into_iter()
+            .filter(|&(x, y)| board[x][y] == 0)
+            .collect();
+    }
+    (0..BOARD_SIZE)
+        .flat_map(|x| (0..BOARD_SIZE).map(move |y| (x, y)))
+        .filter(|&(x, y)| board[x][y] == 0)
+        .collect()
+}
+
+pub fn apply_move(board: &mut Board, mouse: &mut Position, role: u8, (x, y): (usize, usize)) {
+    if role == 1 {
+        board[mouse.x][mouse.y] = 0;
+        board[x][y] = 1;
+        *mouse = Position::new(x, y);
+    } else {
+        board[x][y] = 2;
+    }
+}
+
+pub fn check_any_left_move(board: &Board, mouse: &Position) -> bool {
+    neighbours(mouse.x, mouse.y)
+        .iter()
+        .any(|&(x, y)| board[x][y] == 0)
+}
+
+pub fn generate_mouse_pos() -> (usize, usize) {
+    generate_mouse_pos_with(&mut rand::thread_rng())
+}
+
+pub fn generate_mouse_pos_with(rng: &mut impl Rng) -> (usize, usize) {
+    let row = rng.gen_range(3..=7);
+    let col = rng.gen_range(3..=7);
+    (row, col)
+}
+
+pub fn generate_wall_positions(mouse_pos: (u8, u8), count: usize) -> Vec<(u8, u8)> {
+    generate_wall_positions_with(&mut rand::thread_rng(), mouse_pos, count)
+}
+
+pub fn generate_wall_positions_with(
+    rng: &mut impl Rng,
+    mouse_pos: (u8, u8),
+    count: usize,
+) -> Vec<(u8, u8)> {
+    let mut positions = HashSet::new();
+
+    while positions.len() < count {
+        let row = rng.gen_range(0..BOARD_SIZE);
+        let col = rng.gen_range(0..BOARD_SIZE);
+        let pos = (row as u8, col as u8);
+
+        if pos == mouse_pos {
+            continue;
+        }
+        positions.insert(pos);
+    }
+
+    positions.into_iter().collect()
+}
+
+// Pozitia de start data de o samanta, ca partidele sa poata fi reluate.
+pub fn seeded_start(seed: u64, walls: usize) -> (Board, Position) {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let (row, col) = generate_mouse_pos_with(&mut rng);
+    let walls = generate_wall_positions_with(&mut rng, (row as u8, col as u8), walls);
+    (new_board((row, col), &walls), Position::new(row, col))
+}
+
+pub fn new_board(mouse: (usize, usize), walls: &[(u8, u8)]) -> Board {
+    let mut board: Board = [[0; BOARD_SIZE]; BOARD_SIZE];
+    board[mouse.0][mouse.1] = 1;
+    for &(r, c) in walls {
+        board[r as usize][c as usize] = 2;
+    }
+    board
+}
+
+const CELLS: usize = BOARD_SIZE * BOARD_SIZE;
+// Numerele fixe din cheile Zobrist: randul 0 pentru soarece, randul 1 pentru ziduri.
+const ZOBRIST_KEYS: [[u64; CELLS]; 2] = zobrist_keys();
+const ZOBRIST_WALLS_TO_MOVE: u64 = 0x2d35_8dcc_aa6c_78a5;
+
+// Splitmix64 la compilare, ca aceeasi pozitie sa aiba aceeasi cheie in orice proces.
+const fn zobrist_keys() -> [[u64; CELLS]; 2] {
+    let mut keys = [[0; CELLS]; 2];
+    let mut state: u64 = 0x7472_6170_6d6f_7573;
+    let mut index = 0;
+    while index < 2 * CELLS {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        keys[index / CELLS][index % CELLS] = z ^ (z >> 31);
+        index += 1;
+    }
+    keys
+}
+
+// Cheia Zobrist a pozitiei: zidurile, soarecele si cine muta, strinse cu xor. Gaurile si poarta
+// nu intra in cheie, asa ca motoarele si tabelele de transpozitii vad doar table clasice.
+pub fn zobrist(board: &Board, to_move: u8) -> u64 {
+    debug_assert!(
+        board.iter().flatten().all(|&cell| cell < HOLE),
+        "cheia Zobrist e doar pentru tabla clasica"
+    );
+    let mut key = if to_move == 2 {
+        ZOBRIST_WALLS_TO_MOVE
+    } else {
+        0
+    };
+    for (x, row) in board.iter().enumerate() {
+        for (y, &cell) in row.iter().enumerate() {
+            if cell == 1 || cell == 2 {
+                key ^= ZOBRIST_KEYS[cell as usize - 1][x * BOARD_SIZE + y];
+            }
+        }
+    }
+    key
+}
+
+// Cheia dupa mutare, fara sa recitim tabla; `mouse` e pozitia dinaintea mutarii.
+pub fn zobrist_after(key: u64, mouse: &Position, role: u8, (x, y): (usize, usize)) -> u64 {
+    let key = key ^ ZOBRIST_WALLS_TO_MOVE;
+    if role == 1 {
+        key ^ ZOBRIST_KEYS[0][mouse.x * BOARD_SIZE + mouse.y] ^ ZOBRIST_KEYS[0][x * BOARD_SIZE + y]
+    } else {
+        key ^ ZOBRIST_KEYS[1][x * BOARD_SIZE + y]
+    }
+}
+
+// Regulile alese la crearea camerei; Variant::default() e jocul clasic.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Variant {
+    // Zidurile pun doua ziduri pe tura.
+    pub double_walls: bool,
+    // Soarecele poate merge si doua celule, prin una libera.
+    pub long_jump: bool,
+    // Gauri puse la inceput, pe care soarecele le sare in linie dreapta.
+    pub holes: bool,
+    // Soarecele iese doar pe poarta; restul marginii e tabla obisnuita.
+    pub gate: bool,
+}
+
+impl Variant {
+    pub fn parse(spec: &str) -> Result<Self, AppError> {
+        let mut variant = Variant::default();
+        for rule in spec
+            .split(',')
+            .map(str::trim)
+            .filter(|rule| !rule.is_empty())
+        {
+            match rule {
+                "clasic" => {}
+                "ziduri2" => variant.double_walls = true,
+                "salt" => variant.long_jump = true,
+                "gauri" => variant.holes = true,
+                "poarta" => variant.gate = true,
+                _ => {
+                    return Err(AppError::ConfigError(format!(
+                        "regula necunoscuta '{}' (ziduri2, salt, gauri, poarta)",
+                        rule
+                    )))
+                }
+            }
+        }
+        Ok(variant)
+    }
+
+    // Pe fir regulile merg ca un octet, cate un bit pentru fiecare.
+    pub fn to_byte(&self) -> u8 {
+        self.double_walls as u8
+            | (self.long_jump as u8) << 1
+            | (self.holes as u8) << 2
+            | (self.gate as u8) << 3
+    }
+
+    pub fn from_byte(byte: u8) -> Self {
+        Variant {
+            double_walls: byte & 1 != 0,
+            long_jump: byte & 2 != 0,
+            holes: byte & 4 != 0,
+            gate: byte & 8 != 0,
+        }
+    }
+
+    pub fn is_classic(&self) -> bool {
+        *self == Variant::default()
+    }
+
+    pub fn walls_per_turn(&self) -> u8 {
+        if self.double_walls {
+            2
+        } else {
+            1
+        }
+    }
+
+    // Celula pe care soarecele castiga; pe poarta o recunoastem dupa tabla de dinaintea mutarii.
+    pub fn is_exit(&self, board: &Board, (x, y): (usize, usize)) -> bool {
+        if self.gate {
+            board[x][y] == GATE
+        } else {
+            is_border(x, y)
+        }
+    }
+
+    pub fn mouse_moves(&self, board: &Board, mouse: &Position) -> Vec<(usize, usize)> {
+        let from = (mouse.x, mouse.y);
+        let open = |(x, y): (usize, usize)| board[x][y] == 0 || board[x][y] == GATE;
+        let mut moves = Vec::new();
+        let add = |cell: (usize, usize), moves: &mut Vec<(usize, usize)>| {
+            if cell != from && !moves.contains(&cell) {
+                moves.push(cell);
+            }
+        };
+        for cell in neighbours(from.0, from.1) {
+            if open(cell) {
+                add(cell, &mut moves);
+            } else if self.holes && board[cell.0][cell.1] == HOLE {
+                if let Some(landing) = beyond(from, cell).filter(|&landing| open(landing)) {
+                    add(landing, &mut moves);
+                }
+            }
+            // Pe poarta jocul se termina, deci nu trecem mai departe prin ea.
+            if self.long_jump && board[cell.0][cell.1] == 0 && !self.is_exit(board, cell) {
+                for next in neighbours(cell.0, cell.1) {
+                    if open(next) {
+                        add(next, &mut moves);
+                    }
+                }
+            }
+        }
+        moves
+    }
+
+    pub fn legal_moves(&self, board: &Board, mouse: &Position, role: u8) -> Vec<(usize, usize)> {
+        if role == 1 {
+            self.mouse_moves(board, mouse)
+        } else {
+            legal_moves(board, mouse, 2)
+        }
+    }
+
+    pub fn is_trapped(&self, board: &Board, mouse: &Position) -> bool {
+        self.mouse_moves(board, mouse).is_empty()
+    }
+
+    // Pune gaurile si poarta peste o tabla de start clasica.
+    pub fn prepare(&self, board: &mut Board, mouse: &Position, rng: &mut impl Rng) {
+        if self.gate {
+            let start = rng.gen_range(1..BOARD_SIZE - GATE_WIDTH);
+            let last = BOARD_SIZE - 1;
+            let side = rng.gen_range(0..4);
+            for offset in start..start + GATE_WIDTH {
+                let (x, y) = match side {
+                    0 => (0, offset),
+                    1 => (last, offset),
+                    2 => (offset, 0),
+                    _ => (offset, last),
+                };
+                board[x][y] = GATE;
+            }
+        }
+        if self.holes {
+            let near = neighbours(mouse.x, mouse.y);
+            let mut placed = 0;
+            while placed < HOLES {
+                let (x, y) = (
+                    rng.gen_range(1..BOARD_SIZE - 1),
+                    rng.gen_range(1..BOARD_SIZE - 1),
+                );
+                if board[x][y] == 0 && !near.contains(&(x, y)) {
+                    board[x][y] = HOLE;
+                    placed += 1;
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for Variant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rules: Vec<&str> = [
+            (self.double_walls, "ziduri2"),
+            (self.long_jump, "salt"),
+            (self.holes, "gauri"),
+            (self.gate, "poarta"),
+        ]
+        .into_iter()
+        .filter_map(|(on, name)| on.then_some(name))
+        .collect();
+        if rules.is_empty() {
+            write!(f, "clasic")
+        } else {
+            write!(f, "{}", rules.join(","))
+        }
+    }
+}
+
+// Celula de dincolo de `through`, in linie dreapta de la `from`. Trecem prin coordonatele
+// axiale, unde directiile nu mai depind de paritatea randului.
+fn beyond(from: (usize, usize), through: (usize, usize)) -> Option<(usize, usize)> {
+    let axial =
+        |(x, y): (usize, usize)| (x as isize, y as isize - (x as isize - (x & 1) as isize) / 2);
+    let (fr, fq) = axial(from);
+    let (tr, tq) = axial(through);
+    let (r, q) = (2 * tr - fr, 2 * tq - fq);
+    let col = q + (r - (r & 1)) / 2;
+    (r >= 0 && col >= 0 && (r as usize) < BOARD_SIZE && (col as usize) < BOARD_SIZE)
+        .then_some((r as usize, col as usize))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn board_with_mouse(mouse: (usize, usize)) -> (Board, Position) {
+        let mut board: Board = [[0; BOARD_SIZE]; BOARD_SIZE];
+        board[mouse.0][mouse.1] = 1;
+        (board, Position::new(mouse.0, mouse.1))
+    }
+
+    fn sorted(mut cells: Vec<(usize, usize)>) -> Vec<(usize, usize)> {
+        cells.sort();
+        cells
+    }
+
+    #[test]
+    fn parse_accepts_classic_and_rule_lists() {
+        assert_eq!(Variant::parse("").unwrap(), Variant::default());
+        assert_eq!(Variant::parse("clasic").unwrap(), Variant::default());
+        let variant = Variant::parse(" salt , poarta,").unwrap();
+        assert_eq!(
+            variant,
+            Variant {
+                long_jump: true,
+                gate: true,
+                ..Variant::default()
+            }
+        );
+        assert!(Variant::parse("salt,zbor").is_err());
+    }
+
+    #[test]
+    fn text_and_byte_forms_round_trip() {
+        for byte in 0..16 {
+            let variant = Variant::from_byte(byte);
+            assert_eq!(variant.to_byte(), byte);
+            assert_eq!(Variant::parse(&variant.to_string()).unwrap(), variant);
+        }
+        assert_eq!(Variant::default().to_string(), "clasic");
+    }
+
+    #[test]
+    fn classic_moves_match_legal_moves() {
+        for seed in 0..50 {
+            let (board, mouse) = seeded_start(seed, INITIAL_WALLS);
+            let classic = Variant::default();
+            assert_eq!(
+                sorted(classic.mouse_moves(&board, &mouse)),
+                sorted(legal_moves(&board, &mouse, 1))
+            );
+            assert_eq!(
+                classic.is_trapped(&board, &mouse),
+                !check_any_left_move(&board, &mouse)
+            );
+        }
+    }
+
+    #[test]
+    fn long_jump_reaches_the_second_ring_through_free_cells() {
+        let salt = Variant::parse("salt").unwrap();
+        let (mut board, mouse) = board_with_mouse((5, 5));
+        assert_eq!(salt.mouse_moves(&board, &mouse).len(), 18);
+
+        // Fara vecini liberi nu mai are prin ce sari.
+        for (x, y) in neighbours(5, 5) {
+            board[x][y] = 2;
+        }
+        assert!(salt.is_trapped(&board, &mouse));
+
+        // Printr-un singur vecin liber ajunge doar in vecinii acestuia.
+        board[5][6] = 0;
+        let mut expected: Vec<_> = neighbours(5, 6)
+            .into_iter()
+            .filter(|&(x, y)| board[x][y] == 0)
+            .collect();
+        expected.push((5, 6));
+        assert_eq!(sorted(salt.mouse_moves(&board, &mouse)), sorted(expected));
+    }
+
+    #[test]
+    fn long_jump_does_not_pass_through_an_exit() {
+        let salt = Variant::parse("salt").unwrap();
+        let (mut board, mouse) = board_with_mouse((1, 1));
+        for (x, y) in neighbours(1, 1) {
+            if !is_border(x, y) {
+                board[x][y] = 2;
+            }
+        }
+        let moves = salt.mouse_moves(&board, &mouse);
+        assert!(moves.iter().all(|&(x, y)| is_border(x, y)));
+    }
+
+    #[test]
+    fn holes_are_jumped_in_a_straight_line() {
+        let gauri = Variant::parse("gauri").unwrap();
+        let (mut board, mouse) = board_with_mouse((5, 5));
+        for (x, y) in neighbours(5, 5) {
+            board[x][y] = 2;
+        }
+        // Randul 5 e impar: (5, 6) e la dreapta, iar dincolo de el e (5, 7).
+        board[5][6] = HOLE;
+        assert_eq!(gauri.mouse_moves(&board, &mouse), vec![(5, 7)]);
+        // Pe diagonala: (4, 5) e sus-stanga fata de randul impar, iar de pe randul par
+        // sus-stanga inseamna si coloana anterioara.
+        board[5][6] = 2;
+        board[4][5] = HOLE;
+        assert_eq!(gauri.mouse_moves(&board, &mouse), vec![(3, 4)]);
+
+        // Zidul de dincolo de gaura blocheaza saritura, iar fara regula gaura e doar obstacol.
+        board[3][4] = 2;
+        assert!(gauri.is_trapped(&board, &mouse));
+        board[3][4] = 0;
+        assert!(Variant::default().is_trapped(&board, &mouse));
+    }
+
+    #[test]
+    fn hole_on_the_edge_leads_nowhere() {
+        let gauri = Variant::parse("gauri").unwrap();
+        let (mut board, mouse) = board_with_mouse((1, 5));
+        for (x, y) in neighbours(1, 5) {
+            board[x][y] = 2;
+        }
+        board[0][5] = HOLE;
+        assert!(gauri.is_trapped(&board, &mouse));
+    }
+
+    #[test]
+    fn only_the_gate_is_an_exit() {
+        let poarta = Variant::parse("poarta").unwrap();
+        let (mut board, mouse) = board_with_mouse((5, 5));
+        board[0][4] = GATE;
+        assert!(poarta.is_exit(&board, (0, 4)));
+        assert!(!poarta.is_exit(&board, (0, 5)));
+        assert!(!poarta.is_exit(&board, (5, 5)));
+        assert!(Variant::default().is_exit(&board, (0, 5)));
+
+        // Poarta e o celula pe care soarecele poate pasi, dar nu se poate zidi.
+        board[mouse.x][mouse.y] = 0;
+        board[1][4] = 1;
+        let mouse = Position::new(1, 4);
+        assert!(poarta.mouse_moves(&board, &mouse).contains(&(0, 4)));
+        assert!(!poarta.legal_moves(&board, &mouse, 2).contains(&(0, 4)));
+    }
+
+    #[test]
+    fn prepare_places_gate_and_holes_away_from_the_mouse() {
+        let variant = Variant::parse("gauri,poarta").unwrap();
+        for seed in 0..50 {
+            let (mut board, mouse) = seeded_start(seed, INITIAL_WALLS);
+            variant.prepare(&mut board, &mouse, &mut StdRng::seed_from_u64(seed));
+            let cells: Vec<_> = (0..BOARD_SIZE)
+                .flat_map(|x| (0..BOARD_SIZE).map(move |y| (x, y)))
+                .collect();
+            let gate: Vec<_> = cells
+                .iter()
+                .filter(|&&(x, y)| board[x][y] == GATE)
+                .collect();
+            let holes: Vec<_> = cells
+                .iter()
+                .filter(|&&(x, y)| board[x][y] == HOLE)
+                .collect();
+            assert_eq!(gate.len(), GATE_WIDTH);
+            assert!(gate.iter().all(|&&(x, y)| is_border(x, y)));
+            assert_eq!(holes.len(), HOLES);
+            assert!(holes.iter().all(
+                |&&(x, y)| !is_border(x, y) && !neighbours(mouse.x, mouse.y).contains(&(x, y))
+            ));
+            assert_eq!(board[mouse.x][mouse.y], 1);
+        }
+    }
+
+    // Joaca partide cu mutari aleatoare si compara cheia tinuta la zi cu cea recalculata.
+    #[test]
+    fn incremental_zobrist_matches_full_key_every_ply() {
+        for seed in 0..200 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let (mut board, mut mouse) = seeded_start(seed, rng.gen_range(0..=20));
+            let mut to_move = 1;
+            let mut key = zobrist(&board, to_move);
+            loop {
+                let moves = legal_moves(&board, &mouse, to_move);
+                if moves.is_empty() || is_border(mouse.x, mouse.y) {
+                    break;
+                }
+                let cell = moves[rng.gen_range(0..moves.len())];
+                key = zobrist_after(key, &mouse, to_move, cell);
+                apply_move(&mut board, &mut mouse, to_move, cell);
+                to_move = 3 - to_move;
+                assert_eq!(key, zobrist(&board, to_move), "samanta {}", seed);
+            }
+        }
+    }
+
+    // Aceeasi verificare pe partidele euristice din care bench-ul isi ia pozitiile.
+    #[test]
+    fn incremental_zobrist_matches_full_key_in_heuristic_games() {
+        for seed in 1..=30 {
+            let (mut board, mut mouse) = seeded_start(seed, INITIAL_WALLS);
+            let mut to_move = 1;
+            let mut key = zobrist(&board, to_move);
+            while let Some(cell) = crate::ai::computer_move(&board, &mouse, to_move) {
+                if is_border(mouse.x, mouse.y) {
+                    break;
+                }
+                key = zobrist_after(key, &mouse, to_move, cell);
+                apply_move(&mut board, &mut mouse, to_move, cell);
+                to_move = 3 - to_move;
+                assert_eq!(key, zobrist(&board, to_move), "samanta {}", seed);
+            }
+        }
+    }
+
+    // Zid, soarece, zid in doua ordini diferite: aceeasi pozitie, aceeasi cheie.
+    #[test]
+    fn transpositions_share_a_key() {
+        let (start, start_mouse) = board_with_mouse((5, 5));
+        let keys: Vec<u64> = [[(2, 2), (5, 6), (8, 7)], [(8, 7), (5, 6), (2, 2)]]
+            .iter()
+            .map(|line| {
+                let (mut board, mut mouse) = (start, start_mouse);
+                let mut key = zobrist(&board, 2);
+                for (&cell, role) in line.iter().zip([2, 1, 2]) {
+                    key = zobrist_after(key, &mouse, role, cell);
+                    apply_move(&mut board, &mut mouse, role, cell);
+                }
+                assert_eq!(key, zobrist(&board, 1));
+                key
+            })
+            .collect();
+        assert_eq!(keys[0], keys[1]);
+        assert_ne!(zobrist(&start, 1), zobrist(&start, 2));
+    }
+
+    #[test]
+    #[should_panic(expected = "tabla clasica")]
+    fn zobrist_rejects_variant_boards() {
+        let (mut board, _) = board_with_mouse((5, 5));
+        board[2][2] = HOLE;
+        zobrist(&board, 1);
+    }
+}
@@ -0,0 +1,174 @@
+use crate::rules::{Board, BOARD_SIZE};
+use crate::Position;
+
+// Simetriile unei table cu randurile impare deplasate la dreapta. Oglindirea stanga-dreapta nu
+// merge niciodata: ar muta deplasarea pe randurile pare. Pe latura impara randurile isi pastreaza
+// paritatea la oglindirea sus-jos, deci ea e buna; pe latura para paritatea se schimba si ramane
+// doar rotatia de 180 de grade, care schimba si deplasarea la loc.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Symmetry {
+    Identity,
+    FlipRows,
+    Rotate,
+}
+
+pub fn symmetries(size: usize) -> [Symmetry; 2] {
+    if size % 2 == 1 {
+        [Symmetry::Identity, Symmetry::FlipRows]
+    } else {
+        [Symmetry::Identity, Symmetry::Rotate]
+    }
+}
+
+impl Symmetry {
+    pub fn apply(self, (x, y): (usize, usize), size: usize) -> (usize, usize) {
+        match self {
+            Symmetry::Identity => (x, y),
+            Symmetry::FlipRows => (size - 1 - x, y),
+            Symmetry::Rotate => (size - 1 - x, size - 1 - y),
+        }
+    }
+
+    // Toate simetriile de aici se anuleaza aplicate de doua ori.
+    pub fn inverse(self) -> Symmetry {
+        self
+    }
+
+    pub fn board(self, board: &Board, size: usize) -> Board {
+        let mut result = [[0; BOARD_SIZE]; BOARD_SIZE];
+        for (x, row) in board.iter().enumerate().take(size) {
+            for (y, &cell) in row.iter().enumerate().take(size) {
+                let (tx, ty) = self.apply((x, y), size);
+                result[tx][ty] = cell;
+            }
+        }
+        result
+    }
+}
+
+// Forma canonica a pozitiei: cea mai mica tabla dintre imaginile ei prin simetrii.
+// Pozitiile simetrice au aceeasi forma, deci cartile si tabelele le pot tine o singura data.
+pub struct Canonical {
+    pub board: Board,
+    pub mouse: Position,
+    pub symmetry: Symmetry,
+    size: usize,
+}
+
+impl Canonical {
+    pub fn new(board: &Board, mouse: &Position, size: usize) -> Self {
+        let mut best = Canonical {
+            board: *board,
+            mouse: *mouse,
+            symmetry: Symmetry::Identity,
+            size,
+        };
+        for symmetry in symmetries(size) {
+            let image = symmetry.board(board, size);
+            if image < best.board {
+                let (x, y) = symmetry.apply((mouse.x, mouse.y), size);
+                best.board = image;
+                best.mouse = Position::new(x, y);
+                best.symmetry = symmetry;
+            }
+        }
+        best
+    }
+
+    // O mutare de pe tabla reala, vazuta pe forma canonica.
+    pub fn to_canonical(&self, cell: (usize, usize)) -> (usize, usize) {
+        self.symmetry.apply(cell, self.size)
+    }
+
+    // O mutare de pe forma canonica, intoarsa pe tabla reala.
+    pub fn from_canonical(&self, cell: (usize, usize)) -> (usize, usize) {
+        self.symmetry.inverse().apply(cell, self.size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::{is_border_on, neighbours_on};
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    fn random_position(rng: &mut StdRng, size: usize) -> (Board, Position) {
+        let mut board = [[0; BOARD_SIZE]; BOARD_SIZE];
+        for row in board.iter_mut().take(size) {
+            for cell in row.iter_mut().take(size) {
+                if rng.gen_bool(0.3) {
+                    *cell = 2;
+                }
+            }
+        }
+        let (x, y) = (rng.gen_range(0..size), rng.gen_range(0..size));
+        board[x][y] = 1;
+        (board, Position::new(x, y))
+    }
+
+    #[test]
+    fn every_symmetry_keeps_the_hex_layout() {
+        for size in 3..=BOARD_SIZE {
+            for symmetry in symmetries(size) {
+                for x in 0..size {
+                    for y in 0..size {
+                        let (tx, ty) = symmetry.apply((x, y), size);
+                        assert!(tx < size && ty < size);
+                        assert_eq!(is_border_on(x, y, size), is_border_on(tx, ty, size));
+                        let mut expected: Vec<_> = neighbours_on(x, y, size)
+                            .into_iter()
+                            .map(|cell| symmetry.apply(cell, size))
+                            .collect();
+                        let mut actual = neighbours_on(tx, ty, size);
+                        expected.sort();
+                        actual.sort();
+                        assert_eq!(
+                            expected,
+                            actual,
+                            "{:?} pe latura {} la {:?}",
+                            symmetry,
+                            size,
+                            (x, y)
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn symmetric_images_share_the_canonical_form() {
+        let mut rng = StdRng::seed_from_u64(49);
+        for size in 3..=BOARD_SIZE {
+            for _ in 0..50 {
+                let (board, mouse) = random_position(&mut rng, size);
+                let canonical = Canonical::new(&board, &mouse, size);
+                for symmetry in symmetries(size) {
+                    let (x, y) = symmetry.apply((mouse.x, mouse.y), size);
+                    let image =
+                        Canonical::new(&symmetry.board(&board, size), &Position::new(x, y), size);
+                    assert!(image.board == canonical.board);
+                    assert_eq!(image.mouse, canonical.mouse);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn moves_survive_the_trip_through_the_canonical_frame() {
+        let mut rng = StdRng::seed_from_u64(490);
+        for size in 3..=BOARD_SIZE {
+            for _ in 0..50 {
+                let (board, mouse) = random_position(&mut rng, size);
+                let canonical = Canonical::new(&board, &mouse, size);
+                for (x, row) in board.iter().enumerate().take(size) {
+                    for (y, &cell) in row.iter().enumerate().take(size) {
+                        let seen = canonical.to_canonical((x, y));
+                        assert_eq!(canonical.board[seen.0][seen.1], cell);
+                        assert_eq!(canonical.from_canonical(seen), (x, y));
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,488 @@
+use rand::Rng;
+use std::{fs, path::Path};
+
+use crate::rules::{is_border_on, neighbours_on, Board, BOARD_SIZE};
+use crate::{AppError, Position};
+
+pub const MIN_TABLE_SIZE: usize = 3;
+// La 5x5 sunt deja 9 * 2^24 pozitii (aproape 150 MB). La 6x6 ar fi 16 * 2^35 (peste 5 * 10^11),
+// iar la 7x7 25 * 2^48; simetria doar injumatateste numarul si nici rezolvarea pe straturi de ziduri
+// nu scade totalul, asa ca tablele mai mari raman in grija cautarii din motor.
+pub const MAX_TABLE_SIZE: usize = 5;
+
+const MAGIC: &[u8; 4] = b"TTMT";
+const VERSION: u8 = 1;
+const MOUSE_WINS: u8 = 0x80;
+const START_ATTEMPTS: usize = 10_000;
+const BALANCED_PLIES: u8 = 4;
+
+// Rezultatul cu joc perfect: cine castiga si in cate mutari (ale ambilor jucatori).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Value {
+    pub mouse_wins: bool,
+    pub plies: u8,
+}
+
+impl Value {
+    fn from_byte(byte: u8) -> Self {
+        Value {
+            mouse_wins: byte & MOUSE_WINS != 0,
+            plies: byte & !MOUSE_WINS,
+        }
+    }
+}
+
+// Cat de buna e valoarea pentru soarece: castigul rapid e cel mai bun, pierderea rapida cea mai rea.
+fn mouse_score(byte: u8) -> i16 {
+    let plies = (byte & !MOUSE_WINS) as i16;
+    if byte & MOUSE_WINS != 0 {
+        256 - plies
+    } else {
+        plies - 256
+    }
+}
+
+// Tabla mica e coltul din stanga sus al tablei de joc; soarecele sta mereu in interior.
+// Tabelul pastreaza doar pozitiile cu zidurile la mutare, indexate dupa celula soarecelui
+// si masca zidurilor fara bitul acelei celule.
+pub struct Tablebase {
+    pub size: usize,
+    interior: Vec<usize>,
+    slots: Vec<Option<usize>>,
+    neighbours: Vec<Vec<usize>>,
+    border: Vec<bool>,
+    walls: Vec<u8>,
+}
+
+impl Tablebase {
+    fn empty(size: usize) -> Result<Self, AppError> {
+        if !(MIN_TABLE_SIZE..=MAX_TABLE_SIZE).contains(&size) {
+            return Err(AppError::ConfigError(format!(
+                "tabla de {}x{} nu poate fi rezolvata (intre {} si {})",
+                size, size, MIN_TABLE_SIZE, MAX_TABLE_SIZE
+            )));
+        }
+        let cells = size * size;
+        let border: Vec<bool> = (0..cells)
+            .map(|cell| is_border_on(cell / size, cell % size, size))
+            .collect();
+        let interior: Vec<usize> = (0..cells).filter(|&cell| !border[cell]).collect();
+        let mut slots = vec![None; cells];
+        for (slot, &cell) in interior.iter().enumerate() {
+            slots[cell] = Some(slot);
+        }
+        let neighbours = (0..cells)
+            .map(|cell| {
+                neighbours_on(cell / size, cell % size, size)
+                    .into_iter()
+                    .map(|(x, y)| x * size + y)
+                    .collect()
+            })
+            .collect();
+        Ok(Tablebase {
+            size,
+            walls: vec![0; interior.len() << (cells - 1)],
+            interior,
+            slots,
+            neighbours,
+            border,
+        })
+    }
+
+    // Analiza retrograda: zidurile doar se adauga, deci rezolvam straturile de la tabla plina spre cea goala.
+    pub fn solve(size: usize) -> Result<Self, AppError> {
+        let mut table = Self::empty(size)?;
+        let cells = size * size;
+        let interior = table.interior.clone();
+        let shift = cells - 1;
+        let mut mouse = vec![0; table.walls.len()];
+        for count in (0..cells).rev() {
+            for_each_mask(shift, count, |squeezed| {
+                for (slot, &cell) in interior.iter().enumerate() {
+                    let walls = expand(squeezed, cell);
+                    let best = (0..cells)
+                        .filter(|&wall| wall != cell && walls & (1 << wall) == 0)
+                        .map(|wall| {
+                            mouse[(slot << shift) | squeeze(walls | 1 << wall, cell) as usize]
+                        })
+                        .min_by_key(|&value| mouse_score(value));
+                    table.walls[(slot << shift) | squeezed as usize] = best.map_or(0, next);
+                }
+            });
+            for_each_mask(shift, count, |squeezed| {
+                for (slot, &cell) in interior.iter().enumerate() {
+                    let value = table.mouse_to_move(expand(squeezed, cell), cell);
+                    mouse[table.index(slot, squeezed)] = value;
+                }
+            });
+        }
+        Ok(table)
+    }
+
+    fn index(&self, slot: usize, squeezed: u32) -> usize {
+        (slot << (self.size * self.size - 1)) | squeezed as usize
+    }
+
+    fn walls_to_move(&self, walls: u32, cell: usize) -> u8 {
+        let slot = self.slots[cell].expect("soarecele trebuie sa fie in interior");
+        self.walls[self.index(slot, squeeze(walls, cell))]
+    }
+
+    fn mouse_to_move(&self, walls: u32, cell: usize) -> u8 {
+        let mut best: Option<u8> = None;
+        for &to in self.neighbours[cell].iter() {
+            if walls & (1 << to) != 0 {
+                continue;
+            }
+            if self.border[to] {
+                return MOUSE_WINS | 1;
+            }
+            let value = self.walls_to_move(walls, to);
+            if best.is_none_or(|best| mouse_score(value) > mouse_score(best)) {
+                best = Some(value);
+            }
+        }
+        best.map_or(0, next)
+    }
+
+    // Masca zidurilor si celula soarecelui, daca pozitia incape in tabel.
+    fn position(&self, board: &Board, mouse: &Position) -> Option<(u32, usize)> {
+        if mouse.x >= self.size || mouse.y >= self.size {
+            return None;
+        }
+        let cell = mouse.x * self.size + mouse.y;
+        self.slots[cell]?;
+        let walls = (0..self.size * self.size)
+            .filter(|&wall| board[wall / self.size][wall % self.size] == 2)
+            .fold(0, |walls, wall| walls | 1 << wall);
+        Some((walls, cell))
+    }
+
+    pub fn probe(&self, board: &Board, mouse: &Position, to_move: u8) -> Option<Value> {
+        let (walls, cell) = self.position(board, mouse)?;
+        let byte = if to_move == 1 {
+            self.mouse_to_move(walls, cell)
+        } else {
+            self.walls_to_move(walls, cell)
+        };
+        Some(Value::from_byte(byte))
+    }
+
+    pub fn best_move(&self, board: &Board, mouse: &Position, role: u8) -> Option<(usize, usize)> {
+        let (walls, cell) = self.position(board, mouse)?;
+        let to_board = |cell: usize| (cell / self.size, cell % self.size);
+        if role == 1 {
+            let free = self.neighbours[cell]
+                .iter()
+                .copied()
+                .filter(|&to| walls & (1 << to) == 0);
+            if let Some(exit) = free.clone().find(|&to| self.border[to]) {
+                return Some(to_board(exit));
+            }
+            return free
+                .max_by_key(|&to| mouse_score(self.walls_to_move(walls, to)))
+                .map(to_board);
+        }
+        (0..self.size * self.size)
+            .filter(|&wall| wall != cell && walls & (1 << wall) == 0)
+            .min_by_key(|&wall| mouse_score(self.mouse_to_move(walls | 1 << wall, cell)))
+            .map(to_board)
+    }
+
+    // O pozitie de start echilibrata: soarecele la mutare, castigatorul ales la intamplare
+    // si niciunul nu castiga in primele mutari. Pe tablele prea mici alegem partida cea mai lunga.
+    pub fn balanced_start(&self, count: usize) -> (Board, Position) {
+        let mut rng = rand::thread_rng();
+        let cells = self.size * self.size;
+        let mouse_wins = rng.gen_bool(0.5);
+        let mut longest = (0, 0, self.interior[0]);
+        for _ in 0..START_ATTEMPTS {
+            let cell = self.interior[rng.gen_range(0..self.interior.len())];
+            let mut walls = 0u32;
+            while (walls.count_ones() as usize) < count.min(cells - 1) {
+                let wall = rng.gen_range(0..cells);
+                if wall != cell {
+                    walls |= 1 << wall;
+                }
+            }
+            let value = Value::from_byte(self.mouse_to_move(walls, cell));
+            if value.plies >= BALANCED_PLIES && value.mouse_wins == mouse_wins {
+                longest = (value.plies, walls, cell);
+                break;
+            }
+            if value.plies > longest.0 {
+                longest = (value.plies, walls, cell);
+            }
+        }
+        let (_, walls, cell) = longest;
+        (
+            self.board(walls, cell),
+            Position::new(cell / self.size, cell % self.size),
+        )
+    }
+
+    fn board(&self, walls: u32, cell: usize) -> Board {
+        let mut board = [[0; BOARD_SIZE]; BOARD_SIZE];
+        for wall in (0..self.size * self.size).filter(|&wall| walls & (1 << wall) != 0) {
+            board[wall / self.size][wall % self.size] = 2;
+        }
+        board[cell / self.size][cell % self.size] = 1;
+        board
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), AppError> {
+        let mut bytes = Vec::with_capacity(self.walls.len() + 6);
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        bytes.push(self.size as u8);
+        bytes.extend_from_slice(&self.walls);
+        fs::write(path, bytes).map_err(AppError::IOError)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, AppError> {
+        let bytes = fs::read(path).map_err(AppError::IOError)?;
+        let invalid =
+            |reason: &str| AppError::ConfigError(format!("{}: {}", path.display(), reason));
+        if bytes.len() < 6 || &bytes[..4] != MAGIC {
+            return Err(invalid("nu este un tabel de finaluri"));
+        }
+        if bytes[4] != VERSION {
+            return Err(invalid("versiune necunoscuta"));
+        }
+        let mut table = Self::empty(bytes[5] as usize)?;
+        if bytes.len() - 6 != table.walls.len() {
+            return Err(invalid("fisier incomplet"));
+        }
+        table.walls.copy_from_slice(&bytes[6..]);
+        Ok(table)
+    }
+
+    // Cate pozitii castiga soarecele, din cele cu zidurile la mutare.
+    pub fn mouse_wins(&self) -> usize {
+        self.walls
+            .iter()
+            .filter(|&&byte| byte & MOUSE_WINS != 0)
+            .count()
+    }
+
+    pub fn positions(&self) -> usize {
+        self.walls.len()
+    }
+}
+
+fn next(byte: u8) -> u8 {
+    (byte & MOUSE_WINS) | ((byte & !MOUSE_WINS) + 1)
+}
+
+fn squeeze(walls: u32, cell: usize) -> u32 {
+    (walls & ((1 << cell) - 1)) | ((walls >> (cell + 1)) << cell)
+}
+
+fn expand(squeezed: u32, cell: usize) -> u32 {
+    (squeezed & ((1 << cell) - 1)) | ((squeezed >> cell) << (cell + 1))
+}
+
+// Toate mastile de `bits` biti cu exact `count` biti setati.
+fn for_each_mask(bits: usize, count: usize, mut visit: impl FnMut(u32)) {
+    if count > bits {
+        return;
+    }
+    if count == 0 {
+        visit(0);
+        return;
+    }
+    let end = 1u64 << bits;
+    let mut mask = (1u64 << count) - 1;
+    while mask < end {
+        visit(mask as u32);
+        let lowest = mask & mask.wrapping_neg();
+        let ripple = mask + lowest;
+        mask = (((ripple ^ mask) >> 2) / lowest) | ripple;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    type Memo = HashMap<(Board, bool), Value>;
+
+    fn score(value: Value) -> i16 {
+        mouse_score(value.plies | if value.mouse_wins { MOUSE_WINS } else { 0 })
+    }
+
+    // Minimax direct pe tabla, fara tabel: referinta pentru analiza retrograda.
+    fn minimax(
+        board: &mut Board,
+        mouse: (usize, usize),
+        size: usize,
+        mouse_to_move: bool,
+        memo: &mut Memo,
+    ) -> Value {
+        if let Some(&value) = memo.get(&(*board, mouse_to_move)) {
+            return value;
+        }
+        let mut best: Option<Value> = None;
+        let mut consider = |value: Value| {
+            let better = match best {
+                None => true,
+                Some(best) if mouse_to_move => score(value) > score(best),
+                Some(best) => score(value) < score(best),
+            };
+            if better {
+                best = Some(value);
+            }
+        };
+        if mouse_to_move {
+            for (x, y) in neighbours_on(mouse.0, mouse.1, size) {
+                if board[x][y] != 0 {
+                    continue;
+                }
+                if is_border_on(x, y, size) {
+                    consider(Value {
+                        mouse_wins: true,
+                        plies: 0,
+                    });
+                    continue;
+                }
+                board[mouse.0][mouse.1] = 0;
+                board[x][y] = 1;
+                consider(minimax(board, (x, y), size, false, memo));
+                board[x][y] = 0;
+                board[mouse.0][mouse.1] = 1;
+            }
+        } else {
+            for x in 0..size {
+                for y in 0..size {
+                    if board[x][y] == 0 {
+                        board[x][y] = 2;
+                        consider(minimax(board, mouse, size, true, memo));
+                        board[x][y] = 0;
+                    }
+                }
+            }
+        }
+        let value = best.map_or(
+            Value {
+                mouse_wins: false,
+                plies: 0,
+            },
+            |best| Value {
+                mouse_wins: best.mouse_wins,
+                plies: best.plies + 1,
+            },
+        );
+        memo.insert((*board, mouse_to_move), value);
+        value
+    }
+
+    // Toate pozitiile tablei mici: soarecele in interior si orice multime de ziduri.
+    fn positions(size: usize) -> Vec<(Board, Position)> {
+        let cells = size * size;
+        let mut result = Vec::new();
+        for x in 1..size - 1 {
+            for y in 1..size - 1 {
+                let cell = x * size + y;
+                for walls in (0..1u32 << cells).filter(|walls| walls & (1 << cell) == 0) {
+                    let mut board = [[0; BOARD_SIZE]; BOARD_SIZE];
+                    for wall in (0..cells).filter(|&wall| walls & (1 << wall) != 0) {
+                        board[wall / size][wall % size] = 2;
+                    }
+                    board[x][y] = 1;
+                    result.push((board, Position::new(x, y)));
+                }
+            }
+        }
+        result
+    }
+
+    fn check_against_minimax(size: usize) {
+        let table = Tablebase::solve(size).unwrap();
+        let mut memo = Memo::new();
+        for (mut board, mouse) in positions(size) {
+            let cell = (mouse.x, mouse.y);
+            let mouse_value = minimax(&mut board, cell, size, true, &mut memo);
+            let walls_value = minimax(&mut board, cell, size, false, &mut memo);
+            assert_eq!(table.probe(&board, &mouse, 1), Some(mouse_value));
+            assert_eq!(table.probe(&board, &mouse, 2), Some(walls_value));
+
+            // Mutarea aleasa de tabel pastreaza valoarea pozitiei.
+            if let Some((x, y)) = table.best_move(&board, &mouse, 1) {
+                let after = if is_border_on(x, y, size) {
+                    Value {
+                        mouse_wins: true,
+                        plies: 1,
+                    }
+                } else {
+                    board[cell.0][cell.1] = 0;
+                    board[x][y] = 1;
+                    let value = minimax(&mut board, (x, y), size, false, &mut memo);
+                    board[x][y] = 0;
+                    board[cell.0][cell.1] = 1;
+                    Value {
+                        plies: value.plies + 1,
+                        ..value
+                    }
+                };
+                assert_eq!(after, mouse_value, "soarecele din {:?}", cell);
+            }
+            if let Some((x, y)) = table.best_move(&board, &mouse, 2) {
+                assert_eq!(board[x][y], 0);
+                board[x][y] = 2;
+                let value = minimax(&mut board, cell, size, true, &mut memo);
+                let after = Value {
+                    plies: value.plies + 1,
+                    ..value
+                };
+                assert_eq!(after, walls_value, "zidurile cu soarecele in {:?}", cell);
+            }
+        }
+    }
+
+    #[test]
+    fn three_by_three_matches_minimax() {
+        check_against_minimax(3);
+    }
+
+    #[test]
+    fn four_by_four_matches_minimax() {
+        check_against_minimax(4);
+    }
+
+    #[test]
+    fn sizes_outside_the_range_are_rejected() {
+        assert!(Tablebase::solve(MIN_TABLE_SIZE - 1).is_err());
+        assert!(Tablebase::solve(MAX_TABLE_SIZE + 1).is_err());
+    }
+
+    #[test]
+    fn file_round_trip() {
+        let table = Tablebase::solve(4).unwrap();
+        let path = std::env::temp_dir().join(format!("ttmt-{}.bin", std::process::id()));
+        table.save(&path).unwrap();
+        let bytes = fs::read(&path).unwrap();
+        assert_eq!(&bytes[..4], MAGIC);
+        assert_eq!(bytes.len(), 6 + table.positions());
+
+        let loaded = Tablebase::load(&path).unwrap();
+        assert_eq!(loaded.size, 4);
+        assert!(loaded.walls == table.walls);
+        for (board, mouse) in positions(4).into_iter().step_by(97) {
+            for role in [1, 2] {
+                assert_eq!(
+                    loaded.probe(&board, &mouse, role),
+                    table.probe(&board, &mouse, role)
+                );
+            }
+        }
+
+        fs::write(&path, &bytes[..bytes.len() - 1]).unwrap();
+        assert!(Tablebase::load(&path).is_err());
+        fs::write(&path, [b"TTMX".as_slice(), &bytes[4..]].concat()).unwrap();
+        assert!(Tablebase::load(&path).is_err());
+        fs::write(&path, [&bytes[..4], &[VERSION + 1], &bytes[5..]].concat()).unwrap();
+        assert!(Tablebase::load(&path).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,319 @@
+use std::fmt;
+
+use crate::protocol::{clean_name, RoomOptions};
+use crate::AppError;
+
+pub const MAX_PLAYERS: usize = 32;
+pub const BYE: u8 = u8::MAX;
+pub const NO_ROOM: i8 = -1;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Format {
+    #[default]
+    RoundRobin,
+    Swiss,
+}
+
+impl Format {
+    pub fn parse(value: &str) -> Result<Self, AppError> {
+        match value.trim() {
+            "campionat" => Ok(Format::RoundRobin),
+            "elvetian" => Ok(Format::Swiss),
+            _ => Err(AppError::ConfigError(format!(
+                "format de turneu necunoscut: '{}'",
+                value
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Format::RoundRobin => write!(f, "campionat"),
+            Format::Swiss => write!(f, "elvetian"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Status {
+    #[default]
+    Registering,
+    Running,
+    Finished,
+}
+
+// Rezultatul unei perechi, din perspectiva primului jucator.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Outcome {
+    #[default]
+    Pending,
+    FirstWon,
+    SecondWon,
+    Draw,
+    BothLost,
+}
+
+impl Outcome {
+    // Puncte in jumatati: victoria 2, egalul 1.
+    pub fn points(&self) -> [u8; 2] {
+        match self {
+            Outcome::Pending | Outcome::BothLost => [0, 0],
+            Outcome::FirstWon => [2, 0],
+            Outcome::SecondWon => [0, 2],
+            Outcome::Draw => [1, 1],
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Standing {
+    pub name: String,
+    pub points: u8,
+    pub played: u8,
+    pub buchholz: u16,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Pairing {
+    pub first: u8,
+    pub second: u8,
+    pub room: i8,
+    pub outcome: Outcome,
+}
+
+// Ce vede un participant: jucatorii in ordinea inscrierii si perechile rundei curente.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TournamentView {
+    pub id: u8,
+    pub format: Format,
+    pub status: Status,
+    pub round: u8,
+    pub rounds: u8,
+    pub you: u8,
+    pub options: RoomOptions,
+    pub players: Vec<Standing>,
+    pub pairings: Vec<Pairing>,
+}
+
+impl TournamentView {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = vec![
+            self.id,
+            self.format as u8,
+            self.status as u8,
+            self.round,
+            self.rounds,
+            self.you,
+        ];
+        push_text(&mut bytes, &self.options.encode());
+        bytes.push(self.players.len() as u8);
+        for player in self.players.iter() {
+            push_text(&mut bytes, &player.name);
+            bytes.push(player.points);
+            bytes.push(player.played);
+            bytes.extend_from_slice(&player.buchholz.to_be_bytes());
+        }
+        bytes.push(self.pairings.len() as u8);
+        for pairing in self.pairings.iter() {
+            bytes.extend_from_slice(&[
+                pairing.first,
+                pairing.second,
+                pairing.room as u8,
+                pairing.outcome as u8,
+            ]);
+        }
+        bytes
+    }
+
+    pub fn decode(bytes: &[u8]) -> Option<Self> {
+        let mut reader = Reader { bytes, at: 0 };
+        let mut view = TournamentView {
+            id: reader.byte()?,
+            format: match reader.byte()? {
+                0 => Format::RoundRobin,
+                _ => Format::Swiss,
+            },
+            status: match reader.byte()? {
+                0 => Status::Registering,
+                1 => Status::Running,
+                _ => Status::Finished,
+            },
+            round: reader.byte()?,
+            rounds: reader.byte()?,
+            you: reader.byte()?,
+            options: RoomOptions::parse(&reader.text()?).ok()?,
+            ..TournamentView::default()
+        };
+        for _ in 0..reader.byte()? {
+            view.players.push(Standing {
+                name: reader.text()?,
+                points: reader.byte()?,
+                played: reader.byte()?,
+                buchholz: u16::from_be_bytes([reader.byte()?, reader.byte()?]),
+            });
+        }
+        for _ in 0..reader.byte()? {
+            view.pairings.push(Pairing {
+                first: reader.byte()?,
+                second: reader.byte()?,
+                room: reader.byte()? as i8,
+                outcome: match reader.byte()? {
+                    1 => Outcome::FirstWon,
+                    2 => Outcome::SecondWon,
+                    3 => Outcome::Draw,
+                    4 => Outcome::BothLost,
+                    _ => Outcome::Pending,
+                },
+            });
+        }
+        Some(view)
+    }
+
+    // Indicii jucatorilor ordonati dupa puncte, apoi dupa Buchholz.
+    pub fn ranking(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.players.len()).collect();
+        order.sort_by(|&a, &b| {
+            let (a, b) = (&self.players[a], &self.players[b]);
+            (b.points, b.buchholz).cmp(&(a.points, a.buchholz))
+        });
+        order
+    }
+}
+
+pub fn format_points(points: u16) -> String {
+    if points.is_multiple_of(2) {
+        (points / 2).to_string()
+    } else {
+        format!("{}.5", points / 2)
+    }
+}
+
+// Cererea de intrare intr-un turneu: "nou;nume=..;format=..;runde=..;timp=.." sau "<id>;nume=..".
+// Cine revine intr-un turneu trimite si "cheie=..", primita de la server la inscriere.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TournamentRequest {
+    pub id: Option<u8>,
+    pub name: String,
+    pub token: String,
+    pub format: Format,
+    pub rounds: u8,
+    pub options: RoomOptions,
+}
+
+impl TournamentRequest {
+    pub fn parse(text: &str) -> Result<Self, AppError> {
+        let (id, rest) = text.split_once(';').unwrap_or((text, ""));
+        let id = match id.trim() {
+            "nou" => None,
+            id => Some(id.parse::<u8>().map_err(|_| {
+                AppError::UnexpectedResponse(format!("cod de turneu invalid: '{}'", id))
+            })?),
+        };
+
+        let mut request = TournamentRequest {
+            id,
+            ..TournamentRequest::default()
+        };
+        let mut options = Vec::new();
+        for pair in rest.split(';').map(str::trim) {
+            match pair.split_once('=') {
+                Some(("nume", value)) => request.name = clean_name(value),
+                Some(("cheie", value)) => request.token = value.trim().to_string(),
+                Some(("format", value)) => request.format = Format::parse(value)?,
+                Some(("runde", value)) => {
+                    request.rounds = value.trim().parse::<u8>().map_err(|_| {
+                        AppError::ConfigError(format!("numar de runde invalid: '{}'", value))
+                    })?
+                }
+                _ => options.push(pair),
+            }
+        }
+        request.options = RoomOptions::parse(&options.join(";"))?;
+        Ok(request)
+    }
+
+    pub fn encode(&self) -> String {
+        let mut text = match self.id {
+            Some(id) => id.to_string(),
+            None => format!("nou;format={}", self.format),
+        };
+        let name = clean_name(&self.name);
+        if !name.is_empty() {
+            text.push_str(&format!(";nume={}", name));
+        }
+        if !self.token.is_empty() {
+            text.push_str(&format!(";cheie={}", self.token));
+        }
+        if self.id.is_none() {
+            if self.rounds > 0 {
+                text.push_str(&format!(";runde={}", self.rounds));
+            }
+            let options = self.options.encode();
+            if !options.is_empty() {
+                text.push(';');
+                text.push_str(&options);
+            }
+        }
+        text
+    }
+}
+
+fn push_text(bytes: &mut Vec<u8>, text: &str) {
+    let text = text.as_bytes();
+    let len = text.len().min(u8::MAX as usize);
+    bytes.push(len as u8);
+    bytes.extend_from_slice(&text[..len]);
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    at: usize,
+}
+
+impl Reader<'_> {
+    fn byte(&mut self) -> Option<u8> {
+        let byte = *self.bytes.get(self.at)?;
+        self.at += 1;
+        Some(byte)
+    }
+
+    fn text(&mut self) -> Option<String> {
+        let len = self.byte()? as usize;
+        let text = self.bytes.get(self.at..self.at + len)?;
+        self.at += len;
+        Some(String::from_utf8_lossy(text).to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn request_round_trip_keeps_the_token() {
+        let request = TournamentRequest {
+            id: Some(4),
+            name: String::from("ana"),
+            token: String::from("00ff00ff00ff00ff"),
+            ..TournamentRequest::default()
+        };
+        assert_eq!(request.encode(), "4;nume=ana;cheie=00ff00ff00ff00ff");
+        assert_eq!(
+            TournamentRequest::parse(&request.encode()).unwrap(),
+            request
+        );
+
+        let created = TournamentRequest {
+            format: Format::Swiss,
+            rounds: 3,
+            options: RoomOptions::parse("meci=3").unwrap(),
+            ..TournamentRequest::default()
+        };
+        assert_eq!(
+            TournamentRequest::parse(&created.encode()).unwrap(),
+            created
+        );
+    }
+}
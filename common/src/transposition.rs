@@ -0,0 +1,172 @@
+// Tabela de transpozitii: tine ce a aflat o cautare despre o pozitie, dupa cheia ei Zobrist
+// (rules::zobrist). Are marime fixa si o intrare noua o inlocuieste pe cea veche din acelasi loc.
+pub struct TranspositionTable<T> {
+    entries: Vec<Option<(u64, T)>>,
+    mask: usize,
+    pub probes: u64,
+    pub hits: u64,
+}
+
+// Ce stie alpha-beta despre o pozitie: valoarea e exacta sau doar o margine a ei.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Bound {
+    Exact,
+    Lower,
+    Upper,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Score {
+    pub value: i32,
+    pub depth: u8,
+    pub bound: Bound,
+}
+
+impl Score {
+    // Valoarea care se poate folosi direct in fereastra (alpha, beta), daca exista.
+    pub fn cutoff(&self, depth: usize, alpha: i32, beta: i32) -> Option<i32> {
+        if (self.depth as usize) < depth {
+            return None;
+        }
+        match self.bound {
+            Bound::Exact => Some(self.value),
+            Bound::Lower if self.value >= beta => Some(self.value),
+            Bound::Upper if self.value <= alpha => Some(self.value),
+            _ => None,
+        }
+    }
+
+    // Rezultatul unei cautari cu fereastra (alpha, beta) de la inceputul ei.
+    pub fn new(value: i32, depth: usize, alpha: i32, beta: i32) -> Self {
+        let bound = if value <= alpha {
+            Bound::Upper
+        } else if value >= beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+        Score {
+            value,
+            depth: depth.min(u8::MAX as usize) as u8,
+            bound,
+        }
+    }
+}
+
+impl<T: Clone> TranspositionTable<T> {
+    // 2^bits intrari.
+    pub fn new(bits: u32) -> Self {
+        let size = 1usize << bits;
+        TranspositionTable {
+            entries: vec![None; size],
+            mask: size - 1,
+            probes: 0,
+            hits: 0,
+        }
+    }
+
+    // O tabela care nu tine nimic, pentru cautarile fara memorie.
+    pub fn disabled() -> Self {
+        TranspositionTable {
+            entries: Vec::new(),
+            mask: 0,
+            probes: 0,
+            hits: 0,
+        }
+    }
+
+    pub fn get(&mut self, key: u64) -> Option<&T> {
+        if self.entries.is_empty() {
+            return None;
+        }
+        self.probes += 1;
+        match &self.entries[key as usize & self.mask] {
+            Some((stored, value)) if *stored == key => {
+                self.hits += 1;
+                Some(value)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn insert(&mut self, key: u64, value: T) {
+        if !self.entries.is_empty() {
+            self.entries[key as usize & self.mask] = Some((key, value));
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.iter_mut().for_each(|entry| *entry = None);
+        self.probes = 0;
+        self.hits = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stores_and_finds_by_full_key() {
+        let mut table = TranspositionTable::new(4);
+        table.insert(0x1234, 7);
+        assert_eq!(table.get(0x1234), Some(&7));
+        // Acelasi loc (aceiasi biti de jos), alta cheie: nu e o potrivire.
+        assert_eq!(table.get(0x1234 + 16), None);
+        assert_eq!((table.probes, table.hits), (2, 1));
+    }
+
+    #[test]
+    fn new_entry_replaces_the_old_one_in_its_slot() {
+        let mut table = TranspositionTable::new(4);
+        table.insert(3, "vechi");
+        table.insert(3 + 16, "nou");
+        assert_eq!(table.get(3), None);
+        assert_eq!(table.get(3 + 16), Some(&"nou"));
+        // Alt loc nu e atins.
+        table.insert(4, "alt");
+        assert_eq!(table.get(3 + 16), Some(&"nou"));
+        assert_eq!(table.get(4), Some(&"alt"));
+    }
+
+    #[test]
+    fn clear_forgets_entries_and_counters() {
+        let mut table = TranspositionTable::new(2);
+        table.insert(1, 1);
+        table.get(1);
+        table.clear();
+        assert_eq!((table.probes, table.hits), (0, 0));
+        assert_eq!(table.get(1), None);
+    }
+
+    #[test]
+    fn disabled_table_stores_nothing() {
+        let mut table = TranspositionTable::disabled();
+        table.insert(1, 1);
+        assert_eq!(table.get(1), None);
+        assert_eq!(table.probes, 0);
+    }
+
+    #[test]
+    fn score_bounds_follow_the_search_window() {
+        assert_eq!(Score::new(5, 3, 0, 10).bound, Bound::Exact);
+        assert_eq!(Score::new(0, 3, 0, 10).bound, Bound::Upper);
+        assert_eq!(Score::new(12, 3, 0, 10).bound, Bound::Lower);
+        assert_eq!(Score::new(1, 300, 0, 10).depth, u8::MAX);
+    }
+
+    #[test]
+    fn cutoff_needs_enough_depth_and_a_usable_bound() {
+        let exact = Score::new(5, 3, 0, 10);
+        assert_eq!(exact.cutoff(3, -100, 100), Some(5));
+        assert_eq!(exact.cutoff(4, -100, 100), None);
+
+        let lower = Score::new(12, 3, 0, 10);
+        assert_eq!(lower.cutoff(2, 0, 12), Some(12));
+        assert_eq!(lower.cutoff(2, 0, 20), None);
+
+        let upper = Score::new(-4, 3, 0, 10);
+        assert_eq!(upper.cutoff(2, -4, 10), Some(-4));
+        assert_eq!(upper.cutoff(2, -10, 10), None);
+    }
+}
@@ -0,0 +1,153 @@
+use std::{
+    collections::VecDeque,
+    io::Write,
+    net::{Shutdown, TcpStream},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use common::{
+    protocol::{clean_chat, clean_name, Message},
+    AppError,
+};
+
+use crate::{config::Heartbeat, serve_idle_client, ConnectionGuard};
+
+const CHAT_BURST: usize = 5;
+const CHAT_WINDOW: Duration = Duration::from_secs(10);
+const LOBBY_HISTORY: usize = 20;
+
+pub struct ChatLimiter {
+    sent: VecDeque<Instant>,
+}
+
+impl ChatLimiter {
+    pub fn new() -> Self {
+        ChatLimiter {
+            sent: VecDeque::new(),
+        }
+    }
+
+    // Cel mult CHAT_BURST mesaje in fereastra CHAT_WINDOW.
+    pub fn allow(&mut self) -> bool {
+        let now = Instant::now();
+        while self
+            .sent
+            .front()
+            .is_some_and(|sent| now.duration_since(*sent) > CHAT_WINDOW)
+        {
+            self.sent.pop_front();
+        }
+        if self.sent.len() >= CHAT_BURST {
+            return false;
+        }
+        self.sent.push_back(now);
+        true
+    }
+}
+
+pub fn chat_from(from: &str, text: &str) -> Option<Message> {
+    let text = clean_chat(text);
+    if text.is_empty() {
+        return None;
+    }
+    Some(Message::Chat {
+        from: from.to_string(),
+        text,
+    })
+}
+
+pub fn rate_limited() -> Message {
+    Message::Chat {
+        from: String::new(),
+        text: String::from("Prea multe mesaje, mai asteapta putin"),
+    }
+}
+
+struct Member {
+    id: usize,
+    stream: TcpStream,
+}
+
+pub struct Lobby {
+    members: Vec<Member>,
+    history: VecDeque<Message>,
+    next_id: usize,
+}
+
+impl Lobby {
+    pub fn new() -> Self {
+        Lobby {
+            members: Vec::new(),
+            history: VecDeque::new(),
+            next_id: 0,
+        }
+    }
+
+    fn broadcast(&mut self, message: &Message) {
+        let bytes = message.to_bytes();
+        self.members
+            .retain(|member| (&member.stream).write_all(&bytes).is_ok());
+        self.history.push_back(message.clone());
+        if self.history.len() > LOBBY_HISTORY {
+            self.history.pop_front();
+        }
+    }
+}
+
+pub fn join_lobby(
+    stream: TcpStream,
+    name: &str,
+    lobby: &Arc<Mutex<Lobby>>,
+    heartbeat: Heartbeat,
+    _guard: ConnectionGuard,
+) {
+    let mut name = clean_name(name);
+    if name.is_empty() {
+        name = String::from("Anonim");
+    }
+    let id = {
+        let mut lobby = lobby.lock().unwrap();
+        let id = lobby.next_id;
+        lobby.next_id += 1;
+        let writer = match stream.try_clone() {
+            Ok(writer) => writer,
+            Err(e) => {
+                AppError::CloneError(e.to_string()).log();
+                return;
+            }
+        };
+        let _ = writer.set_write_timeout(Some(Duration::from_secs(1)));
+        for message in lobby.history.iter() {
+            if let Err(e) = (&writer).write_all(&message.to_bytes()) {
+                AppError::WriteError(e.to_string()).log();
+                return;
+            }
+        }
+        lobby.members.push(Member { id, stream: writer });
+        id
+    };
+    info!("{} a intrat in chatul comun", name);
+
+    let mut limiter = ChatLimiter::new();
+    let reason = serve_idle_client(&stream, heartbeat, |message| match message {
+        Message::Chat { text, .. } => {
+            if !limiter.allow() {
+                let _ = (&stream).write_all(&rate_limited().to_bytes());
+                return;
+            }
+            if let Some(message) = chat_from(&name, &text) {
+                lobby.lock().unwrap().broadcast(&message);
+            }
+        }
+        message => debug!("Mesaj ignorat in chatul comun: {:?}", message),
+    });
+    debug!("{} a iesit din chatul comun: {}", name, reason);
+
+    lobby
+        .lock()
+        .unwrap()
+        .members
+        .retain(|member| member.id != id);
+    let _ = stream.shutdown(Shutdown::Both);
+}
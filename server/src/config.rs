@@ -0,0 +1,457 @@
+use std::{fs, net::IpAddr, path::Path, sync::Arc, time::Duration};
+
+use common::{
+    ai::LOOKAHEAD_DEPTH,
+    book::{Booked, OpeningBook},
+    engine::{Budget, Engine, Heuristic, Local, Lookahead, Strategy},
+    external::External,
+    mcts::Mcts,
+    rules::INITIAL_WALLS,
+    AppError,
+};
+use serde::Deserialize;
+
+use crate::log::LogLevel;
+
+const DEFAULT_CONFIG_FILE: &str = "server.toml";
+
+const USAGE: &str = "Utilizare: server [optiuni]
+
+Optiuni:
+  --config <fisier>          fisier de configurare TOML (implicit server.toml, daca exista)
+  --address <ip>             adresa pe care asculta serverul (implicit 127.0.0.1)
+  --port <port>              portul (implicit 8080)
+  --max-rooms <n>            numarul maxim de camere (1-127)
+  --max-connections <n>      numarul maxim de conexiuni simultane
+  --log-level <nivel>        error, warn, info sau debug
+  --heartbeat-interval <ms>  cat de des trimitem ping clientilor
+  --heartbeat-timeout <ms>   dupa cat timp fara raspuns deconectam clientul
+  --initial-walls <n>        numarul de ziduri puse la inceputul jocului
+  --reconnect-grace <sec>    cat timp asteptam un jucator deconectat
+  --engine <motor>           cum muta calculatorul: euristic, anticipare, mcts sau extern
+  --engine-iterations <n>    cate iteratii cauta mcts la fiecare mutare
+  --engine-threads <n>       pe cate fire cauta mcts
+  --engine-time <ms>         cat timp are motorul pentru o mutare
+  --engine-command <cmd>     programul pornit pentru motorul extern
+  --opening-book <fisier>    cartea de deschideri a calculatorului (gol = fara carte)
+  --match-records <fisier>   unde salvam meciurile terminate (gol = nu salvam)
+  --help                     afiseaza acest mesaj";
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub server: ServerSettings,
+    pub game: GameSettings,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerSettings {
+    pub address: String,
+    pub port: u16,
+    pub max_rooms: usize,
+    pub max_connections: usize,
+    pub log_level: LogLevel,
+    pub heartbeat_interval_ms: u64,
+    pub heartbeat_timeout_ms: u64,
+    pub match_records: String,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Heartbeat {
+    pub interval: Duration,
+    pub timeout: Duration,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GameSettings {
+    pub initial_walls: usize,
+    pub reconnect_grace_secs: u64,
+    pub engine: EngineKind,
+    pub engine_iterations: u32,
+    pub engine_threads: usize,
+    pub engine_time_ms: u64,
+    pub engine_command: String,
+    pub opening_book: String,
+    // Cartea o citim o singura data, la pornire, si o impart toate camerele.
+    #[serde(skip)]
+    pub book: Option<Arc<OpeningBook>>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+pub enum EngineKind {
+    #[serde(rename = "euristic")]
+    Heuristic,
+    #[serde(rename = "anticipare")]
+    Lookahead,
+    #[serde(rename = "mcts")]
+    Mcts,
+    #[serde(rename = "extern")]
+    External,
+}
+
+impl EngineKind {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "euristic" => Some(EngineKind::Heuristic),
+            "anticipare" => Some(EngineKind::Lookahead),
+            "mcts" => Some(EngineKind::Mcts),
+            "extern" => Some(EngineKind::External),
+            _ => None,
+        }
+    }
+}
+
+impl Default for ServerSettings {
+    fn default() -> Self {
+        ServerSettings {
+            address: String::from("127.0.0.1"),
+            port: 8080,
+            max_rooms: 100,
+            max_connections: 256,
+            log_level: LogLevel::Info,
+            heartbeat_interval_ms: 2000,
+            heartbeat_timeout_ms: 10000,
+            match_records: String::from("matches.toml"),
+        }
+    }
+}
+
+impl Default for GameSettings {
+    fn default() -> Self {
+        GameSettings {
+            initial_walls: INITIAL_WALLS,
+            reconnect_grace_secs: 60,
+            engine: EngineKind::Heuristic,
+            engine_iterations: 3000,
+            engine_threads: 1,
+            engine_time_ms: 1000,
+            engine_command: String::new(),
+            opening_book: String::new(),
+            book: None,
+        }
+    }
+}
+
+impl ServerSettings {
+    pub fn heartbeat(&self) -> Heartbeat {
+        Heartbeat {
+            interval: Duration::from_millis(self.heartbeat_interval_ms),
+            timeout: Duration::from_millis(self.heartbeat_timeout_ms),
+        }
+    }
+}
+
+impl GameSettings {
+    pub fn engine(&self) -> Result<Box<dyn Engine>, AppError> {
+        let strategy: Box<dyn Strategy> = match self.engine {
+            EngineKind::Heuristic => Box::new(Heuristic),
+            EngineKind::Lookahead => Box::new(Lookahead::new(LOOKAHEAD_DEPTH)),
+            EngineKind::Mcts => Box::new(Mcts::new(self.budget(), self.engine_threads)),
+            EngineKind::External => return Ok(Box::new(External::spawn(&self.engine_command)?)),
+        };
+        Ok(Box::new(Local::new(match self.book.as_ref() {
+            Some(book) => Box::new(Booked::new(book.clone(), strategy)),
+            None => strategy,
+        })))
+    }
+
+    pub fn budget(&self) -> Budget {
+        Budget {
+            iterations: Some(self.engine_iterations),
+            time: Some(Duration::from_millis(self.engine_time_ms)),
+        }
+    }
+}
+
+impl Config {
+    pub fn from_args(args: impl Iterator<Item = String>) -> Result<Self, AppError> {
+        let args: Vec<String> = args.collect();
+        if args.iter().any(|arg| arg == "--help" || arg == "-h") {
+            println!("{}", USAGE);
+            std::process::exit(0);
+        }
+
+        let config_path = args
+            .iter()
+            .position(|arg| arg == "--config")
+            .map(|index| {
+                args.get(index + 1).cloned().ok_or_else(|| {
+                    AppError::ConfigError(String::from("--config are nevoie de un fisier"))
+                })
+            })
+            .transpose()?;
+
+        let mut config = match config_path {
+            Some(path) => Config::from_file(Path::new(&path))?,
+            None if Path::new(DEFAULT_CONFIG_FILE).exists() => {
+                Config::from_file(Path::new(DEFAULT_CONFIG_FILE))?
+            }
+            None => Config::default(),
+        };
+
+        let mut args = args.into_iter();
+        while let Some(flag) = args.next() {
+            let mut value = || {
+                args.next().ok_or_else(|| {
+                    AppError::ConfigError(format!("{} are nevoie de o valoare\n\n{}", flag, USAGE))
+                })
+            };
+            match flag.as_str() {
+                "--config" => {
+                    value()?;
+                }
+                "--address" => config.server.address = value()?,
+                "--port" => config.server.port = parse_number(&flag, &value()?)?,
+                "--max-rooms" => config.server.max_rooms = parse_number(&flag, &value()?)?,
+                "--max-connections" => {
+                    config.server.max_connections = parse_number(&flag, &value()?)?
+                }
+                "--log-level" => {
+                    let level = value()?;
+                    config.server.log_level = LogLevel::parse(&level).ok_or_else(|| {
+                        AppError::ConfigError(format!(
+                            "nivel de log necunoscut '{}' (error, warn, info, debug)",
+                            level
+                        ))
+                    })?;
+                }
+                "--heartbeat-interval" => {
+                    config.server.heartbeat_interval_ms = parse_number(&flag, &value()?)?
+                }
+                "--heartbeat-timeout" => {
+                    config.server.heartbeat_timeout_ms = parse_number(&flag, &value()?)?
+                }
+                "--match-records" => config.server.match_records = value()?,
+                "--initial-walls" => config.game.initial_walls = parse_number(&flag, &value()?)?,
+                "--reconnect-grace" => {
+                    config.game.reconnect_grace_secs = parse_number(&flag, &value()?)?
+                }
+                "--engine" => {
+                    let engine = value()?;
+                    config.game.engine = EngineKind::parse(&engine).ok_or_else(|| {
+                        AppError::ConfigError(format!(
+                            "motor necunoscut '{}' (euristic, anticipare, mcts, extern)",
+                            engine
+                        ))
+                    })?;
+                }
+                "--engine-iterations" => {
+                    config.game.engine_iterations = parse_number(&flag, &value()?)?
+                }
+                "--engine-threads" => config.game.engine_threads = parse_number(&flag, &value()?)?,
+                "--engine-time" => config.game.engine_time_ms = parse_number(&flag, &value()?)?,
+                "--engine-command" => config.game.engine_command = value()?,
+                "--opening-book" => config.game.opening_book = value()?,
+                _ => {
+                    return Err(AppError::ConfigError(format!(
+                        "optiune necunoscuta '{}'\n\n{}",
+                        flag, USAGE
+                    )))
+                }
+            }
+        }
+
+        config.validate()?;
+        if !config.game.opening_book.is_empty() {
+            let book = OpeningBook::load(Path::new(&config.game.opening_book))?;
+            config.game.book = Some(Arc::new(book));
+        }
+        Ok(config)
+    }
+
+    fn from_file(path: &Path) -> Result<Self, AppError> {
+        let contents = fs::read_to_string(path).map_err(|e| {
+            AppError::ConfigError(format!("nu s-a putut citi {}: {}", path.display(), e))
+        })?;
+        toml::from_str(&contents)
+            .map_err(|e| AppError::ConfigError(format!("{}: {}", path.display(), e)))
+    }
+
+    fn validate(&self) -> Result<(), AppError> {
+        if self.server.address.parse::<IpAddr>().is_err() {
+            return Err(AppError::ConfigError(format!(
+                "adresa '{}' nu este o adresa IP valida",
+                self.server.address
+            )));
+        }
+        if self.server.port == 0 {
+            return Err(AppError::ConfigError(String::from(
+                "portul trebuie sa fie intre 1 si 65535",
+            )));
+        }
+        if !(1..=i8::MAX as usize).contains(&self.server.max_rooms) {
+            return Err(AppError::ConfigError(format!(
+                "max_rooms trebuie sa fie intre 1 si {}",
+                i8::MAX
+            )));
+        }
+        if self.server.max_connections < 2 {
+            return Err(AppError::ConfigError(String::from(
+                "max_connections trebuie sa fie cel putin 2",
+            )));
+        }
+        if self.server.heartbeat_interval_ms < 100 {
+            return Err(AppError::ConfigError(String::from(
+                "heartbeat_interval_ms trebuie sa fie cel putin 100",
+            )));
+        }
+        if self.server.heartbeat_timeout_ms < 2 * self.server.heartbeat_interval_ms {
+            return Err(AppError::ConfigError(String::from(
+                "heartbeat_timeout_ms trebuie sa fie cel putin dublul lui heartbeat_interval_ms",
+            )));
+        }
+        if !(1..=40).contains(&self.game.initial_walls) {
+            return Err(AppError::ConfigError(String::from(
+                "initial_walls trebuie sa fie intre 1 si 40",
+            )));
+        }
+        if !(1..=600).contains(&self.game.reconnect_grace_secs) {
+            return Err(AppError::ConfigError(String::from(
+                "reconnect_grace_secs trebuie sa fie intre 1 si 600",
+            )));
+        }
+        if !(1..=1_000_000).contains(&self.game.engine_iterations) {
+            return Err(AppError::ConfigError(String::from(
+                "engine_iterations trebuie sa fie intre 1 si 1000000",
+            )));
+        }
+        if !(1..=64).contains(&self.game.engine_threads) {
+            return Err(AppError::ConfigError(String::from(
+                "engine_threads trebuie sa fie intre 1 si 64",
+            )));
+        }
+        if !(10..=60_000).contains(&self.game.engine_time_ms) {
+            return Err(AppError::ConfigError(String::from(
+                "engine_time_ms trebuie sa fie intre 10 si 60000",
+            )));
+        }
+        if self.game.engine == EngineKind::External && self.game.engine_command.trim().is_empty() {
+            return Err(AppError::ConfigError(String::from(
+                "motorul extern are nevoie de engine_command",
+            )));
+        }
+        Ok(())
+    }
+
+    pub fn bind_address(&self) -> String {
+        format!("{}:{}", self.server.address, self.server.port)
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(flag: &str, value: &str) -> Result<T, AppError> {
+    value
+        .parse::<T>()
+        .map_err(|_| AppError::ConfigError(format!("{} asteapta un numar, nu '{}'", flag, value)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> impl Iterator<Item = String> {
+        args.iter()
+            .map(|arg| arg.to_string())
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    // Fisierul e al testului, ca sa nu depindem de un server.toml din directorul curent.
+    fn config_file(name: &str, contents: &str) -> String {
+        let path = std::env::temp_dir().join(format!("ttm-{}-{}.toml", name, std::process::id()));
+        fs::write(&path, contents).unwrap();
+        path.display().to_string()
+    }
+
+    fn rejected(change: impl FnOnce(&mut Config)) -> bool {
+        let mut config = Config::default();
+        change(&mut config);
+        config.validate().is_err()
+    }
+
+    #[test]
+    fn defaults_and_the_example_file_are_valid() {
+        assert!(Config::default().validate().is_ok());
+        let example = concat!(env!("CARGO_MANIFEST_DIR"), "/server.example.toml");
+        let config = Config::from_file(Path::new(example)).unwrap();
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn validate_rejects_out_of_range_settings() {
+        assert!(rejected(|config| config.server.port = 0));
+        assert!(rejected(
+            |config| config.server.address = String::from("localhost:80")
+        ));
+        assert!(rejected(|config| config.server.max_rooms = 0));
+        assert!(rejected(|config| config.server.max_rooms = 128));
+        assert!(rejected(|config| config.server.max_connections = 0));
+        assert!(rejected(|config| config.server.heartbeat_interval_ms = 50));
+        assert!(rejected(|config| config.server.heartbeat_timeout_ms = 3000));
+        assert!(rejected(|config| config.game.initial_walls = 0));
+        assert!(rejected(|config| config.game.engine = EngineKind::External));
+        assert!(!rejected(|config| config.server.max_rooms = 127));
+    }
+
+    #[test]
+    fn flags_are_checked_when_parsed() {
+        let path = config_file("flags", "");
+        let with = |extra: &[&str]| {
+            let mut all = vec!["--config", path.as_str()];
+            all.extend_from_slice(extra);
+            Config::from_args(args(&all))
+        };
+        assert!(with(&["--port", "0"]).is_err());
+        assert!(with(&["--port", "70000"]).is_err());
+        assert!(with(&["--max-rooms", "0"]).is_err());
+        assert!(with(&["--max-connections", "0"]).is_err());
+        assert!(with(&["--log-level", "verbose"]).is_err());
+        assert!(with(&["--engine", "sah"]).is_err());
+        assert!(with(&["--port"]).is_err());
+        assert!(with(&["--culoare", "rosu"]).is_err());
+        assert!(with(&["--log-level", "DEBUG"]).is_ok());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn config_file_is_checked_like_the_flags() {
+        for (name, contents) in [
+            ("level", "[server]\nlog_level = \"verbose\"\n"),
+            ("rooms", "[server]\nmax_rooms = 0\n"),
+            ("unknown", "[server]\nculoare = \"rosu\"\n"),
+        ] {
+            let path = config_file(name, contents);
+            assert!(
+                Config::from_args(args(&["--config", &path])).is_err(),
+                "{}",
+                name
+            );
+            fs::remove_file(&path).unwrap();
+        }
+        assert!(Config::from_args(args(&["--config", "/nu/exista.toml"])).is_err());
+    }
+
+    #[test]
+    fn flags_override_the_config_file() {
+        let path = config_file(
+            "override",
+            "[server]\nport = 9000\nmax_rooms = 10\nlog_level = \"debug\"\n\n[game]\ninitial_walls = 12\n",
+        );
+        let config = Config::from_args(args(&[
+            "--port",
+            "9100",
+            "--config",
+            &path,
+            "--initial-walls",
+            "20",
+        ]))
+        .unwrap();
+        assert_eq!(config.server.port, 9100);
+        assert_eq!(config.server.max_rooms, 10);
+        assert_eq!(config.server.log_level, LogLevel::Debug);
+        assert_eq!(config.game.initial_walls, 20);
+        assert_eq!(config.bind_address(), "127.0.0.1:9100");
+        fs::remove_file(&path).unwrap();
+    }
+}
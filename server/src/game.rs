@@ -0,0 +1,1094 @@
+use std::{
+    io::Write,
+    net::{Shutdown, TcpStream},
+    sync::mpsc::{Receiver, RecvTimeoutError, Sender},
+    thread,
+    time::{Duration, Instant},
+};
+
+use common::{
+    clock::TimeControl,
+    protocol::{Message, Snapshot},
+    rules::{generate_mouse_pos, generate_wall_positions, new_board, Board, Variant},
+    AppError, Position,
+};
+
+use serde::Serialize;
+
+use crate::{
+    chat::{chat_from, rate_limited, ChatLimiter},
+    config::Heartbeat,
+    matches::Match,
+    ConnectionGuard,
+};
+
+pub const MOUSE: usize = 0;
+pub const WALLS: usize = 1;
+const MAX_SPECTATORS: usize = 16;
+const REMATCH_TIMEOUT: Duration = Duration::from_secs(120);
+
+pub enum RoomEvent {
+    Received(usize, u32, Message),
+    Disconnected(usize, u32),
+    Reconnected(usize, TcpStream, ConnectionGuard),
+    Spectator(TcpStream, ConnectionGuard),
+}
+
+pub struct Player {
+    stream: Option<TcpStream>,
+    generation: u32,
+    disconnected_at: Option<Instant>,
+    last_seen: Instant,
+    chat: ChatLimiter,
+    _guard: Option<ConnectionGuard>,
+}
+
+impl Player {
+    pub fn new(stream: TcpStream, guard: Option<ConnectionGuard>) -> Self {
+        Player {
+            stream: Some(stream),
+            generation: 0,
+            disconnected_at: None,
+            last_seen: Instant::now(),
+            chat: ChatLimiter::new(),
+            _guard: guard,
+        }
+    }
+
+    fn send(&self, message: &Message) {
+        if let Some(stream) = self.stream.as_ref() {
+            if let Err(e) = (&*stream).write_all(&message.to_bytes()) {
+                AppError::WriteError(e.to_string()).log();
+            }
+        }
+    }
+
+    fn shutdown(&self) {
+        if let Some(stream) = self.stream.as_ref() {
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+    }
+}
+
+struct Spectator {
+    stream: TcpStream,
+    _guard: ConnectionGuard,
+}
+
+// Trimite mesajul tuturor spectatorilor si ii scoate pe cei deconectati.
+fn broadcast(spectators: &mut Vec<Spectator>, message: &Message) {
+    let bytes = message.to_bytes();
+    spectators.retain(|spectator| match (&spectator.stream).write_all(&bytes) {
+        Ok(_) => true,
+        Err(e) => {
+            debug!("Spectator deconectat: {}", e);
+            let _ = spectator.stream.shutdown(Shutdown::Both);
+            false
+        }
+    });
+}
+
+pub struct Timing {
+    pub grace: Duration,
+    pub heartbeat: Heartbeat,
+    pub time_control: TimeControl,
+}
+
+struct Clocks {
+    time_control: TimeControl,
+    remaining: [Duration; 2],
+    turn_started: Instant,
+}
+
+impl Clocks {
+    fn new(time_control: TimeControl) -> Option<Self> {
+        let initial = time_control.initial()?;
+        Some(Clocks {
+            time_control,
+            remaining: [initial, initial],
+            turn_started: Instant::now(),
+        })
+    }
+
+    fn deadline(&self, turn: usize) -> Instant {
+        self.turn_started + self.remaining[turn]
+    }
+
+    fn switch(&mut self, finished: usize) {
+        let used = self.turn_started.elapsed();
+        self.remaining[finished] = self.time_control.after_move(self.remaining[finished], used);
+        self.turn_started = Instant::now();
+    }
+
+    // La anularea unei mutari, cel intrerupt pierde timpul consumat, fara increment.
+    fn rewind(&mut self, interrupted: usize) {
+        let used = self.turn_started.elapsed();
+        self.remaining[interrupted] = self.remaining[interrupted].saturating_sub(used);
+        if let TimeControl::PerMove(per_move) = self.time_control {
+            self.remaining = [per_move, per_move];
+        }
+        self.turn_started = Instant::now();
+    }
+
+    fn message(&self, turn: usize) -> Message {
+        let mut remaining = self.remaining;
+        remaining[turn] = remaining[turn].saturating_sub(self.turn_started.elapsed());
+        Message::Clock(
+            remaining.map(|time| time.as_millis().min(u32::MAX as u128) as u32),
+            turn as u8 + 1,
+        )
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Ending {
+    Escape,
+    Trapped,
+    Timeout,
+    Forfeit,
+}
+
+// Locurile jucatorilor (nu rolurile) pentru castigator si soarece.
+pub struct GameResult {
+    pub winner: usize,
+    pub mouse: usize,
+    pub mouse_moves: u32,
+    pub ending: Ending,
+}
+
+// Starea de dinaintea unei mutari, pentru anulare.
+struct Ply {
+    role: usize,
+    board: Board,
+    mouse: (usize, usize),
+    mouse_moves: u32,
+    walls_left: u8,
+}
+
+pub struct GameState {
+    pub board: Board,
+    pub mouse: Position,
+    pub turn: usize,
+    pub variant: Variant,
+    // Cate ziduri mai are de pus jucatorul cu zidurile in tura curenta.
+    walls_left: u8,
+    mouse_moves: u32,
+    last_move: Option<(u8, u8)>,
+    history: Vec<Ply>,
+}
+
+impl GameState {
+    pub fn new(board: Board, mouse: Position, variant: Variant) -> Self {
+        GameState {
+            board,
+            mouse,
+            turn: MOUSE,
+            variant,
+            walls_left: variant.walls_per_turn(),
+            mouse_moves: 0,
+            last_move: None,
+            history: Vec::new(),
+        }
+    }
+
+    // Startul nu vine din tabelul de finaluri: tabelul acopera doar table de cel mult 5x5,
+    // iar partidele online se joaca mereu pe tabla intreaga (protocolul nu transmite marimea).
+    pub fn random(initial_walls: usize, variant: Variant) -> Self {
+        let (row, col) = generate_mouse_pos();
+        let walls = generate_wall_positions((row as u8, col as u8), initial_walls);
+        let mut board = new_board((row, col), &walls);
+        let mouse = Position::new(row, col);
+        variant.prepare(&mut board, &mouse, &mut rand::thread_rng());
+        GameState::new(board, mouse, variant)
+    }
+
+    fn snapshot(&self, player: usize) -> Snapshot {
+        Snapshot {
+            player: player as u8 + 1,
+            ..self.snapshot_for_spectator()
+        }
+    }
+
+    fn snapshot_for_spectator(&self) -> Snapshot {
+        Snapshot {
+            player: 0,
+            turn: self.turn as u8 + 1,
+            mouse: (self.mouse.x as u8, self.mouse.y as u8),
+            board: self.board,
+        }
+    }
+
+    // Jocul clasic nu trimite "ru", ca un client mai vechi sa nu primeasca un mesaj necunoscut.
+    fn rules(&self) -> Option<Message> {
+        (!self.variant.is_classic()).then_some(Message::Rules(self.variant, self.walls_left))
+    }
+
+    fn remember(&mut self, role: usize) {
+        self.history.push(Ply {
+            role,
+            board: self.board,
+            mouse: (self.mouse.x, self.mouse.y),
+            mouse_moves: self.mouse_moves,
+            walls_left: self.walls_left,
+        });
+    }
+
+    fn can_take_back(&self, role: usize) -> bool {
+        self.history.iter().any(|ply| ply.role == role)
+    }
+
+    // Anuleaza ultima mutare a rolului dat, impreuna cu raspunsul adversarului daca exista.
+    fn take_back(&mut self, role: usize) -> bool {
+        let Some(count) = self.history.iter().rev().position(|ply| ply.role == role) else {
+            return false;
+        };
+        let start = self.history.len() - count - 1;
+        let ply = self.history.drain(start..).next().unwrap();
+        self.board = ply.board;
+        self.mouse = Position::new(ply.mouse.0, ply.mouse.1);
+        self.mouse_moves = ply.mouse_moves;
+        self.walls_left = ply.walls_left;
+        self.turn = role;
+        true
+    }
+
+    // Muta soarecele pe o celula deja verificata; iesirea termina jocul.
+    fn move_mouse(&mut self, (row, col): (usize, usize)) -> Option<usize> {
+        let escaped = self.variant.is_exit(&self.board, (row, col));
+        self.remember(MOUSE);
+        self.board[self.mouse.x][self.mouse.y] = 0;
+        self.board[row][col] = 1;
+        self.mouse = Position::new(row, col);
+        self.last_move = Some((row as u8, col as u8));
+        self.mouse_moves += 1;
+        if escaped {
+            return Some(MOUSE);
+        }
+        self.turn = WALLS;
+        self.walls_left = self.variant.walls_per_turn();
+        None
+    }
+
+    // Intoarce castigatorul, daca mutarea a terminat jocul.
+    fn apply(&mut self, player: usize, message: Message) -> Option<usize> {
+        if player != self.turn {
+            debug!("Mesaj primit in afara turei: {:?}", message);
+            return None;
+        }
+
+        match (player, message) {
+            (MOUSE, Message::Move(row, col)) => {
+                let (row, col) = (row as usize, col as usize);
+                if !self
+                    .variant
+                    .mouse_moves(&self.board, &self.mouse)
+                    .contains(&(row, col))
+                {
+                    warn!("Mutare invalida a soarecelui: {}, {}", row, col);
+                    return None;
+                }
+                self.move_mouse((row, col))
+            }
+            // "wi" nu spune pe unde iese soarecele, asa ca il primim doar cand iesirea e unica;
+            // altfel clientul trebuie sa trimita mutarea pe iesire.
+            (MOUSE, Message::Win) => {
+                let exits: Vec<_> = self
+                    .variant
+                    .mouse_moves(&self.board, &self.mouse)
+                    .into_iter()
+                    .filter(|&cell| self.variant.is_exit(&self.board, cell))
+                    .collect();
+                match exits[..] {
+                    [cell] => self.move_mouse(cell),
+                    [] => {
+                        warn!("Soarecele nu poate iesi de pe tabla");
+                        None
+                    }
+                    _ => {
+                        warn!("Soarecele are mai multe iesiri, asteptam mutarea");
+                        None
+                    }
+                }
+            }
+            (MOUSE, Message::Lose) if self.variant.is_trapped(&self.board, &self.mouse) => {
+                Some(WALLS)
+            }
+            (WALLS, Message::Move(row, col)) => {
+                let (row, col) = (row as usize, col as usize);
+                if self.board[row][col] != 0 {
+                    warn!("Zid invalid: {}, {}", row, col);
+                    return None;
+                }
+                self.remember(WALLS);
+                self.board[row][col] = 2;
+                self.last_move = Some((row as u8, col as u8));
+                if self.variant.is_trapped(&self.board, &self.mouse) {
+                    self.turn = MOUSE;
+                    return Some(WALLS);
+                }
+                self.walls_left = self.walls_left.saturating_sub(1);
+                if self.walls_left == 0 {
+                    self.turn = MOUSE;
+                }
+                None
+            }
+            (_, message) => {
+                debug!("Mesaj ignorat: {:?}", message);
+                None
+            }
+        }
+    }
+}
+
+pub fn spawn_reader(
+    player: usize,
+    generation: u32,
+    stream: &TcpStream,
+    events: &Sender<RoomEvent>,
+) {
+    let mut stream = match stream.try_clone() {
+        Ok(stream) => stream,
+        Err(e) => {
+            AppError::CloneError(e.to_string()).log();
+            let _ = events.send(RoomEvent::Disconnected(player, generation));
+            return;
+        }
+    };
+    let events = events.clone();
+    thread::spawn(move || loop {
+        match Message::read_from(&mut stream) {
+            Ok(message) => {
+                if events
+                    .send(RoomEvent::Received(player, generation, message))
+                    .is_err()
+                {
+                    return;
+                }
+            }
+            Err(e) => {
+                debug!("Cititorul jucatorului {} s-a oprit: {}", player + 1, e);
+                let _ = events.send(RoomEvent::Disconnected(player, generation));
+                return;
+            }
+        }
+    });
+}
+
+pub enum Step {
+    Message(usize, Message),
+    Idle,
+    Closed,
+}
+
+// Jucatorii sunt indexati dupa loc; rolul fiecaruia se schimba la revansa.
+pub struct Session {
+    players: [Player; 2],
+    tokens: [String; 2],
+    names: [String; 2],
+    spectators: Vec<Spectator>,
+    events: Sender<RoomEvent>,
+    receiver: Receiver<RoomEvent>,
+    timing: Timing,
+    clocks: Option<Clocks>,
+    mouse_seat: usize,
+    score: [u8; 2],
+    takeback: Option<usize>,
+    ping_seq: u32,
+    next_ping: Instant,
+}
+
+impl Session {
+    pub fn new(
+        players: [Player; 2],
+        tokens: [String; 2],
+        names: [String; 2],
+        events: Sender<RoomEvent>,
+        receiver: Receiver<RoomEvent>,
+        timing: Timing,
+    ) -> Self {
+        for (seat, player) in players.iter().enumerate() {
+            if let Some(stream) = player.stream.as_ref() {
+                spawn_reader(seat, player.generation, stream, &events);
+            }
+        }
+        Session {
+            players,
+            tokens,
+            names,
+            spectators: Vec::new(),
+            events,
+            receiver,
+            timing,
+            clocks: None,
+            mouse_seat: 0,
+            score: [0, 0],
+            takeback: None,
+            ping_seq: 0,
+            next_ping: Instant::now(),
+        }
+    }
+
+    fn role(&self, seat: usize) -> usize {
+        if seat == self.mouse_seat {
+            MOUSE
+        } else {
+            WALLS
+        }
+    }
+
+    fn seat(&self, role: usize) -> usize {
+        if role == MOUSE {
+            self.mouse_seat
+        } else {
+            1 - self.mouse_seat
+        }
+    }
+
+    pub fn swap_roles(&mut self) {
+        self.mouse_seat = 1 - self.mouse_seat;
+    }
+
+    fn send_all(&mut self, message: &Message) {
+        for player in self.players.iter() {
+            player.send(message);
+        }
+        broadcast(&mut self.spectators, message);
+    }
+
+    fn name_messages(&self) -> [Message; 2] {
+        [
+            Message::Name(MOUSE as u8 + 1, self.names[self.seat(MOUSE)].clone()),
+            Message::Name(WALLS as u8 + 1, self.names[self.seat(WALLS)].clone()),
+        ]
+    }
+
+    // Cererea de anulare trece la adversar; daca o accepta, tabla si istoricul revin impreuna.
+    fn takeback(&mut self, seat: usize, wanted: bool, state: &mut GameState) {
+        match (wanted, self.takeback) {
+            (true, Some(requester)) if requester != seat => {
+                self.takeback = None;
+                let interrupted = state.turn;
+                if !state.take_back(self.role(requester)) {
+                    return;
+                }
+                info!("Jucatorul {} si-a anulat ultima mutare", requester + 1);
+                for seat in 0..2 {
+                    let snapshot = state.snapshot(self.role(seat));
+                    self.players[seat].send(&Message::Snapshot(snapshot));
+                }
+                if let Some(rules) = state.rules() {
+                    self.send_all(&rules);
+                }
+                broadcast(
+                    &mut self.spectators,
+                    &Message::Snapshot(state.snapshot_for_spectator()),
+                );
+                if let Some(clocks) = self.clocks.as_mut() {
+                    clocks.rewind(interrupted);
+                    let message = clocks.message(state.turn);
+                    self.send_all(&message);
+                }
+            }
+            (true, Some(_)) => {}
+            (true, None) if state.can_take_back(self.role(seat)) => {
+                self.takeback = Some(seat);
+                self.players[1 - seat].send(&Message::Takeback(true));
+            }
+            (true, None) => self.players[seat].send(&Message::Takeback(false)),
+            (false, Some(requester)) if requester != seat => {
+                self.takeback = None;
+                self.players[requester].send(&Message::Takeback(false));
+            }
+            (false, _) => {}
+        }
+    }
+
+    // Scorul seriei: jucatorii il primesc din perspectiva lor, spectatorii in ordinea rolurilor.
+    fn send_score(&mut self) {
+        for seat in 0..2 {
+            self.players[seat].send(&Message::Score([self.score[seat], self.score[1 - seat]]));
+        }
+        let score = Message::Score([self.score[self.seat(MOUSE)], self.score[self.seat(WALLS)]]);
+        broadcast(&mut self.spectators, &score);
+    }
+
+    fn result(&self, winner: usize, state: &GameState, ending: Ending) -> GameResult {
+        GameResult {
+            winner,
+            mouse: self.mouse_seat,
+            mouse_moves: state.mouse_moves,
+            ending,
+        }
+    }
+
+    pub fn send_match(&mut self, game_match: &Match) {
+        for seat in 0..2 {
+            self.players[seat].send(&Message::Match(game_match.state(seat)));
+        }
+        let state = Message::Match(game_match.state(self.mouse_seat));
+        broadcast(&mut self.spectators, &state);
+    }
+
+    // Pauza intre jocurile unui meci; intoarce false daca sesiunea s-a inchis.
+    pub fn pause(&mut self, duration: Duration, state: &GameState) -> bool {
+        let deadline = Instant::now() + duration;
+        self.clocks = None;
+        while Instant::now() < deadline {
+            if let Step::Closed = self.step(Some(deadline), state) {
+                return false;
+            }
+        }
+        true
+    }
+
+    fn finish(&mut self, winner: usize) {
+        self.players[winner].send(&Message::Win);
+        self.players[1 - winner].send(&Message::Lose);
+        let over = Message::Over(self.role(winner) as u8 + 1);
+        broadcast(&mut self.spectators, &over);
+        self.score[winner] = self.score[winner].saturating_add(1);
+        self.send_score();
+    }
+
+    // Joaca un joc si intoarce rezultatul, sau None daca jocul a fost abandonat.
+    pub fn play(&mut self, state: &mut GameState) -> Option<GameResult> {
+        self.clocks = Clocks::new(self.timing.time_control);
+        self.takeback = None;
+        let grace = self.timing.grace;
+        // Gaurile si poarta nu fac parte din handshake, asa ca la variante trimitem tabla.
+        let started = self.score != [0, 0];
+        let snapshot = started || !state.variant.is_classic();
+        if let Some(rules) = state.rules() {
+            self.send_all(&rules);
+        }
+        for seat in 0..2 {
+            let role = self.role(seat);
+            self.players[seat].send(&Message::Token(self.tokens[seat].clone()));
+            if snapshot {
+                self.players[seat].send(&Message::Snapshot(state.snapshot(role)));
+            }
+        }
+        if started {
+            self.send_score();
+        }
+        if snapshot {
+            broadcast(
+                &mut self.spectators,
+                &Message::Snapshot(state.snapshot_for_spectator()),
+            );
+        }
+        for message in self.name_messages() {
+            self.send_all(&message);
+        }
+        if let Some(message) = self
+            .clocks
+            .as_ref()
+            .map(|clocks| clocks.message(state.turn))
+        {
+            self.send_all(&message);
+        }
+
+        loop {
+            if self
+                .players
+                .iter()
+                .all(|player| player.disconnected_at.is_some())
+            {
+                info!("Ambii jucatori s-au deconectat");
+                broadcast(&mut self.spectators, &Message::Over(0));
+                return None;
+            }
+            if let Some(seat) = self.players.iter().position(|player| {
+                player
+                    .disconnected_at
+                    .is_some_and(|at| at.elapsed() >= grace)
+            }) {
+                info!("Jucatorul {} nu s-a mai intors", seat + 1);
+                self.players[1 - seat].send(&Message::Left);
+                let over = Message::Over(self.role(1 - seat) as u8 + 1);
+                broadcast(&mut self.spectators, &over);
+                return Some(self.result(1 - seat, state, Ending::Forfeit));
+            }
+            let loser = state.turn;
+            let loser_seat = self.seat(loser);
+            if let Some(clocks) = self.clocks.as_mut() {
+                if clocks.deadline(loser) <= Instant::now() {
+                    info!("Jucatorului {} i-a expirat timpul", loser_seat + 1);
+                    clocks.remaining[loser] = Duration::ZERO;
+                    let message = clocks.message(loser);
+                    self.send_all(&message);
+                    let winner = self.seat(1 - loser);
+                    self.finish(winner);
+                    return Some(self.result(winner, state, Ending::Timeout));
+                }
+            }
+
+            let deadline = self
+                .players
+                .iter()
+                .filter_map(|player| player.disconnected_at)
+                .map(|at| at + grace)
+                .chain(
+                    self.clocks
+                        .as_ref()
+                        .map(|clocks| clocks.deadline(state.turn)),
+                )
+                .min();
+
+            match self.step(deadline, state) {
+                Step::Message(seat, Message::Takeback(wanted)) => {
+                    self.takeback(seat, wanted, state)
+                }
+                Step::Message(seat, message) => {
+                    let turn = state.turn;
+                    let winner = state.apply(self.role(seat), message);
+                    if let Some((row, col)) = state.last_move.take() {
+                        self.players[1 - seat].send(&Message::Move(row, col));
+                        broadcast(&mut self.spectators, &Message::Move(row, col));
+                        // O mutare noua inseamna ca cererea de anulare nu mai e valabila.
+                        if self.takeback.take().is_some() {
+                            self.players[1 - seat].send(&Message::Takeback(false));
+                        }
+                    }
+                    if let Some(role) = winner {
+                        let winner = self.seat(role);
+                        info!("Jocul s-a terminat, a castigat jucatorul {}", winner + 1);
+                        self.finish(winner);
+                        let ending = if role == MOUSE {
+                            Ending::Escape
+                        } else {
+                            Ending::Trapped
+                        };
+                        return Some(self.result(winner, state, ending));
+                    }
+                    if let Some(clocks) = self.clocks.as_mut().filter(|_| state.turn != turn) {
+                        clocks.switch(turn);
+                        let message = clocks.message(state.turn);
+                        self.send_all(&message);
+                    }
+                }
+                Step::Idle => {}
+                Step::Closed => return None,
+            }
+        }
+    }
+
+    // Asteapta ca ambii jucatori sa ceara revansa.
+    pub fn wait_rematch(&mut self, state: &GameState) -> bool {
+        let deadline = Instant::now() + REMATCH_TIMEOUT;
+        let mut wanted = [false, false];
+        self.clocks = None;
+        loop {
+            if let Some(seat) = self
+                .players
+                .iter()
+                .position(|player| player.disconnected_at.is_some())
+            {
+                self.players[1 - seat].send(&Message::Rematch(false));
+                return false;
+            }
+            if Instant::now() >= deadline {
+                self.send_all(&Message::Rematch(false));
+                return false;
+            }
+            match self.step(Some(deadline), state) {
+                Step::Message(seat, Message::Rematch(true)) => {
+                    wanted[seat] = true;
+                    if wanted == [true, true] {
+                        info!("Revansa acceptata");
+                        return true;
+                    }
+                    self.players[1 - seat].send(&Message::Rematch(true));
+                }
+                Step::Message(seat, Message::Rematch(false)) => {
+                    self.players[1 - seat].send(&Message::Rematch(false));
+                    return false;
+                }
+                Step::Message(_, _) | Step::Idle => {}
+                Step::Closed => return false,
+            }
+        }
+    }
+
+    // Trateaza evenimentele comune (ping, chat, reconectari, spectatori) pana la `deadline`.
+    fn step(&mut self, deadline: Option<Instant>, state: &GameState) -> Step {
+        let heartbeat = self.timing.heartbeat;
+        let now = Instant::now();
+        if now >= self.next_ping {
+            self.ping_seq = self.ping_seq.wrapping_add(1);
+            for player in self.players.iter() {
+                player.send(&Message::Ping(self.ping_seq));
+            }
+            self.next_ping = now + heartbeat.interval;
+        }
+        for seat in 0..self.players.len() {
+            if self.players[seat].disconnected_at.is_none()
+                && self.players[seat].last_seen.elapsed() > heartbeat.timeout
+            {
+                info!("Jucatorul {} nu mai raspunde la ping", seat + 1);
+                self.disconnect(seat);
+                return Step::Idle;
+            }
+        }
+
+        let deadline = deadline.map_or(self.next_ping, |deadline| deadline.min(self.next_ping));
+        match self
+            .receiver
+            .recv_timeout(deadline.saturating_duration_since(Instant::now()))
+        {
+            Ok(RoomEvent::Received(seat, generation, message))
+                if generation == self.players[seat].generation =>
+            {
+                self.players[seat].last_seen = Instant::now();
+                match message {
+                    Message::Ping(seq) => self.players[seat].send(&Message::Pong(seq)),
+                    Message::Pong(_) => {}
+                    Message::Chat { text, .. } => {
+                        if !self.players[seat].chat.allow() {
+                            self.players[seat].send(&rate_limited());
+                        } else if let Some(message) = chat_from(&self.names[seat], &text) {
+                            self.send_all(&message);
+                        }
+                    }
+                    message => return Step::Message(seat, message),
+                }
+            }
+            Ok(RoomEvent::Disconnected(seat, generation))
+                if generation == self.players[seat].generation =>
+            {
+                self.disconnect(seat);
+            }
+            Ok(RoomEvent::Reconnected(seat, stream, guard)) => {
+                info!("Jucatorul {} s-a reconectat", seat + 1);
+                let snapshot = Message::Snapshot(state.snapshot(self.role(seat)));
+                let player = &mut self.players[seat];
+                player.shutdown();
+                player.generation += 1;
+                spawn_reader(seat, player.generation, &stream, &self.events);
+                player.stream = Some(stream);
+                player.disconnected_at = None;
+                player.last_seen = Instant::now();
+                player._guard = Some(guard);
+                if let Some(rules) = state.rules() {
+                    player.send(&rules);
+                }
+                player.send(&snapshot);
+                if let Some(clocks) = self.clocks.as_ref() {
+                    self.players[seat].send(&clocks.message(state.turn));
+                }
+                self.players[1 - seat].send(&Message::Back);
+                if self.players[1 - seat].disconnected_at.is_some() {
+                    self.players[seat].send(&Message::Waiting);
+                }
+            }
+            Ok(RoomEvent::Spectator(stream, guard)) => {
+                if self.spectators.len() >= MAX_SPECTATORS {
+                    info!("Prea multi spectatori, conexiunea este refuzata");
+                    let _ = (&stream).write_all(b"-5");
+                    return Step::Idle;
+                }
+                let mut spectator = vec![Spectator {
+                    stream,
+                    _guard: guard,
+                }];
+                for message in self.name_messages() {
+                    broadcast(&mut spectator, &message);
+                }
+                if let Some(rules) = state.rules() {
+                    broadcast(&mut spectator, &rules);
+                }
+                broadcast(
+                    &mut spectator,
+                    &Message::Snapshot(state.snapshot_for_spectator()),
+                );
+                if let Some(clocks) = self.clocks.as_ref() {
+                    broadcast(&mut spectator, &clocks.message(state.turn));
+                }
+                if self.score != [0, 0] {
+                    let score = Message::Score([
+                        self.score[self.seat(MOUSE)],
+                        self.score[self.seat(WALLS)],
+                    ]);
+                    broadcast(&mut spectator, &score);
+                }
+                self.spectators.append(&mut spectator);
+            }
+            Ok(_) => {}
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return Step::Closed,
+        }
+        Step::Idle
+    }
+
+    fn disconnect(&mut self, seat: usize) {
+        if self.players[seat].disconnected_at.is_some() {
+            return;
+        }
+        info!(
+            "Jucatorul {} s-a deconectat, il asteptam {} secunde",
+            seat + 1,
+            self.timing.grace.as_secs()
+        );
+        let player = &mut self.players[seat];
+        player.shutdown();
+        player.stream = None;
+        player.disconnected_at = Some(Instant::now());
+        player._guard = None;
+        self.players[1 - seat].send(&Message::Waiting);
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        for player in self.players.iter() {
+            player.shutdown();
+        }
+        for spectator in self.spectators.iter() {
+            let _ = spectator.stream.shutdown(Shutdown::Both);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{net::TcpListener, sync::mpsc};
+
+    const START: (usize, usize) = (5, 5);
+
+    fn classic() -> GameState {
+        GameState::new(
+            new_board(START, &[]),
+            Position::new(START.0, START.1),
+            Variant::default(),
+        )
+    }
+
+    fn mouse_move(state: &GameState) -> (u8, u8) {
+        let (row, col) = state.variant.mouse_moves(&state.board, &state.mouse)[0];
+        (row as u8, col as u8)
+    }
+
+    fn same_position(state: &GameState, board: &Board, mouse: Position, turn: usize) {
+        assert_eq!(&state.board, board);
+        assert_eq!(state.mouse, mouse);
+        assert_eq!(state.turn, turn);
+    }
+
+    #[test]
+    fn take_back_removes_the_move_and_the_reply() {
+        let mut state = classic();
+        assert!(!state.can_take_back(MOUSE));
+        assert!(!state.take_back(MOUSE));
+        let (board, mouse) = (state.board, state.mouse);
+
+        let (row, col) = mouse_move(&state);
+        assert_eq!(state.apply(MOUSE, Message::Move(row, col)), None);
+        assert_eq!(state.apply(WALLS, Message::Move(0, 0)), None);
+        assert_eq!(state.turn, MOUSE);
+        assert_eq!(state.mouse_moves, 1);
+
+        assert!(state.take_back(MOUSE));
+        same_position(&state, &board, mouse, MOUSE);
+        assert_eq!(state.mouse_moves, 0);
+        assert_eq!(state.walls_left, 1);
+        assert!(!state.can_take_back(MOUSE));
+        assert!(!state.can_take_back(WALLS));
+    }
+
+    #[test]
+    fn take_back_of_the_walls_keeps_the_mouse_move() {
+        let mut state = classic();
+        let (row, col) = mouse_move(&state);
+        state.apply(MOUSE, Message::Move(row, col));
+        let (board, mouse) = (state.board, state.mouse);
+        state.apply(WALLS, Message::Move(0, 0));
+
+        assert!(state.take_back(WALLS));
+        same_position(&state, &board, mouse, WALLS);
+        assert_eq!(state.mouse_moves, 1);
+        assert!(state.can_take_back(MOUSE));
+    }
+
+    #[test]
+    fn take_back_restores_the_walls_left_in_a_turn() {
+        let mut state = classic();
+        state.variant = Variant::parse("ziduri2").unwrap();
+        state.walls_left = state.variant.walls_per_turn();
+        let (row, col) = mouse_move(&state);
+        state.apply(MOUSE, Message::Move(row, col));
+        assert_eq!(state.walls_left, 2);
+        let before_walls = state.board;
+        state.apply(WALLS, Message::Move(0, 0));
+        assert_eq!((state.turn, state.walls_left), (WALLS, 1));
+        let after_first = state.board;
+        state.apply(WALLS, Message::Move(0, 1));
+        assert_eq!(state.turn, MOUSE);
+
+        // Ultima mutare a zidurilor este al doilea zid, nu toata tura.
+        assert!(state.take_back(WALLS));
+        same_position(&state, &after_first, state.mouse, WALLS);
+        assert_eq!(state.walls_left, 1);
+
+        assert!(state.take_back(WALLS));
+        same_position(&state, &before_walls, state.mouse, WALLS);
+        assert_eq!(state.walls_left, 2);
+    }
+
+    #[test]
+    fn rewind_charges_the_interrupted_player_without_increment() {
+        let mut clocks = Clocks::new(TimeControl::Bank {
+            initial: Duration::from_secs(60),
+            increment: Duration::from_secs(5),
+        })
+        .unwrap();
+        clocks.turn_started = Instant::now() - Duration::from_secs(10);
+        clocks.rewind(WALLS);
+        assert_eq!(clocks.remaining[MOUSE], Duration::from_secs(60));
+        assert!(clocks.remaining[WALLS] <= Duration::from_secs(50));
+        assert!(clocks.remaining[WALLS] > Duration::from_secs(49));
+
+        let mut clocks = Clocks::new(TimeControl::PerMove(Duration::from_secs(30))).unwrap();
+        clocks.turn_started = Instant::now() - Duration::from_secs(10);
+        clocks.rewind(MOUSE);
+        assert_eq!(clocks.remaining, [Duration::from_secs(30); 2]);
+    }
+
+    #[test]
+    fn switch_charges_the_mover_and_adds_the_increment() {
+        let mut clocks = Clocks::new(TimeControl::Bank {
+            initial: Duration::from_secs(60),
+            increment: Duration::from_secs(5),
+        })
+        .unwrap();
+        clocks.turn_started = Instant::now() - Duration::from_secs(10);
+        clocks.switch(MOUSE);
+        assert!(clocks.remaining[MOUSE] <= Duration::from_secs(55));
+        assert!(clocks.remaining[MOUSE] > Duration::from_secs(54));
+        assert_eq!(clocks.remaining[WALLS], Duration::from_secs(60));
+
+        let mut clocks = Clocks::new(TimeControl::PerMove(Duration::from_secs(30))).unwrap();
+        clocks.turn_started = Instant::now() - Duration::from_secs(20);
+        clocks.switch(WALLS);
+        assert_eq!(clocks.remaining, [Duration::from_secs(30); 2]);
+        assert!(Clocks::new(TimeControl::Unlimited).is_none());
+    }
+
+    #[test]
+    fn deadline_passes_once_the_turn_uses_the_remaining_time() {
+        let mut clocks = Clocks::new(TimeControl::PerMove(Duration::from_secs(30))).unwrap();
+        assert!(clocks.deadline(MOUSE) > Instant::now());
+        clocks.turn_started = Instant::now() - Duration::from_secs(31);
+        assert!(clocks.deadline(MOUSE) <= Instant::now());
+
+        // Mesajul de ceas scade din timpul celui la mutare ce a consumat deja.
+        clocks.turn_started = Instant::now() - Duration::from_secs(10);
+        match clocks.message(WALLS) {
+            Message::Clock([mouse, walls], turn) => {
+                assert_eq!(mouse, 30_000);
+                assert!((19_000..=20_000).contains(&walls));
+                assert_eq!(turn, 2);
+            }
+            message => panic!("mesaj neasteptat: {:?}", message),
+        }
+    }
+
+    fn connected_pair(listener: &TcpListener) -> (TcpStream, TcpStream) {
+        let client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (server, _) = listener.accept().unwrap();
+        client
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        (client, server)
+    }
+
+    // Citeste pana la primul mesaj cautat, sarind peste nume, ping-uri si altele.
+    fn wait_for(stream: &mut TcpStream, wanted: impl Fn(&Message) -> bool) -> Message {
+        loop {
+            let message = Message::read_from(stream).unwrap();
+            if wanted(&message) {
+                return message;
+            }
+        }
+    }
+
+    fn send(mut stream: &TcpStream, message: Message) {
+        stream.write_all(&message.to_bytes()).unwrap();
+    }
+
+    #[test]
+    fn takeback_request_is_cancelled_by_a_new_move_and_rolls_back_when_accepted() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let (mut mouse, mouse_server) = connected_pair(&listener);
+        let (mut walls, walls_server) = connected_pair(&listener);
+        let (events, receiver) = mpsc::channel();
+        let mut session = Session::new(
+            [
+                Player::new(mouse_server, None),
+                Player::new(walls_server, None),
+            ],
+            [String::new(), String::new()],
+            [String::from("ana"), String::from("dan")],
+            events,
+            receiver,
+            Timing {
+                grace: Duration::from_secs(60),
+                heartbeat: Heartbeat {
+                    interval: Duration::from_secs(60),
+                    timeout: Duration::from_secs(120),
+                },
+                time_control: TimeControl::Unlimited,
+            },
+        );
+
+        let mut state = classic();
+        let first = mouse_move(&state);
+        let mut expected = state.board;
+        expected[START.0][START.1] = 0;
+        expected[first.0 as usize][first.1 as usize] = 1;
+        expected[0][0] = 2;
+        let second = {
+            let mut after = classic();
+            after.apply(MOUSE, Message::Move(first.0, first.1));
+            mouse_move(&after)
+        };
+
+        let players = thread::spawn(move || {
+            let moved = |message: &Message| matches!(message, Message::Move(..));
+            let takeback = |message: &Message| matches!(message, Message::Takeback(_));
+
+            send(&mouse, Message::Move(first.0, first.1));
+            wait_for(&mut walls, moved);
+            send(&walls, Message::Move(0, 0));
+            wait_for(&mut mouse, moved);
+
+            send(&mouse, Message::Takeback(true));
+            assert_eq!(wait_for(&mut walls, takeback), Message::Takeback(true));
+            send(&mouse, Message::Move(second.0, second.1));
+            wait_for(&mut walls, moved);
+            assert_eq!(wait_for(&mut walls, takeback), Message::Takeback(false));
+
+            send(&walls, Message::Move(0, 1));
+            wait_for(&mut mouse, moved);
+            send(&mouse, Message::Takeback(true));
+            assert_eq!(wait_for(&mut walls, takeback), Message::Takeback(true));
+            send(&walls, Message::Takeback(true));
+            wait_for(&mut mouse, |message| {
+                matches!(message, Message::Snapshot(_))
+            });
+        });
+
+        // Jucatorii pleaca dupa ultima anulare, asa ca jocul se termina fara rezultat.
+        assert!(session.play(&mut state).is_none());
+        players.join().unwrap();
+        same_position(
+            &state,
+            &expected,
+            Position::new(first.0 as usize, first.1 as usize),
+            MOUSE,
+        );
+        assert_eq!(state.mouse_moves, 1);
+        assert!(session.takeback.is_none());
+    }
+}
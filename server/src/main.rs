@@ -1,27 +1,83 @@
-use std::cmp;
 use std::{
-    collections::{HashSet, VecDeque},
-    io::{Read, Write},
+    io::{ErrorKind, Read, Write},
     net::{TcpListener, TcpStream},
-    sync::{Arc, RwLock},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{self, Sender},
+        Arc, Mutex, RwLock,
+    },
     thread,
+    time::{Duration, Instant},
 };
 
-use common::{AppError, Position};
-use rand::Rng;
+use common::{
+    engine::{Budget, Engine, Heuristic, Local},
+    protocol::{JoinRequest, Message, RoomOptions},
+    rules::{
+        check_any_left_move, generate_mouse_pos, generate_wall_positions, is_border, legal_moves,
+        new_board, Board,
+    },
+    AppError, Position,
+};
+
+#[macro_use]
+mod log;
+mod chat;
+mod config;
+mod game;
+mod matches;
+mod tournament;
+
+use chat::Lobby;
+use config::{Config, GameSettings, Heartbeat};
+use game::{GameState, Player, RoomEvent, Session, Timing};
+use matches::Match;
+use tournament::{Entry, Tournaments};
+
+const WAIT_POLL: Duration = Duration::from_millis(100);
+const MATCH_PAUSE: Duration = Duration::from_secs(5);
 
 fn main() -> std::io::Result<()> {
-    let listener = TcpListener::bind("127.0.0.1:8080")?;
-    println!("Serverul rulează pe 127.0.0.1:8080");
+    let config = match Config::from_args(std::env::args().skip(1)) {
+        Ok(config) => config,
+        Err(e) => {
+            e.log();
+            std::process::exit(2);
+        }
+    };
+    log::set_level(config.server.log_level);
 
-    let rooms_manager = Arc::new(RwLock::new(RoomsManager::new()));
+    let listener = TcpListener::bind(config.bind_address())?;
+    info!("Serverul rulează pe {}", config.bind_address());
+
+    let rooms_manager = Arc::new(RwLock::new(RoomsManager::new(config.server.max_rooms)));
+    let connections = Arc::new(AtomicUsize::new(0));
+    let lobby = Arc::new(Mutex::new(Lobby::new()));
+    let tournaments = Arc::new(Mutex::new(Tournaments::new()));
+    let config = Arc::new(config);
 
     for stream in listener.incoming() {
         let rooms_manager = Arc::clone(&rooms_manager);
         match stream {
-            Ok(stream) => {
+            Ok(mut stream) => {
+                let guard = ConnectionGuard::new(&connections);
+                if guard.count() > config.server.max_connections {
+                    warn!("Prea multe conexiuni, refuzam {:?}", stream.peer_addr());
+                    let _ = stream.write_all(b"-3");
+                    continue;
+                }
+                let config = Arc::clone(&config);
+                let lobby = Arc::clone(&lobby);
+                let tournaments = Arc::clone(&tournaments);
                 thread::spawn(move || {
-                    handle_client(stream, rooms_manager.clone());
+                    handle_client(
+                        stream,
+                        rooms_manager.clone(),
+                        guard,
+                        config,
+                        lobby,
+                        tournaments,
+                    );
                 });
             }
             Err(e) => {
@@ -33,34 +89,101 @@ fn main() -> std::io::Result<()> {
     Ok(())
 }
 
+struct ConnectionGuard {
+    connections: Arc<AtomicUsize>,
+}
+
+impl ConnectionGuard {
+    fn new(connections: &Arc<AtomicUsize>) -> Self {
+        connections.fetch_add(1, Ordering::SeqCst);
+        ConnectionGuard {
+            connections: Arc::clone(connections),
+        }
+    }
+
+    fn count(&self) -> usize {
+        self.connections.load(Ordering::SeqCst)
+    }
+}
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        self.connections.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
 struct Room {
     player1: Option<TcpStream>,
     player2: Option<TcpStream>,
     is_full: bool,
     code: i8,
     is_taken: bool,
+    ready: u8,
+    connections: Vec<ConnectionGuard>,
+    tokens: [String; 2],
+    events: Option<Sender<RoomEvent>>,
+    options: RoomOptions,
+    names: [String; 2],
+    tickets: Option<[String; 2]>,
+    tournament: Option<Entry>,
 }
 
 impl Room {
-    fn new(code: i8) -> Self {
+    fn new(code: i8, options: RoomOptions) -> Self {
         Room {
             player1: None,
             player2: None,
             is_full: false,
             code,
             is_taken: false,
+            ready: 0,
+            connections: Vec::new(),
+            tokens: [String::new(), String::new()],
+            events: None,
+            options,
+            names: [String::new(), String::new()],
+            tickets: None,
+            tournament: None,
         }
     }
 
-    fn add_to_room(&mut self, stream: &TcpStream) -> bool {
+    // In camerele de turneu fiecare bilet are locul lui, iar numele vin de la turneu.
+    fn add_with_ticket(
+        &mut self,
+        stream: &TcpStream,
+        guard: ConnectionGuard,
+        ticket: &str,
+    ) -> bool {
+        let seat = self
+            .tickets
+            .as_ref()
+            .and_then(|tickets| tickets.iter().position(|t| t == ticket));
+        let slot = match seat {
+            Some(0) => &mut self.player1,
+            Some(_) => &mut self.player2,
+            None => return false,
+        };
+        if slot.is_some() {
+            return false;
+        }
+        *slot = Some(stream.try_clone().unwrap());
+        self.connections.push(guard);
+        self.is_full = self.player1.is_some() && self.player2.is_some();
+        true
+    }
+
+    fn add_to_room(&mut self, stream: &TcpStream, guard: ConnectionGuard, name: String) -> bool {
+        self.connections.push(guard);
         if self.player1.is_none() {
             self.player1 = Some(stream.try_clone().unwrap());
+            self.names[0] = name;
             if self.player2.is_some() {
                 self.is_full = true;
             }
             return true;
         } else if self.player2.is_none() {
             self.player2 = Some(stream.try_clone().unwrap());
+            self.names[1] = name;
             if self.player1.is_some() {
                 self.is_full = true;
             }
@@ -72,38 +195,105 @@ impl Room {
 
 struct RoomsManager {
     rooms: Vec<Room>,
+    max_rooms: usize,
 }
 
 impl RoomsManager {
-    fn new() -> Self {
-        RoomsManager { rooms: Vec::new() }
+    fn new(max_rooms: usize) -> Self {
+        RoomsManager {
+            rooms: Vec::new(),
+            max_rooms,
+        }
     }
 
-    fn add_to_random_room(&mut self, stream: &TcpStream) -> i8 {
+    fn add_to_random_room(
+        &mut self,
+        stream: &TcpStream,
+        guard: ConnectionGuard,
+        request: JoinRequest,
+    ) -> Option<i8> {
+        // Jucatorul intra doar intr-o camera cu aceleasi optiuni; altfel ar pierde ce a cerut.
         for room in self.rooms.iter_mut() {
-            if !room.is_full {
-                room.add_to_room(stream);
-                return room.code;
+            if !room.is_full && room.tickets.is_none() && room.options == request.options {
+                room.add_to_room(stream, guard, request.name);
+                return Some(room.code);
             }
         }
+        if self.rooms.len() >= self.max_rooms {
+            return None;
+        }
 
-        self.rooms.push(Room::new(self.rooms.len() as i8));
-        let room_id = self.rooms.len() - 1;
-        self.rooms[room_id].add_to_room(stream);
-        room_id as i8
+        // Codul trebuie sa fie liber: o camera veche poate inca juca dupa ce altele s-au inchis.
+        let code = self.free_code()?;
+        let mut room = Room::new(code, request.options);
+        room.add_to_room(stream, guard, request.name);
+        self.rooms.push(room);
+        Some(code)
     }
 
-    fn add_to_specific_room(&mut self, stream: &TcpStream, code: i8) -> i8 {
+    fn free_code(&self) -> Option<i8> {
+        (0..=i8::MAX).find(|code| self.rooms.iter().all(|room| room.code != *code))
+    }
+
+    fn add_to_specific_room(
+        &mut self,
+        stream: &TcpStream,
+        code: i8,
+        guard: ConnectionGuard,
+        request: JoinRequest,
+    ) -> Option<i8> {
         for room in self.rooms.iter_mut() {
+            if room.code == code && room.tickets.is_some() {
+                return room
+                    .add_with_ticket(stream, guard, &request.ticket)
+                    .then_some(code);
+            }
             if room.code == code && !room.is_full {
-                room.add_to_room(stream);
-                return room.code;
+                room.add_to_room(stream, guard, request.name);
+                return Some(room.code);
             }
         }
-        self.rooms.push(Room::new(code));
+        if self.rooms.len() >= self.max_rooms {
+            return None;
+        }
+        self.rooms.push(Room::new(code, request.options));
         let room_id = self.rooms.len() - 1;
-        self.rooms[room_id].add_to_room(stream);
-        code
+        self.rooms[room_id].add_to_room(stream, guard, request.name);
+        Some(code)
+    }
+
+    fn reserve_room(
+        &mut self,
+        options: RoomOptions,
+        names: [String; 2],
+        tickets: [String; 2],
+        entry: Entry,
+    ) -> Option<i8> {
+        if self.rooms.len() >= self.max_rooms {
+            return None;
+        }
+        let code = self.free_code()?;
+        let mut room = Room::new(code, options);
+        room.names = names;
+        room.tickets = Some(tickets);
+        room.tournament = Some(entry);
+        self.rooms.push(room);
+        Some(code)
+    }
+
+    // Anuleaza o camera de turneu in care jocul nu a inceput; intoarce cine era prezent.
+    fn cancel_reservation(&mut self, code: i8) -> Option<[bool; 2]> {
+        let index = self
+            .rooms
+            .iter()
+            .position(|room| room.code == code && room.tickets.is_some())?;
+        let room = &self.rooms[index];
+        if room.is_full {
+            return None;
+        }
+        let present = [room.player1.is_some(), room.player2.is_some()];
+        self.rooms.remove(index);
+        Some(present)
     }
 
     fn remove_from_room(&mut self, code: usize) {
@@ -116,15 +306,72 @@ impl RoomsManager {
     }
 
     fn show_rooms(&self) {
-        println!("Camere curente: ");
+        debug!("Camere curente: ");
         for room in self.rooms.iter() {
-            println!("Camera {}", room.code);
-            println!("Player1: {:?}", room.player1);
-            println!("Player2: {:?}", room.player2);
-            println!("---------------------------");
+            debug!("Camera {}", room.code);
+            debug!("Player1: {:?}", room.player1);
+            debug!("Player2: {:?}", room.player2);
+            debug!("---------------------------");
+        }
+    }
+
+    fn find_token(&self, token: &str) -> Option<(Sender<RoomEvent>, usize)> {
+        for room in self.rooms.iter() {
+            if let (Some(events), Some(player)) = (
+                room.events.as_ref(),
+                room.tokens.iter().position(|t| !t.is_empty() && t == token),
+            ) {
+                return Some((events.clone(), player));
+            }
+        }
+        None
+    }
+
+    fn find_game(&self, code: i8) -> Option<Sender<RoomEvent>> {
+        self.rooms
+            .iter()
+            .find(|room| room.code == code)
+            .and_then(|room| room.events.clone())
+    }
+
+    fn remove_waiting_room(&mut self, code: i8) -> bool {
+        match self
+            .rooms
+            .iter()
+            .position(|room| room.code == code && !room.is_full)
+        {
+            // Camera de turneu ramane rezervata pentru cand revine jucatorul.
+            Some(index) if self.rooms[index].tickets.is_some() => {
+                let room = &mut self.rooms[index];
+                room.player1 = None;
+                room.player2 = None;
+                room.connections.clear();
+                true
+            }
+            Some(index) => {
+                self.rooms.remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Jocul porneste doar dupa ce ambii jucatori au confirmat startul, ca sa nu citim
+    // din acelasi socket pe doua fire.
+    fn mark_ready(&mut self, code: i8) -> bool {
+        match self.rooms.iter_mut().find(|room| room.code == code) {
+            Some(room) => {
+                room.ready += 1;
+                room.ready == 2
+            }
+            None => false,
         }
     }
 
+    fn has_room(&self, code: i8) -> bool {
+        self.rooms.iter().any(|room| room.code == code)
+    }
+
     fn check_room(&mut self, code: i8) -> bool {
         for room in self.rooms.iter() {
             if room.code == code && room.is_full {
@@ -135,26 +382,86 @@ impl RoomsManager {
     }
 }
 
-fn handle_client(mut stream: TcpStream, rooms_manager: Arc<RwLock<RoomsManager>>) {
-    let mut room_id: i8 = -1;
-
+fn handle_client(
+    mut stream: TcpStream,
+    rooms_manager: Arc<RwLock<RoomsManager>>,
+    guard: ConnectionGuard,
+    config: Arc<Config>,
+    lobby: Arc<Mutex<Lobby>>,
+    tournaments: Arc<Mutex<Tournaments>>,
+) {
+    let request: JoinRequest;
+
+    // Locul din max_connections e deja ocupat, asa ca nu asteptam cererea la nesfarsit.
+    let heartbeat = config.server.heartbeat();
+    if let Err(e) = stream.set_read_timeout(Some(heartbeat.timeout)) {
+        AppError::StreamUnavailable(e.to_string()).log();
+    }
     let mut buffer: [u8; 1024] = [0; 1024];
-    match stream.read(&mut buffer) {
+    let read = stream.read(&mut buffer);
+    if let Err(e) = stream.set_read_timeout(None) {
+        AppError::StreamUnavailable(e.to_string()).log();
+    }
+    match read {
         Ok(0) => {
-            println!("Clientul s-a deconectat inainte sa fie pus intr-o camera!!!");
+            info!("Clientul s-a deconectat inainte sa fie pus intr-o camera!!!");
             return;
         }
         Ok(n) => {
             let code = String::from_utf8_lossy(&buffer[..n]);
             let code = code.trim();
 
-            match code.parse::<i8>() {
-                Ok(num) => {
-                    println!("Cod camera de la client: {}", num);
-                    room_id = num;
-                }
-                Err(e) => println!("Eroare la parsare: {}", e),
+            if let Some(token) = code.strip_prefix("resume ") {
+                resume_game(stream, token, &rooms_manager, guard);
+                return;
+            }
+
+            if let Some(name) = code.strip_prefix("lobby") {
+                chat::join_lobby(stream, name, &lobby, heartbeat, guard);
+                return;
+            }
+
+            if let Some(request) = code.strip_prefix("tournament ") {
+                tournament::join_tournament(
+                    stream,
+                    request,
+                    &tournaments,
+                    &rooms_manager,
+                    heartbeat,
+                    guard,
+                );
+                return;
+            }
+
+            if let Some(code) = code.strip_prefix("spectate ") {
+                spectate_game(stream, code, &rooms_manager, guard);
+                return;
             }
+
+            request = match JoinRequest::parse(code)
+                .and_then(|request| match i8::try_from(request.code) {
+                    Ok(_) => Ok(request),
+                    Err(_) => Err(AppError::UnexpectedResponse(format!(
+                        "cod de camera invalid: {}",
+                        request.code
+                    ))),
+                })
+                .and_then(|request| match request.code {
+                    -2 => default_options_only(&request.options, "camera contra calculatorului")
+                        .map(|_| request),
+                    _ if !request.ticket.is_empty() => {
+                        classic_only(&request.options, "camera de turneu").map(|_| request)
+                    }
+                    _ => Ok(request),
+                }) {
+                Ok(request) => request,
+                Err(e) => {
+                    warn!("Cerere respinsa: {}", e);
+                    let _ = stream.write_all(b"-4");
+                    return;
+                }
+            };
+            debug!("Cod camera de la client: {}", request.code);
         }
         Err(e) => {
             AppError::ReadError(e.to_string()).log();
@@ -162,12 +469,20 @@ fn handle_client(mut stream: TcpStream, rooms_manager: Arc<RwLock<RoomsManager>>
         }
     }
 
+    let mut room_id = request.code as i8;
     match room_id {
         -1 => {
             let mut rooms_manager = rooms_manager.write().unwrap();
-            room_id = rooms_manager.add_to_random_room(&stream);
+            room_id = match rooms_manager.add_to_random_room(&stream, guard, request) {
+                Some(room_id) => room_id,
+                None => {
+                    warn!("Numarul maxim de camere a fost atins");
+                    let _ = stream.write_all(b"-3");
+                    return;
+                }
+            };
 
-            println!(
+            info!(
                 "Clientul {:?} a fost adaugat in camera: {}",
                 stream, room_id
             );
@@ -188,18 +503,25 @@ fn handle_client(mut stream: TcpStream, rooms_manager: Arc<RwLock<RoomsManager>>
                     AppError::WriteError(e.to_string()).log();
                 }
             }
-            read_ok(&Some(stream.try_clone().unwrap()));
             stream.flush().unwrap();
             let stream_for_computer = stream.try_clone().expect("Nu s-a putut clona stream-ul");
 
-            handle_room_computer(room_id, Some(stream_for_computer));
+            handle_room_computer(room_id, Some(stream_for_computer), &config.game, heartbeat);
+            drop(guard);
             return;
         }
         _ => {
             let mut rooms_manager = rooms_manager.write().unwrap();
-            room_id = rooms_manager.add_to_specific_room(&stream, room_id);
+            room_id = match rooms_manager.add_to_specific_room(&stream, room_id, guard, request) {
+                Some(room_id) => room_id,
+                None => {
+                    warn!("Numarul maxim de camere a fost atins");
+                    let _ = stream.write_all(b"-3");
+                    return;
+                }
+            };
 
-            println!(
+            info!(
                 "Clientul {:?} a fost adaugat in camera: {}",
                 stream, room_id
             );
@@ -214,16 +536,10 @@ fn handle_client(mut stream: TcpStream, rooms_manager: Arc<RwLock<RoomsManager>>
             rooms_manager.show_rooms();
         }
     }
-    loop {
-        {
-            let mut rooms_manager = rooms_manager.write().unwrap();
-            if rooms_manager.check_room(room_id) {
-                println!("Camera este plina {}", room_id);
-                break;
-            }
-        }
-    }
     read_ok(&Some(stream.try_clone().unwrap()));
+    if !wait_for_opponent(&stream, room_id, &rooms_manager, heartbeat) {
+        return;
+    }
 
     let message = format!("Jocul a inceput in camera! {}", room_id);
     match stream.write_all(message.as_bytes()) {
@@ -234,12 +550,206 @@ fn handle_client(mut stream: TcpStream, rooms_manager: Arc<RwLock<RoomsManager>>
     }
     stream.flush().unwrap();
     read_ok(&Some(stream));
-    handle_room(room_id, rooms_manager);
+    if !rooms_manager.write().unwrap().mark_ready(room_id) {
+        return;
+    }
+    handle_room(room_id, rooms_manager, &config);
 }
 
-fn handle_room(room_id: i8, rooms_manager: Arc<RwLock<RoomsManager>>) {
+fn wait_for_opponent(
+    stream: &TcpStream,
+    room_id: i8,
+    rooms_manager: &Arc<RwLock<RoomsManager>>,
+    heartbeat: Heartbeat,
+) -> bool {
+    if let Err(e) = stream.set_read_timeout(Some(WAIT_POLL)) {
+        AppError::StreamUnavailable(e.to_string()).log();
+    }
+
+    let mut inbox = Vec::new();
+    let mut buffer = [0; 64];
+    let mut ping_seq: u32 = 0;
+    let mut next_ping = Instant::now() + heartbeat.interval;
+    let mut last_seen = Instant::now();
+
+    loop {
+        if rooms_manager.write().unwrap().check_room(room_id) {
+            debug!("Camera este plina {}", room_id);
+            break;
+        }
+        if !rooms_manager.read().unwrap().has_room(room_id) {
+            info!("Camera {} a fost inchisa inainte de joc", room_id);
+            return false;
+        }
+        if Instant::now() >= next_ping {
+            ping_seq = ping_seq.wrapping_add(1);
+            if let Err(e) = (&*stream).write_all(&Message::Ping(ping_seq).to_bytes()) {
+                AppError::WriteError(e.to_string()).log();
+            }
+            next_ping = Instant::now() + heartbeat.interval;
+        }
+
+        let alive = match (&*stream).read(&mut buffer) {
+            Ok(0) => false,
+            Ok(n) => {
+                inbox.extend_from_slice(&buffer[..n]);
+                while let Ok(Some((_, used))) = Message::parse(&inbox) {
+                    inbox.drain(..used);
+                    last_seen = Instant::now();
+                }
+                true
+            }
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                last_seen.elapsed() <= heartbeat.timeout
+            }
+            Err(e) => {
+                AppError::ReadError(e.to_string()).log();
+                false
+            }
+        };
+
+        if !alive && rooms_manager.write().unwrap().remove_waiting_room(room_id) {
+            info!("Jucatorul din camera {} a plecat inainte de joc", room_id);
+            return false;
+        }
+    }
+
+    if let Err(e) = stream.set_read_timeout(None) {
+        AppError::StreamUnavailable(e.to_string()).log();
+    }
+    true
+}
+
+// Citeste mesajele unui client aflat in afara jocurilor (chat comun, turneu) si ii trimite
+// ping-uri, ca o conexiune moarta sa nu tina firul si locul ocupate la nesfarsit.
+// Se opreste cand clientul pleaca sau tace mai mult de heartbeat.timeout.
+fn serve_idle_client(
+    stream: &TcpStream,
+    heartbeat: Heartbeat,
+    mut handle: impl FnMut(Message),
+) -> AppError {
+    serve_client(stream, heartbeat, |message| {
+        handle(message);
+        true
+    })
+    .expect_err("clientul din afara jocurilor nu se opreste singur")
+}
+
+// Ca serve_idle_client, dar handle poate incheia singur conversatia intorcand false.
+fn serve_client(
+    stream: &TcpStream,
+    heartbeat: Heartbeat,
+    mut handle: impl FnMut(Message) -> bool,
+) -> Result<(), AppError> {
+    if let Err(e) = stream.set_read_timeout(Some(WAIT_POLL)) {
+        return Err(AppError::StreamUnavailable(e.to_string()));
+    }
+
+    let mut inbox = Vec::new();
+    let mut buffer = [0; 1024];
+    let mut ping_seq: u32 = 0;
+    let mut next_ping = Instant::now() + heartbeat.interval;
+    let mut last_seen = Instant::now();
+
+    loop {
+        if Instant::now() >= next_ping {
+            ping_seq = ping_seq.wrapping_add(1);
+            if let Err(e) = (&*stream).write_all(&Message::Ping(ping_seq).to_bytes()) {
+                return Err(AppError::WriteError(e.to_string()));
+            }
+            next_ping = Instant::now() + heartbeat.interval;
+        }
+
+        match (&*stream).read(&mut buffer) {
+            Ok(0) => {
+                return Err(AppError::ConnectionError(String::from(
+                    "clientul a inchis conexiunea",
+                )))
+            }
+            Ok(n) => inbox.extend_from_slice(&buffer[..n]),
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                if last_seen.elapsed() > heartbeat.timeout {
+                    return Err(AppError::ConnectionError(String::from(
+                        "clientul nu a mai raspuns la ping",
+                    )));
+                }
+                continue;
+            }
+            Err(e) => return Err(AppError::ReadError(e.to_string())),
+        }
+
+        loop {
+            match Message::parse(&inbox) {
+                Ok(Some((message, used))) => {
+                    inbox.drain(..used);
+                    last_seen = Instant::now();
+                    if !matches!(message, Message::Pong(_)) && !handle(message) {
+                        return Ok(());
+                    }
+                }
+                Ok(None) => break,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+fn resume_game(
+    stream: TcpStream,
+    token: &str,
+    rooms_manager: &Arc<RwLock<RoomsManager>>,
+    guard: ConnectionGuard,
+) {
+    let found = rooms_manager.read().unwrap().find_token(token.trim());
+    match found {
+        Some((events, player)) => {
+            info!("Jucatorul {} revine in joc: {:?}", player + 1, stream);
+            if events
+                .send(RoomEvent::Reconnected(player, stream, guard))
+                .is_err()
+            {
+                info!("Jocul s-a terminat intre timp");
+            }
+        }
+        None => {
+            info!("Cod de reluare necunoscut: {}", token);
+            let _ = stream.shutdown(std::net::Shutdown::Both);
+        }
+    }
+}
+
+fn spectate_game(
+    mut stream: TcpStream,
+    code: &str,
+    rooms_manager: &Arc<RwLock<RoomsManager>>,
+    guard: ConnectionGuard,
+) {
+    let found = code
+        .trim()
+        .parse::<i8>()
+        .ok()
+        .and_then(|code| rooms_manager.read().unwrap().find_game(code));
+    match found {
+        Some(events) => {
+            info!("Spectator nou in camera {}: {:?}", code.trim(), stream);
+            if events.send(RoomEvent::Spectator(stream, guard)).is_err() {
+                info!("Jocul s-a terminat intre timp");
+            }
+        }
+        None => {
+            info!("Nu exista niciun joc in camera {}", code.trim());
+            let _ = stream.write_all(b"-5");
+        }
+    }
+}
+
+fn handle_room(room_id: i8, rooms_manager: Arc<RwLock<RoomsManager>>, config: &Config) {
+    let settings = &config.game;
     let mut player1: Option<TcpStream> = None;
     let mut player2: Option<TcpStream> = None;
+    let mut options = RoomOptions::default();
+    let mut names = [String::new(), String::new()];
+    let mut entry = None;
 
     {
         let mut rooms_manager = rooms_manager.write().unwrap();
@@ -251,6 +761,9 @@ fn handle_room(room_id: i8, rooms_manager: Arc<RwLock<RoomsManager>>) {
                 room.is_taken = true;
                 player1 = room.player1.as_ref().and_then(|s| s.try_clone().ok());
                 player2 = room.player2.as_ref().and_then(|s| s.try_clone().ok());
+                options = room.options.clone();
+                names = room.names.clone();
+                entry = room.tournament.take();
                 break;
             }
         }
@@ -283,29 +796,9 @@ fn handle_room(room_id: i8, rooms_manager: Arc<RwLock<RoomsManager>>) {
     read_ok(&player1);
     read_ok(&player2);
 
-    let walls = generate_wall_positions((row as u8, col as u8));
-
-    let mut buffer: [u8; 20] = [0; 20];
-    buffer[0] = walls[0].0;
-    buffer[1] = walls[0].1;
-    buffer[2] = walls[1].0;
-    buffer[3] = walls[1].1;
-    buffer[4] = walls[2].0;
-    buffer[5] = walls[2].1;
-    buffer[6] = walls[3].0;
-    buffer[7] = walls[3].1;
-    buffer[8] = walls[4].0;
-    buffer[9] = walls[4].1;
-    buffer[10] = walls[5].0;
-    buffer[11] = walls[5].1;
-    buffer[12] = walls[6].0;
-    buffer[13] = walls[6].1;
-    buffer[14] = walls[7].0;
-    buffer[15] = walls[7].1;
-    buffer[16] = walls[8].0;
-    buffer[17] = walls[8].1;
-    buffer[18] = walls[9].0;
-    buffer[19] = walls[9].1;
+    let walls = generate_wall_positions((row as u8, col as u8), settings.initial_walls);
+
+    let buffer: Vec<u8> = walls.iter().flat_map(|&(row, col)| [row, col]).collect();
 
     if let Err(e) = player1.as_ref().unwrap().write_all(&buffer) {
         AppError::WriteError(e.to_string()).log();
@@ -317,122 +810,160 @@ fn handle_room(room_id: i8, rooms_manager: Arc<RwLock<RoomsManager>>) {
     }
     read_ok(&player2);
 
-    println!("Incepem jocul!");
+    info!(
+        "Incepem jocul in camera {} (timp: {}, reguli: {})!",
+        room_id, options.time_control, options.variant
+    );
 
-    let mut close = false;
+    let tokens = [new_token(), new_token()];
+    let (events, receiver) = mpsc::channel();
+    let mut guards = Vec::new();
+    {
+        let mut rooms_manager = rooms_manager.write().unwrap();
+        if let Some(room) = rooms_manager
+            .rooms
+            .iter_mut()
+            .find(|room| room.code == room_id)
+        {
+            room.tokens = tokens.clone();
+            room.events = Some(events.clone());
+            guards = std::mem::take(&mut room.connections);
+        }
+    }
+    let mut guards = guards.into_iter();
+    let players = [
+        Player::new(player1.unwrap(), guards.next()),
+        Player::new(player2.unwrap(), guards.next()),
+    ];
+    let mut board = new_board((row, col), &walls);
+    options.variant.prepare(
+        &mut board,
+        &Position::new(row, col),
+        &mut rand::thread_rng(),
+    );
+
+    for (index, name) in names.iter_mut().enumerate() {
+        if name.is_empty() {
+            *name = format!("Jucator {}", index + 1);
+        }
+    }
 
+    let new_match = || Match::new(room_id, options.games, names.clone());
+    let mut current = (options.games > 1).then(new_match);
+    let mut session = Session::new(
+        players,
+        tokens,
+        names.clone(),
+        events,
+        receiver,
+        Timing {
+            grace: Duration::from_secs(settings.reconnect_grace_secs),
+            heartbeat: config.server.heartbeat(),
+            time_control: options.time_control,
+        },
+    );
+    let mut state = GameState::new(board, Position::new(row, col), options.variant);
     loop {
-        let mut buffer: [u8; 2] = [0; 2];
-        match player1.as_ref().unwrap().read(&mut buffer) {
-            Ok(n) if n > 0 => {
-                if buffer[0] == 119 && buffer[1] == b'i' {
-                    buffer[0] = b'l';
-                    buffer[1] = b'o';
-                    close = true;
-                } else if buffer[0] == b'l' && buffer[1] == b'o' {
-                    buffer[0] = b'w';
-                    buffer[1] = b'i';
-                    close = true;
-                }
-                // println!("--> P2.send: {}, {}", buffer[0], buffer[1]);
-                let _n = player2
-                    .as_ref()
-                    .unwrap()
-                    .write(&buffer)
-                    .expect("Eroare la scriere");
-            }
-            Ok(0) => {
-                println!("Clientul s-a deconectat!");
-
-                let mut buffer = [0; 2];
-                buffer[0] = b'y';
-                buffer[1] = b'e';
-
-                let _n = player2
-                    .as_ref()
-                    .unwrap()
-                    .write(&buffer)
-                    .expect("Eroare la scriere");
-
-                let _ = player1.as_ref().unwrap().shutdown(std::net::Shutdown::Both);
-                let _ = player2.as_ref().unwrap().shutdown(std::net::Shutdown::Both);
-                {
-                    let mut rooms_manager = rooms_manager.write().unwrap();
-                    rooms_manager.remove_from_room(room_id as usize);
-                }
-                return;
-            }
-            Ok(_) => {}
-            Err(e) => {
-                AppError::ReadError(e.to_string()).log();
-            }
+        if let Some(game_match) = &current {
+            session.send_match(game_match);
         }
-        let mut buffer: [u8; 2] = [0; 2];
-
-        match player2.as_ref().unwrap().read(&mut buffer) {
-            Ok(n) if n > 0 => {
-                if buffer[0] == b'w' && buffer[1] == b'i' {
-                    buffer[0] = b'l';
-                    buffer[1] = b'o';
-                    close = true;
-                } else if buffer[0] == b'l' && buffer[1] == b'o' {
-                    buffer[0] = b'w';
-                    buffer[1] = b'i';
-                    close = true;
-                }
-                // println!("<-- P1.send: {}, {}", buffer[0], buffer[1]);
-                let _n = player1
-                    .as_ref()
-                    .unwrap()
-                    .write(&buffer)
-                    .expect("Eroare la scriere");
+        let Some(result) = session.play(&mut state) else {
+            if let Some(entry) = &entry {
+                entry.report(None, true);
             }
-            Ok(0) => {
-                println!("Clientul s-a deconectat!");
-
-                let mut buffer = [0; 2];
-                buffer[0] = b'y';
-                buffer[1] = b'e';
-
-                let _n = player1
-                    .as_ref()
-                    .unwrap()
-                    .write(&buffer)
-                    .expect("Eroare la scriere");
-                if let Err(e) = player1.as_ref().unwrap().shutdown(std::net::Shutdown::Both) {
-                    AppError::StreamUnavailable(e.to_string()).log();
-                }
-                if let Err(e) = player2.as_ref().unwrap().shutdown(std::net::Shutdown::Both) {
-                    AppError::StreamUnavailable(e.to_string()).log();
-                }
-                {
-                    let mut rooms_manager = rooms_manager.write().unwrap();
-                    rooms_manager.remove_from_room(room_id as usize);
+            break;
+        };
+        if let Some(game_match) = current.as_mut() {
+            game_match.record(result);
+            session.send_match(game_match);
+            if !game_match.is_over() {
+                if !session.pause(MATCH_PAUSE, &state) {
+                    break;
                 }
-                return;
+                session.swap_roles();
+                state = GameState::random(settings.initial_walls, options.variant);
+                continue;
             }
-            Ok(_) => {}
-            Err(e) => {
-                AppError::ReadError(e.to_string()).log();
+            info!("Meciul din camera {} s-a terminat", room_id);
+            if let Err(e) = game_match.save(&config.server.match_records) {
+                e.log();
             }
-        }
-        if close {
-            {
-                let mut rooms_manager = rooms_manager.write().unwrap();
-                rooms_manager.remove_from_room(room_id as usize);
+            if let Some(entry) = &entry {
+                entry.report(game_match.winner().0, false);
+                break;
             }
+            *game_match = new_match();
+        } else if let Some(entry) = &entry {
+            entry.report(Some(result.winner), false);
+            break;
+        }
+        if !session.wait_rematch(&state) {
             break;
         }
+        info!("Revansa in camera {}, rolurile se schimba", room_id);
+        session.swap_roles();
+        state = GameState::random(settings.initial_walls, options.variant);
     }
+    drop(session);
+
+    let mut rooms_manager = rooms_manager.write().unwrap();
+    rooms_manager.remove_from_room(room_id as usize);
+}
+
+// Calculatorul si turneele joaca doar dupa regulile clasice, asa ca refuzam variantele cerute.
+fn classic_only(options: &RoomOptions, place: &str) -> Result<(), AppError> {
+    if options.variant.is_classic() {
+        return Ok(());
+    }
+    Err(AppError::ConfigError(format!(
+        "{} se joaca doar cu regulile clasice, nu '{}'",
+        place, options.variant
+    )))
+}
+
+// Calculatorul joaca o singura partida clasica, fara ceas; orice alta optiune ar fi ignorata.
+fn default_options_only(options: &RoomOptions, place: &str) -> Result<(), AppError> {
+    if *options == RoomOptions::default() {
+        return Ok(());
+    }
+    Err(AppError::ConfigError(format!(
+        "{} nu accepta optiuni de camera, nu '{}'",
+        place,
+        options.encode()
+    )))
+}
+
+fn new_token() -> String {
+    format!("{:016x}", rand::random::<u64>())
 }
 
-fn handle_room_computer(room_id: i8, stream: Option<TcpStream>) {
+fn handle_room_computer(
+    room_id: i8,
+    stream: Option<TcpStream>,
+    settings: &GameSettings,
+    heartbeat: Heartbeat,
+) {
+    // Pornim motorul inainte de handshake, ca un motor extern lent sa nu intarzie prima mutare.
+    let engine = settings.engine();
+    // In handshake clientul nu primeste ping-uri, asa ca fiecare confirmare are un termen.
+    if let Err(e) = stream
+        .as_ref()
+        .unwrap()
+        .set_read_timeout(Some(heartbeat.timeout))
+    {
+        AppError::StreamUnavailable(e.to_string()).log();
+    }
+    if !read_ok(&stream) {
+        return;
+    }
     let message = format!("Jocul a inceput in camera! {}", room_id);
     if let Err(e) = stream.as_ref().unwrap().write_all(message.as_bytes()) {
         AppError::WriteError(e.to_string()).log();
     }
     stream.as_ref().unwrap().flush().unwrap();
-    read_ok(&stream);
+    if !read_ok(&stream) {
+        return;
+    }
 
     let role_msg = "Tu pui zidurile!";
     if let Err(e) = stream.as_ref().unwrap().write_all(role_msg.as_bytes()) {
@@ -442,250 +973,174 @@ fn handle_room_computer(room_id: i8, stream: Option<TcpStream>) {
         AppError::WriteError(e.to_string()).log();
     }
 
-    read_ok(&stream);
+    if !read_ok(&stream) {
+        return;
+    }
 
-    let (mut mouse_x, mut mouse_y) = send_mouse_pos_computer(&stream);
+    let (mouse_x, mouse_y) = send_mouse_pos_computer(&stream);
     stream
         .as_ref()
         .unwrap()
         .flush()
         .expect("Eroare la flush după trimiterea poziției mouse-ului");
-    read_ok(&stream);
-
-    let walls = generate_wall_positions((mouse_x as u8, mouse_y as u8));
-
-    let mut buffer: [u8; 20] = [0; 20];
-    buffer[0] = walls[0].0;
-    buffer[1] = walls[0].1;
-    buffer[2] = walls[1].0;
-    buffer[3] = walls[1].1;
-    buffer[4] = walls[2].0;
-    buffer[5] = walls[2].1;
-    buffer[6] = walls[3].0;
-    buffer[7] = walls[3].1;
-    buffer[8] = walls[4].0;
-    buffer[9] = walls[4].1;
-    buffer[10] = walls[5].0;
-    buffer[11] = walls[5].1;
-    buffer[12] = walls[6].0;
-    buffer[13] = walls[6].1;
-    buffer[14] = walls[7].0;
-    buffer[15] = walls[7].1;
-    buffer[16] = walls[8].0;
-    buffer[17] = walls[8].1;
-    buffer[18] = walls[9].0;
-    buffer[19] = walls[9].1;
-
-    if let Err(e) = stream.as_ref().unwrap().write_all(&buffer) {
-        AppError::WriteError(e.to_string()).log();
+    if !read_ok(&stream) {
+        return;
     }
 
-    read_ok(&stream);
+    let walls = generate_wall_positions((mouse_x as u8, mouse_y as u8), settings.initial_walls);
 
-    let mut board: [[u8; 11]; 11] = [[0; 11]; 11];
-    board[mouse_x][mouse_y] = 1;
+    let buffer: Vec<u8> = walls.iter().flat_map(|&(row, col)| [row, col]).collect();
 
-    board[walls[0].0 as usize][walls[0].1 as usize] = 2;
-    board[walls[1].0 as usize][walls[1].1 as usize] = 2;
-    board[walls[2].0 as usize][walls[2].1 as usize] = 2;
-    board[walls[3].0 as usize][walls[3].1 as usize] = 2;
-    board[walls[4].0 as usize][walls[4].1 as usize] = 2;
-    board[walls[5].0 as usize][walls[5].1 as usize] = 2;
-    board[walls[6].0 as usize][walls[6].1 as usize] = 2;
-    board[walls[7].0 as usize][walls[7].1 as usize] = 2;
-    board[walls[8].0 as usize][walls[8].1 as usize] = 2;
-    board[walls[9].0 as usize][walls[9].1 as usize] = 2;
-
-    loop {
-        if !check_any_left_move(&board, &Position::new(mouse_x, mouse_y)) {
-            buffer[0] = b'w';
-            buffer[1] = b'i';
+    if let Err(e) = stream.as_ref().unwrap().write_all(&buffer) {
+        AppError::WriteError(e.to_string()).log();
+    }
 
-            if let Err(e) = stream.as_ref().unwrap().write_all(&buffer) {
-                AppError::WriteError(e.to_string()).log();
-            }
+    if !read_ok(&stream) {
+        return;
+    }
 
-            break;
+    let mut board = new_board((mouse_x, mouse_y), &walls);
+    let mut mouse = Position::new(mouse_x, mouse_y);
+    let mut engine =
+        match engine.and_then(|mut engine| engine.new_game(&board, &mouse, 1).map(|_| engine)) {
+            Ok(engine) => engine,
+            Err(e) => fallback_engine(e, &board, &mouse),
+        };
+    info!(
+        "Camera {}: calculatorul joaca cu motorul {}",
+        room_id,
+        engine.name()
+    );
+
+    let stream = stream.unwrap();
+    if !computer_turn(
+        &stream,
+        &mut engine,
+        &mut board,
+        &mut mouse,
+        settings.budget(),
+    ) {
+        return;
+    }
+    // Cat asteptam zidul clientului ii trimitem ping-uri; daca tace, jocul se incheie.
+    let result = serve_client(&stream, heartbeat, |message| match message {
+        Message::Win => {
+            info!("Clientul a câștigat!");
+            false
         }
-        if let Some((row, col)) = find_shortest_path_to_border(&board, mouse_x, mouse_y) {
-            board[mouse_x][mouse_y] = 0;
-            board[row][col] = 1;
-            mouse_x = row;
-            mouse_y = col;
-            if mouse_x == 0 || mouse_x == 10 || mouse_y == 0 || mouse_y == 10 {
-                buffer[0] = b'l';
-                buffer[1] = b'o';
-
-                if let Err(e) = stream.as_ref().unwrap().write_all(&buffer) {
-                    AppError::WriteError(e.to_string()).log();
-                }
-                break;
+        Message::Move(r, c) => {
+            let (r, c) = (r as usize, c as usize);
+            // Octetii vin direct de pe socket: un zid in afara tablei sau pe o celula
+            // ocupata inchide conexiunea, inainte sa ajunga pe tabla sau la motor.
+            if !legal_moves(&board, &mouse, 2).contains(&(r, c)) {
+                AppError::InvalidMove(format!(
+                    "Camera {}: zid invalid de la client: {}, {}",
+                    room_id, r, c
+                ))
+                .log();
+                return false;
             }
-            let move_bytes = [row as u8, col as u8];
-            if let Err(e) = stream.as_ref().unwrap().write_all(&move_bytes) {
-                AppError::WriteError(e.to_string()).log();
-            }
-            if let Err(e) = stream.as_ref().unwrap().flush() {
-                AppError::WriteError(e.to_string()).log();
+            board[r][c] = 2;
+            if let Err(e) = engine.opponent_move((r, c)) {
+                engine = fallback_engine(e, &board, &mouse);
             }
-
-            // println!("Mutarea computerului: {},{}", row, col);
-        } else {
-            AppError::UnexpectedResponse(String::from("Fara mutari posibile")).log();
-            break;
+            computer_turn(
+                &stream,
+                &mut engine,
+                &mut board,
+                &mut mouse,
+                settings.budget(),
+            )
         }
-
-        let mut client_move: [u8; 2] = [0; 2];
-        match stream.as_ref().unwrap().read_exact(&mut client_move) {
-            Ok(()) => {
-                if client_move[0] == b'w' && client_move[1] == b'i' {
-                    println!("Clientul a câștigat!");
-                    break;
-                }
-                let r = client_move[0] as usize;
-                let c = client_move[1] as usize;
-                board[r][c] = 2;
-                // println!("Clientul a blocat poziția: {},{}", r, c);
-            }
-            Err(e) => {
-                AppError::ReadError(e.to_string()).log();
-                break;
-            }
+        message => {
+            debug!("Mesaj ignorat in camera {}: {:?}", room_id, message);
+            true
         }
+    });
+    if let Err(e) = result {
+        e.log();
     }
 }
 
-fn find_shortest_path_to_border(
-    board: &[[u8; 11]; 11],
-    start_x: usize,
-    start_y: usize,
-) -> Option<(usize, usize)> {
-    let directions_even = [(-1, -1), (-1, 0), (0, -1), (0, 1), (1, -1), (1, 0)];
-    let directions_odd = [(-1, 0), (-1, 1), (0, -1), (0, 1), (1, 0), (1, 1)];
-
-    let mut queue = VecDeque::new();
-    let mut visited = [[false; 11]; 11];
-
-    queue.push_back((start_x, start_y, None));
-    visited[start_x][start_y] = true;
-
-    let distance_to_border = |x: usize, y: usize| -> i32 {
-        cmp::min(
-            cmp::min(x as i32, (10 - x) as i32),
-            cmp::min(y as i32, (10 - y) as i32),
-        )
-    };
-
-    let mut best_candidate: Option<((usize, usize), i32)> = None;
-
-    while let Some((x, y, first_move)) = queue.pop_front() {
-        if x == 0 || y == 0 || x == 10 || y == 10 {
-            if let Some(fm) = first_move {
-                return Some(fm);
-            }
-        }
-
-        if let Some(fm) = first_move {
-            let d = distance_to_border(x, y);
-            if best_candidate.is_none() || d < best_candidate.unwrap().1 {
-                best_candidate = Some((fm, d));
-            }
-        }
-
-        let directions = if x % 2 == 0 {
-            &directions_even
-        } else {
-            &directions_odd
-        };
-        for &(dx, dy) in directions {
-            let nx = (x as isize + dx) as usize;
-            let ny = (y as isize + dy) as usize;
-
-            if nx < 11
-                && ny < 11
-                && !visited[nx][ny]
-                && board[nx][ny] == 0
-                && check_move(nx, ny, x as u8, y as u8, board)
-            {
-                visited[nx][ny] = true;
-                let new_first_move = first_move.or(Some((nx, ny)));
-                queue.push_back((nx, ny, new_first_move));
-            }
-        }
-    }
-
-    if let Some((cand, _)) = best_candidate {
-        if cand != (start_x, start_y) {
-            return Some(cand);
+// Mutarea calculatorului; false cand partida s-a terminat.
+fn computer_turn(
+    stream: &TcpStream,
+    engine: &mut Box<dyn Engine>,
+    board: &mut Board,
+    mouse: &mut Position,
+    budget: Budget,
+) -> bool {
+    let mut stream = stream;
+    if !check_any_left_move(board, mouse) {
+        if let Err(e) = stream.write_all(b"wi") {
+            AppError::WriteError(e.to_string()).log();
         }
+        return false;
     }
-
-    let directions = if start_x % 2 == 0 {
-        &directions_even
-    } else {
-        &directions_odd
+    let Some((row, col)) = engine_move(engine, board, mouse, budget) else {
+        AppError::UnexpectedResponse(String::from("Fara mutari posibile")).log();
+        return false;
     };
-    for &(dx, dy) in directions {
-        let nx = (start_x as isize + dx) as usize;
-        let ny = (start_y as isize + dy) as usize;
-        if nx < 11
-            && ny < 11
-            && board[nx][ny] == 0
-            && check_move(nx, ny, start_x as u8, start_y as u8, board)
-        {
-            return Some((nx, ny));
+    board[mouse.x][mouse.y] = 0;
+    board[row][col] = 1;
+    *mouse = Position::new(row, col);
+    if is_border(row, col) {
+        if let Err(e) = stream.write_all(b"lo") {
+            AppError::WriteError(e.to_string()).log();
         }
+        return false;
     }
-
-    None
+    if let Err(e) = stream.write_all(&[row as u8, col as u8]) {
+        AppError::WriteError(e.to_string()).log();
+    }
+    if let Err(e) = stream.flush() {
+        AppError::WriteError(e.to_string()).log();
+    }
+    true
 }
 
-fn check_move(r: usize, c: usize, mouse_x: u8, mouse_y: u8, board: &[[u8; 11]; 11]) -> bool {
-    if board[r][c] != 0 {
-        return false;
-    }
+// Daca motorul nu porneste, greseste sau nu raspunde, partida continua cu euristica.
+fn fallback_engine(error: AppError, board: &Board, mouse: &Position) -> Box<dyn Engine> {
+    warn!(
+        "Motorul calculatorului a cedat ({}), trecem pe euristica",
+        error
+    );
+    let mut engine = Local::new(Box::new(Heuristic));
+    let _ = engine.new_game(board, mouse, 1);
+    Box::new(engine)
+}
 
-    let mut valid_moves: Vec<(i32, i32)> = Vec::new();
-
-    let mouse_x = mouse_x as i32;
-    let mouse_y = mouse_y as i32;
-    let r = r as i32;
-    let c = c as i32;
-    if mouse_x % 2 == 0 {
-        valid_moves.push((mouse_x - 1, mouse_y - 1));
-        valid_moves.push((mouse_x - 1, mouse_y));
-        valid_moves.push((mouse_x, mouse_y - 1));
-        valid_moves.push((mouse_x, mouse_y + 1));
-        valid_moves.push((mouse_x + 1, mouse_y - 1));
-        valid_moves.push((mouse_x + 1, mouse_y));
-    } else {
-        valid_moves.push((mouse_x - 1, mouse_y));
-        valid_moves.push((mouse_x - 1, mouse_y + 1));
-        valid_moves.push((mouse_x, mouse_y - 1));
-        valid_moves.push((mouse_x, mouse_y + 1));
-        valid_moves.push((mouse_x + 1, mouse_y));
-        valid_moves.push((mouse_x + 1, mouse_y + 1));
-    }
-
-    if let Some(_pos) = valid_moves.iter().find(|&&(x, y)| x == r && y == c) {
-        return true;
-    }
-    println!("Mutarea {}, {} este invalida!", r, c);
-    false
+fn engine_move(
+    engine: &mut Box<dyn Engine>,
+    board: &Board,
+    mouse: &Position,
+    budget: Budget,
+) -> Option<(usize, usize)> {
+    let error = match engine.pick_move(budget) {
+        Ok(Some(cell)) if legal_moves(board, mouse, 1).contains(&cell) => return Some(cell),
+        Ok(cell) => AppError::InvalidMove(format!("{} a propus {:?}", engine.name(), cell)),
+        Err(e) => e,
+    };
+    *engine = fallback_engine(error, board, mouse);
+    engine.pick_move(budget).ok().flatten()
 }
 
-fn read_ok(stream: &Option<TcpStream>) {
+// Asteapta confirmarea clientului; false daca a inchis conexiunea sau nu a raspuns la timp.
+fn read_ok(stream: &Option<TcpStream>) -> bool {
     let mut buffer = [0; 1024];
 
-    if let Err(e) = stream.as_ref().unwrap().read(&mut buffer) {
-        AppError::ReadError(e.to_string()).log();
+    match stream.as_ref().unwrap().read(&mut buffer) {
+        Ok(0) => false,
+        Ok(_) => true,
+        Err(e) => {
+            AppError::ReadError(e.to_string()).log();
+            false
+        }
     }
 }
 
 fn send_mouse_pos(slot1: &Option<TcpStream>, slot2: &Option<TcpStream>) -> (usize, usize) {
-    let mut rng = rand::thread_rng();
-    let row = rng.gen_range(3..=7);
-    let col = rng.gen_range(3..=7);
+    let (row, col) = generate_mouse_pos();
 
     if let Err(e) = slot1
         .as_ref()
@@ -701,14 +1156,12 @@ fn send_mouse_pos(slot1: &Option<TcpStream>, slot2: &Option<TcpStream>) -> (usiz
     {
         AppError::WriteError(e.to_string()).log();
     }
-    println!("Soarecele este la {}, {}", row, col);
+    debug!("Soarecele este la {}, {}", row, col);
     (row, col)
 }
 
 fn send_mouse_pos_computer(stream: &Option<TcpStream>) -> (usize, usize) {
-    let mut rng = rand::thread_rng();
-    let row = rng.gen_range(3..=7);
-    let col = rng.gen_range(3..=7);
+    let (row, col) = generate_mouse_pos();
 
     if let Err(e) = stream
         .as_ref()
@@ -717,53 +1170,87 @@ fn send_mouse_pos_computer(stream: &Option<TcpStream>) -> (usize, usize) {
     {
         AppError::WriteError(e.to_string()).log();
     }
-    println!("Soarecele este la {}, {}", row, col);
+    debug!("Soarecele este la {}, {}", row, col);
     (row, col)
 }
-fn generate_wall_positions(mouse_pos: (u8, u8)) -> Vec<(u8, u8)> {
-    let mut rng = rand::thread_rng();
-    let mut positions = HashSet::new();
 
-    while positions.len() < 10 {
-        let row = rng.gen_range(0..11);
-        let col = rng.gen_range(0..11);
-        let pos = (row as u8, col as u8);
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn join(
+        manager: &mut RoomsManager,
+        listener: &TcpListener,
+        connections: &Arc<AtomicUsize>,
+        options: &str,
+    ) -> Option<i8> {
+        let _client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (stream, _) = listener.accept().unwrap();
+        let request = JoinRequest {
+            code: -1,
+            name: String::new(),
+            ticket: String::new(),
+            options: RoomOptions::parse(options).unwrap(),
+        };
+        manager.add_to_random_room(&stream, ConnectionGuard::new(connections), request)
+    }
 
-        if pos == mouse_pos {
-            continue;
-        }
-        positions.insert(pos);
+    #[test]
+    fn random_rooms_only_pair_players_with_the_same_options() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let connections = Arc::new(AtomicUsize::new(0));
+        let mut manager = RoomsManager::new(8);
+
+        let classic = join(&mut manager, &listener, &connections, "");
+        let timed = join(&mut manager, &listener, &connections, "timp=mutare:30");
+        let best_of = join(&mut manager, &listener, &connections, "meci=3");
+        assert_eq!(classic, Some(0));
+        assert_eq!(timed, Some(1));
+        assert_eq!(best_of, Some(2));
+
+        assert_eq!(
+            join(&mut manager, &listener, &connections, "meci=3"),
+            best_of
+        );
+        assert_eq!(
+            join(&mut manager, &listener, &connections, "timp=mutare:30"),
+            timed
+        );
+        assert_eq!(join(&mut manager, &listener, &connections, ""), classic);
+        assert!(manager.rooms.iter().all(|room| room.is_full));
+        assert_eq!(
+            manager.rooms[1].options,
+            RoomOptions::parse("timp=mutare:30").unwrap()
+        );
     }
 
-    positions.into_iter().collect()
-}
-pub fn check_any_left_move(board: &[[u8; 11]; 11], mouse: &Position) -> bool {
-    let mut valid_moves: Vec<(i32, i32)> = Vec::new();
-    let mouse_x = mouse.x as i32;
-    let mouse_y = mouse.y as i32;
-
-    if mouse_x % 2 == 0 {
-        valid_moves.push((mouse_x - 1, mouse_y - 1));
-        valid_moves.push((mouse_x - 1, mouse_y));
-        valid_moves.push((mouse_x, mouse_y - 1));
-        valid_moves.push((mouse_x, mouse_y + 1));
-        valid_moves.push((mouse_x + 1, mouse_y - 1));
-        valid_moves.push((mouse_x + 1, mouse_y));
-    } else {
-        valid_moves.push((mouse_x - 1, mouse_y));
-        valid_moves.push((mouse_x - 1, mouse_y + 1));
-        valid_moves.push((mouse_x, mouse_y - 1));
-        valid_moves.push((mouse_x, mouse_y + 1));
-        valid_moves.push((mouse_x + 1, mouse_y));
-        valid_moves.push((mouse_x + 1, mouse_y + 1));
-    }
-
-    let mut ok = false;
-    for pos in valid_moves {
-        if board[pos.0 as usize][pos.1 as usize] == 0 {
-            ok = true;
-            break;
+    #[test]
+    fn random_rooms_take_the_lowest_free_code() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let connections = Arc::new(AtomicUsize::new(0));
+        let mut manager = RoomsManager::new(2);
+
+        assert_eq!(join(&mut manager, &listener, &connections, ""), Some(0));
+        assert_eq!(
+            join(&mut manager, &listener, &connections, "meci=3"),
+            Some(1)
+        );
+        assert_eq!(join(&mut manager, &listener, &connections, "meci=5"), None);
+        manager.remove_from_room(0);
+        assert_eq!(
+            join(&mut manager, &listener, &connections, "meci=5"),
+            Some(0)
+        );
+        assert_eq!(connections.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn computer_rooms_reject_any_room_option() {
+        let place = "camera contra calculatorului";
+        assert!(default_options_only(&RoomOptions::default(), place).is_ok());
+        for options in ["timp=mutare:30", "meci=3", "reguli=ziduri2"] {
+            let options = RoomOptions::parse(options).unwrap();
+            assert!(default_options_only(&options, place).is_err());
         }
     }
-    ok
 }
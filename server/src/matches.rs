@@ -0,0 +1,274 @@
+use std::{
+    cmp::Ordering,
+    fs::OpenOptions,
+    io::Write,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use common::{protocol::MatchState, AppError};
+use serde::Serialize;
+
+use crate::game::{Ending, GameResult};
+
+#[derive(Clone, Copy, Default)]
+struct MouseStats {
+    escapes: u32,
+    escape_moves: u32,
+    survived_moves: u32,
+}
+
+pub struct Match {
+    room: i8,
+    games: u8,
+    names: [String; 2],
+    results: Vec<GameResult>,
+    started: u64,
+}
+
+impl Match {
+    pub fn new(room: i8, games: u8, names: [String; 2]) -> Self {
+        Match {
+            room,
+            games,
+            names,
+            results: Vec::new(),
+            started: unix_time(),
+        }
+    }
+
+    pub fn record(&mut self, result: GameResult) {
+        self.results.push(result);
+    }
+
+    pub fn score(&self) -> [u8; 2] {
+        let mut score = [0, 0];
+        for result in self.results.iter() {
+            score[result.winner] += 1;
+        }
+        score
+    }
+
+    // Meciul se termina dupa N jocuri, la abandon sau cand scorul nu mai poate fi egalat.
+    pub fn is_over(&self) -> bool {
+        let played = self.results.len() as u8;
+        let [first, second] = self.score();
+        let remaining = self.games.saturating_sub(played);
+        played >= self.games
+            || self
+                .results
+                .last()
+                .is_some_and(|result| result.ending == Ending::Forfeit)
+            || first.abs_diff(second) > remaining
+    }
+
+    fn stats(&self, seat: usize) -> MouseStats {
+        let mut stats = MouseStats::default();
+        for result in self.results.iter().filter(|result| result.mouse == seat) {
+            if result.winner == seat {
+                stats.escapes += 1;
+                stats.escape_moves += result.mouse_moves;
+            } else {
+                stats.survived_moves += result.mouse_moves;
+            }
+        }
+        stats
+    }
+
+    // Intoarce castigatorul si daca a fost nevoie de departajare.
+    pub fn winner(&self) -> (Option<usize>, bool) {
+        if let Some(result) = self
+            .results
+            .last()
+            .filter(|result| result.ending == Ending::Forfeit)
+        {
+            return (Some(result.winner), false);
+        }
+        let [first, second] = self.score();
+        match first.cmp(&second) {
+            Ordering::Greater => return (Some(0), false),
+            Ordering::Less => return (Some(1), false),
+            Ordering::Equal => {}
+        }
+
+        // La egalitate castiga evadarea mai rapida, apoi cine a rezistat mai multe mutari ca soarece.
+        let (a, b) = (self.stats(0), self.stats(1));
+        let speed = if a.escapes > 0 && b.escapes > 0 {
+            (b.escape_moves * a.escapes).cmp(&(a.escape_moves * b.escapes))
+        } else {
+            Ordering::Equal
+        };
+        match speed.then(a.survived_moves.cmp(&b.survived_moves)) {
+            Ordering::Greater => (Some(0), true),
+            Ordering::Less => (Some(1), true),
+            Ordering::Equal => (None, true),
+        }
+    }
+
+    // Starea meciului din perspectiva jucatorului de pe locul `seat`.
+    pub fn state(&self, seat: usize) -> MatchState {
+        let score = self.score();
+        let finished = self.is_over();
+        let (winner, tiebreak) = if finished {
+            self.winner()
+        } else {
+            (None, false)
+        };
+        MatchState {
+            game: self.results.len() as u8,
+            games: self.games,
+            score: [score[seat], score[1 - seat]],
+            finished,
+            winner: match winner {
+                Some(winner) if winner == seat => 1,
+                Some(_) => 2,
+                None => 0,
+            },
+            tiebreak,
+        }
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), AppError> {
+        if path.is_empty() {
+            return Ok(());
+        }
+        let (winner, tiebreak) = self.winner();
+        let games = self
+            .results
+            .iter()
+            .map(|result| GameRecord {
+                mouse: &self.names[result.mouse],
+                walls: &self.names[1 - result.mouse],
+                winner: &self.names[result.winner],
+                mouse_moves: result.mouse_moves,
+                ending: result.ending,
+            })
+            .collect();
+        let record = Records {
+            matches: [MatchRecord {
+                room: self.room,
+                started: self.started,
+                finished: unix_time(),
+                players: &self.names,
+                games: self.games,
+                score: self.score(),
+                winner: winner.map_or("egal", |winner| &self.names[winner]),
+                tiebreak,
+                game: games,
+            }],
+        };
+        let text = toml::to_string(&record).map_err(|e| AppError::WriteError(e.to_string()))?;
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .and_then(|mut file| writeln!(file, "{}", text))
+            .map_err(|e| AppError::WriteError(format!("{}: {}", path, e)))
+    }
+}
+
+#[derive(Serialize)]
+struct Records<'a> {
+    matches: [MatchRecord<'a>; 1],
+}
+
+#[derive(Serialize)]
+struct MatchRecord<'a> {
+    room: i8,
+    started: u64,
+    finished: u64,
+    players: &'a [String; 2],
+    games: u8,
+    score: [u8; 2],
+    winner: &'a str,
+    tiebreak: bool,
+    game: Vec<GameRecord<'a>>,
+}
+
+#[derive(Serialize)]
+struct GameRecord<'a> {
+    mouse: &'a str,
+    walls: &'a str,
+    winner: &'a str,
+    mouse_moves: u32,
+    ending: Ending,
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game(mouse: usize, winner: usize, mouse_moves: u32) -> GameResult {
+        GameResult {
+            winner,
+            mouse,
+            mouse_moves,
+            ending: if winner == mouse {
+                Ending::Escape
+            } else {
+                Ending::Trapped
+            },
+        }
+    }
+
+    fn match_of(games: u8, results: Vec<GameResult>) -> Match {
+        let mut played = Match::new(0, games, [String::from("ana"), String::from("dan")]);
+        for result in results {
+            played.record(result);
+        }
+        played
+    }
+
+    #[test]
+    fn tie_goes_to_the_faster_average_escape() {
+        // 0 evadeaza de doua ori in 20 de mutari (10 in medie), 1 o data in 11.
+        let played = match_of(
+            4,
+            vec![game(0, 0, 9), game(1, 1, 11), game(0, 0, 11), game(0, 1, 7)],
+        );
+        assert_eq!(played.score(), [2, 2]);
+        assert!(played.is_over());
+        assert_eq!(played.winner(), (Some(0), true));
+    }
+
+    #[test]
+    fn tie_without_escapes_goes_to_the_longer_survival() {
+        let played = match_of(2, vec![game(0, 1, 15), game(1, 0, 9)]);
+        assert_eq!(played.winner(), (Some(0), true));
+
+        let played = match_of(2, vec![game(0, 1, 12), game(1, 0, 12)]);
+        assert_eq!(played.winner(), (None, true));
+    }
+
+    #[test]
+    fn match_ends_early_once_the_score_cannot_be_levelled() {
+        let mut played = match_of(5, vec![game(0, 0, 8), game(1, 0, 10)]);
+        assert!(!played.is_over());
+        played.record(game(0, 0, 6));
+        assert!(played.is_over());
+        assert_eq!(played.winner(), (Some(0), false));
+
+        let played = match_of(3, vec![game(0, 1, 8)]);
+        assert!(!played.is_over());
+    }
+
+    #[test]
+    fn forfeit_overrides_the_score() {
+        let mut played = match_of(5, vec![game(0, 0, 8), game(1, 0, 10)]);
+        played.record(GameResult {
+            winner: 1,
+            mouse: 0,
+            mouse_moves: 3,
+            ending: Ending::Forfeit,
+        });
+        assert_eq!(played.score(), [2, 1]);
+        assert!(played.is_over());
+        assert_eq!(played.winner(), (Some(1), false));
+    }
+}
@@ -0,0 +1,828 @@
+use std::{
+    io::Write,
+    net::{Shutdown, TcpStream},
+    sync::{Arc, Mutex, RwLock},
+    thread,
+    time::{Duration, Instant},
+};
+
+use common::{
+    protocol::{Message, RoomOptions},
+    tournament::{
+        format_points, Format, Outcome, Pairing, Standing, Status, TournamentRequest,
+        TournamentView, BYE, MAX_PLAYERS, NO_ROOM,
+    },
+    AppError,
+};
+
+use crate::{
+    classic_only, config::Heartbeat, new_token, serve_idle_client, ConnectionGuard, RoomsManager,
+};
+
+const MAX_TOURNAMENTS: usize = 16;
+const TICK: Duration = Duration::from_millis(500);
+const NO_SHOW: Duration = Duration::from_secs(120);
+// Limita pentru cautarea perechilor in sistemul elvetian.
+const PAIRING_STEPS: usize = 100_000;
+
+struct Entrant {
+    name: String,
+    // Cheia primita la inscriere; fara ea nimeni nu poate relua locul jucatorului.
+    token: String,
+    stream: Option<TcpStream>,
+    points: u8,
+    played: u8,
+    opponents: Vec<usize>,
+    mouse_games: u8,
+    had_bye: bool,
+    assigned: Option<(i8, String)>,
+}
+
+struct Game {
+    first: usize,
+    second: Option<usize>,
+    room: i8,
+    tickets: [String; 2],
+    outcome: Outcome,
+    started: Instant,
+}
+
+pub struct Tournament {
+    id: u8,
+    format: Format,
+    rounds: u8,
+    options: RoomOptions,
+    // Cheia celui care a creat turneul; doar el il poate porni.
+    organizer: String,
+    status: Status,
+    round: u8,
+    start_requested: bool,
+    entrants: Vec<Entrant>,
+    games: Vec<Game>,
+    schedule: Vec<Vec<(usize, Option<usize>)>>,
+}
+
+// Legatura dintre o camera si partida din turneu pe care o gazduieste.
+pub struct Entry {
+    tournament: Arc<Mutex<Tournament>>,
+    round: u8,
+    game: usize,
+}
+
+impl Entry {
+    // Locul 0 este primul jucator din pereche (soarecele in primul joc).
+    pub fn report(&self, winner: Option<usize>, abandoned: bool) {
+        let outcome = match (winner, abandoned) {
+            (_, true) => Outcome::BothLost,
+            (Some(0), _) => Outcome::FirstWon,
+            (Some(_), _) => Outcome::SecondWon,
+            (None, _) => Outcome::Draw,
+        };
+        let mut tournament = self.tournament.lock().unwrap();
+        if tournament.round == self.round {
+            tournament.decide(self.game, outcome);
+            tournament.broadcast();
+        }
+    }
+}
+
+pub struct Tournaments {
+    list: Vec<Arc<Mutex<Tournament>>>,
+    next_id: u8,
+}
+
+impl Tournaments {
+    pub fn new() -> Self {
+        Tournaments {
+            list: Vec::new(),
+            next_id: 0,
+        }
+    }
+
+    fn find(&self, id: u8) -> Option<Arc<Mutex<Tournament>>> {
+        self.list
+            .iter()
+            .find(|tournament| tournament.lock().unwrap().id == id)
+            .cloned()
+    }
+
+    fn remove(&mut self, id: u8) {
+        self.list
+            .retain(|tournament| tournament.lock().unwrap().id != id);
+    }
+}
+
+impl Tournament {
+    fn new(id: u8, request: &TournamentRequest) -> Self {
+        Tournament {
+            id,
+            format: request.format,
+            rounds: request.rounds,
+            options: request.options.clone(),
+            organizer: String::new(),
+            status: Status::Registering,
+            round: 0,
+            start_requested: false,
+            entrants: Vec::new(),
+            games: Vec::new(),
+            schedule: Vec::new(),
+        }
+    }
+
+    fn index_of(&self, name: &str) -> Option<usize> {
+        self.entrants
+            .iter()
+            .position(|entrant| entrant.name == name)
+    }
+
+    fn connected(&self) -> usize {
+        self.entrants
+            .iter()
+            .filter(|entrant| entrant.stream.is_some())
+            .count()
+    }
+
+    // Inscrie un jucator sau il reataseaza daca revine cu acelasi nume si cu cheia lui.
+    // Intoarce cheia jucatorului; la inscriere ea ii este trimisa si lui.
+    fn register(&mut self, name: &str, token: &str, stream: TcpStream) -> Result<String, AppError> {
+        if let Some(index) = self.index_of(name) {
+            let entrant = &mut self.entrants[index];
+            if entrant.stream.is_some() || entrant.token != token {
+                return Err(AppError::ServerError(format!(
+                    "numele {} este deja folosit in turneu",
+                    name
+                )));
+            }
+            entrant.stream = Some(stream);
+            let token = entrant.token.clone();
+            if let Some((room, ticket)) = entrant.assigned.clone() {
+                self.send(index, &Message::Assigned(room, ticket));
+            }
+            return Ok(token);
+        }
+        if self.status != Status::Registering {
+            return Err(AppError::ServerError(String::from(
+                "turneul a inceput deja",
+            )));
+        }
+        if self.entrants.len() >= MAX_PLAYERS {
+            return Err(AppError::ServerError(String::from("turneul este plin")));
+        }
+        let token = new_token();
+        self.entrants.push(Entrant {
+            name: name.to_string(),
+            token: token.clone(),
+            stream: Some(stream),
+            points: 0,
+            played: 0,
+            opponents: Vec::new(),
+            mouse_games: 0,
+            had_bye: false,
+            assigned: None,
+        });
+        self.send(self.entrants.len() - 1, &Message::Token(token.clone()));
+        Ok(token)
+    }
+
+    fn send(&mut self, index: usize, message: &Message) {
+        let entrant = &mut self.entrants[index];
+        let failed = entrant
+            .stream
+            .as_ref()
+            .is_some_and(|stream| (&*stream).write_all(&message.to_bytes()).is_err());
+        if failed {
+            entrant.stream = None;
+        }
+    }
+
+    fn broadcast(&mut self) {
+        for index in 0..self.entrants.len() {
+            let view = Message::Tournament(self.view(index));
+            self.send(index, &view);
+        }
+    }
+
+    fn buchholz(&self, index: usize) -> u16 {
+        self.entrants[index]
+            .opponents
+            .iter()
+            .map(|&opponent| self.entrants[opponent].points as u16)
+            .sum()
+    }
+
+    fn view(&self, you: usize) -> TournamentView {
+        TournamentView {
+            id: self.id,
+            format: self.format,
+            status: self.status,
+            round: self.round,
+            rounds: self.rounds,
+            you: you as u8,
+            options: self.options.clone(),
+            players: (0..self.entrants.len())
+                .map(|index| Standing {
+                    name: self.entrants[index].name.clone(),
+                    points: self.entrants[index].points,
+                    played: self.entrants[index].played,
+                    buchholz: self.buchholz(index),
+                })
+                .collect(),
+            pairings: self
+                .games
+                .iter()
+                .map(|game| Pairing {
+                    first: game.first as u8,
+                    second: game.second.map_or(BYE, |second| second as u8),
+                    room: game.room,
+                    outcome: game.outcome,
+                })
+                .collect(),
+        }
+    }
+
+    fn decide(&mut self, game: usize, outcome: Outcome) {
+        let Some(game) = self.games.get_mut(game) else {
+            return;
+        };
+        if game.outcome != Outcome::Pending {
+            return;
+        }
+        game.outcome = outcome;
+        let points = outcome.points();
+        let players = [Some(game.first), game.second];
+        for (seat, player) in players.into_iter().enumerate() {
+            if let Some(player) = player {
+                let entrant = &mut self.entrants[player];
+                entrant.points += points[seat];
+                entrant.played += 1;
+                entrant.assigned = None;
+            }
+        }
+    }
+
+    fn start(&mut self, handle: &Arc<Mutex<Tournament>>, rooms_manager: &RwLock<RoomsManager>) {
+        // Cine s-a deconectat inainte de start nu mai participa.
+        self.entrants.retain(|entrant| entrant.stream.is_some());
+        let players = self.entrants.len();
+        let most = if players.is_multiple_of(2) {
+            players - 1
+        } else {
+            players
+        } as u8;
+        self.rounds = match self.format {
+            Format::RoundRobin => most,
+            Format::Swiss if self.rounds == 0 => {
+                (usize::BITS - (players - 1).leading_zeros()).max(1) as u8
+            }
+            Format::Swiss => self.rounds.min(most),
+        };
+        if self.format == Format::RoundRobin {
+            self.schedule = round_robin(players);
+        }
+        self.status = Status::Running;
+        info!(
+            "Turneul {} ({}) incepe cu {} jucatori si {} runde",
+            self.id, self.format, players, self.rounds
+        );
+        self.next_round(handle, rooms_manager);
+    }
+
+    fn next_round(
+        &mut self,
+        handle: &Arc<Mutex<Tournament>>,
+        rooms_manager: &RwLock<RoomsManager>,
+    ) {
+        self.round += 1;
+        let pairs = match self.format {
+            Format::RoundRobin => self.schedule[self.round as usize - 1].clone(),
+            Format::Swiss => self.swiss_pairs(),
+        };
+        self.games.clear();
+        for (first, second) in pairs {
+            // Soarecele in primul joc este cel care a fost mai rar soarece.
+            let (first, second) = match second {
+                Some(second)
+                    if self.entrants[second].mouse_games < self.entrants[first].mouse_games =>
+                {
+                    (second, Some(first))
+                }
+                second => (first, second),
+            };
+            self.games.push(Game {
+                first,
+                second,
+                room: NO_ROOM,
+                tickets: [new_token(), new_token()],
+                outcome: Outcome::Pending,
+                started: Instant::now(),
+            });
+            match second {
+                Some(second) => {
+                    self.entrants[first].opponents.push(second);
+                    self.entrants[second].opponents.push(first);
+                    self.entrants[first].mouse_games += 1;
+                }
+                None => {
+                    self.entrants[first].had_bye = true;
+                    let bye = self.games.len() - 1;
+                    self.decide(bye, Outcome::FirstWon);
+                }
+            }
+        }
+        info!("Turneul {}: incepe runda {}", self.id, self.round);
+        self.open_rooms(handle, rooms_manager);
+        self.broadcast();
+    }
+
+    // Creeaza camerele care inca nu au putut fi create (de exemplu, serverul era plin).
+    fn open_rooms(
+        &mut self,
+        handle: &Arc<Mutex<Tournament>>,
+        rooms_manager: &RwLock<RoomsManager>,
+    ) {
+        for index in 0..self.games.len() {
+            let game = &self.games[index];
+            let Some(second) = game.second else {
+                continue;
+            };
+            if game.room != NO_ROOM || game.outcome != Outcome::Pending {
+                continue;
+            }
+            let first = game.first;
+            let tickets = game.tickets.clone();
+            let names = [
+                self.entrants[first].name.clone(),
+                self.entrants[second].name.clone(),
+            ];
+            let entry = Entry {
+                tournament: Arc::clone(handle),
+                round: self.round,
+                game: index,
+            };
+            let room = rooms_manager.write().unwrap().reserve_room(
+                self.options.clone(),
+                names,
+                tickets.clone(),
+                entry,
+            );
+            let Some(room) = room else {
+                continue;
+            };
+            info!(
+                "Turneul {}: {} si {} joaca in camera {}",
+                self.id, self.entrants[first].name, self.entrants[second].name, room
+            );
+            self.games[index].room = room;
+            self.games[index].started = Instant::now();
+            for (player, ticket) in [(first, &tickets[0]), (second, &tickets[1])] {
+                self.entrants[player].assigned = Some((room, ticket.clone()));
+                self.send(player, &Message::Assigned(room, ticket.clone()));
+            }
+        }
+    }
+
+    // Cine nu intra in camera in NO_SHOW pierde partida.
+    fn check_no_shows(&mut self, rooms_manager: &RwLock<RoomsManager>) -> bool {
+        let mut changed = false;
+        for index in 0..self.games.len() {
+            let game = &self.games[index];
+            if game.room == NO_ROOM
+                || game.outcome != Outcome::Pending
+                || game.started.elapsed() < NO_SHOW
+            {
+                continue;
+            }
+            let room = game.room;
+            let Some(present) = rooms_manager.write().unwrap().cancel_reservation(room) else {
+                continue;
+            };
+            let outcome = match present {
+                [true, false] => Outcome::FirstWon,
+                [false, true] => Outcome::SecondWon,
+                _ => Outcome::BothLost,
+            };
+            info!(
+                "Turneul {}: partida din camera {} nu a inceput la timp",
+                self.id, room
+            );
+            self.decide(index, outcome);
+            changed = true;
+        }
+        changed
+    }
+
+    fn round_over(&self) -> bool {
+        self.games
+            .iter()
+            .all(|game| game.outcome != Outcome::Pending)
+    }
+
+    fn finish(&mut self) {
+        self.status = Status::Finished;
+        info!("Turneul {} s-a terminat. Clasament:", self.id);
+        let view = self.view(0);
+        for (place, index) in view.ranking().into_iter().enumerate() {
+            let player = &view.players[index];
+            info!(
+                "  {}. {} - {} puncte (Buchholz {})",
+                place + 1,
+                player.name,
+                format_points(player.points.into()),
+                format_points(player.buchholz)
+            );
+        }
+        self.broadcast();
+    }
+
+    // Perechi pe grupe de punctaj, fara revansa daca se poate; cel mai slab clasat fara bye sta runda asta.
+    fn swiss_pairs(&self) -> Vec<(usize, Option<usize>)> {
+        let mut order: Vec<usize> = (0..self.entrants.len()).collect();
+        order.sort_by_key(|&index| {
+            (
+                std::cmp::Reverse(self.entrants[index].points),
+                std::cmp::Reverse(self.buchholz(index)),
+                index,
+            )
+        });
+
+        let mut pairs = Vec::new();
+        if !order.len().is_multiple_of(2) {
+            let position = order
+                .iter()
+                .rposition(|&index| !self.entrants[index].had_bye)
+                .unwrap_or(order.len() - 1);
+            pairs.push((order.remove(position), None));
+        }
+
+        let mut paired = Vec::new();
+        let mut steps = 0;
+        if !self.pair_up(&mut order, &mut paired, &mut steps) {
+            paired = order.chunks(2).map(|pair| (pair[0], pair[1])).collect();
+        }
+        pairs.extend(
+            paired
+                .into_iter()
+                .map(|(first, second)| (first, Some(second))),
+        );
+        pairs
+    }
+
+    fn pair_up(
+        &self,
+        left: &mut Vec<usize>,
+        paired: &mut Vec<(usize, usize)>,
+        steps: &mut usize,
+    ) -> bool {
+        if left.is_empty() {
+            return true;
+        }
+        *steps += 1;
+        if *steps > PAIRING_STEPS {
+            return false;
+        }
+        let first = left.remove(0);
+        for position in 0..left.len() {
+            let second = left[position];
+            if self.entrants[first].opponents.contains(&second) {
+                continue;
+            }
+            left.remove(position);
+            paired.push((first, second));
+            if self.pair_up(left, paired, steps) {
+                return true;
+            }
+            paired.pop();
+            left.insert(position, second);
+        }
+        left.insert(0, first);
+        false
+    }
+}
+
+// Metoda cercului: fiecare joaca o data cu fiecare; la numar impar, perechea cu None e bye.
+fn round_robin(players: usize) -> Vec<Vec<(usize, Option<usize>)>> {
+    let mut circle: Vec<Option<usize>> = (0..players).map(Some).collect();
+    if !players.is_multiple_of(2) {
+        circle.push(None);
+    }
+    let size = circle.len();
+    let mut schedule = Vec::new();
+    for _ in 0..size - 1 {
+        let mut round = Vec::new();
+        for index in 0..size / 2 {
+            match (circle[index], circle[size - 1 - index]) {
+                (Some(first), second) => round.push((first, second)),
+                (None, Some(first)) => round.push((first, None)),
+                (None, None) => {}
+            }
+        }
+        schedule.push(round);
+        circle[1..].rotate_right(1);
+    }
+    schedule
+}
+
+fn run(
+    tournament: Arc<Mutex<Tournament>>,
+    tournaments: Arc<Mutex<Tournaments>>,
+    rooms_manager: Arc<RwLock<RoomsManager>>,
+) {
+    loop {
+        thread::sleep(TICK);
+        let mut current = tournament.lock().unwrap();
+        match current.status {
+            Status::Registering if current.start_requested => {
+                current.start_requested = false;
+                if current.connected() >= 2 {
+                    current.start(&tournament, &rooms_manager);
+                }
+            }
+            Status::Running => {
+                current.open_rooms(&tournament, &rooms_manager);
+                if current.check_no_shows(&rooms_manager) {
+                    current.broadcast();
+                }
+                if current.round_over() {
+                    if current.round >= current.rounds {
+                        current.finish();
+                    } else {
+                        current.next_round(&tournament, &rooms_manager);
+                    }
+                }
+            }
+            _ => {}
+        }
+        if current.status != Status::Running && current.connected() == 0 {
+            info!("Turneul {} a fost inchis", current.id);
+            let id = current.id;
+            drop(current);
+            tournaments.lock().unwrap().remove(id);
+            return;
+        }
+    }
+}
+
+pub fn join_tournament(
+    mut stream: TcpStream,
+    text: &str,
+    tournaments: &Arc<Mutex<Tournaments>>,
+    rooms_manager: &Arc<RwLock<RoomsManager>>,
+    heartbeat: Heartbeat,
+    _guard: ConnectionGuard,
+) {
+    let parsed = TournamentRequest::parse(text)
+        .and_then(|request| classic_only(&request.options, "turneul").map(|_| request));
+    let mut request = match parsed {
+        Ok(request) => request,
+        Err(e) => {
+            warn!("Cerere de turneu respinsa: {}", e);
+            let _ = stream.write_all(b"-4");
+            return;
+        }
+    };
+
+    let tournament = {
+        let mut tournaments_guard = tournaments.lock().unwrap();
+        match request.id {
+            Some(id) => tournaments_guard.find(id),
+            None if tournaments_guard.list.len() >= MAX_TOURNAMENTS => None,
+            None => {
+                if request.name.is_empty() {
+                    request.name = String::from("Organizator");
+                }
+                let id = tournaments_guard.next_id;
+                tournaments_guard.next_id = tournaments_guard.next_id.wrapping_add(1);
+                let tournament = Arc::new(Mutex::new(Tournament::new(id, &request)));
+                tournaments_guard.list.push(Arc::clone(&tournament));
+                info!(
+                    "Turneu nou {} ({}), organizat de {}",
+                    id, request.format, request.name
+                );
+
+                let (tournament_run, tournaments, rooms_manager) = (
+                    Arc::clone(&tournament),
+                    Arc::clone(tournaments),
+                    Arc::clone(rooms_manager),
+                );
+                thread::spawn(move || run(tournament_run, tournaments, rooms_manager));
+                Some(tournament)
+            }
+        }
+    };
+    let Some(tournament) = tournament else {
+        info!("Turneul cerut nu exista sau sunt prea multe turnee");
+        let _ = stream.write_all(b"-6");
+        return;
+    };
+
+    let writer = match stream.try_clone() {
+        Ok(writer) => writer,
+        Err(e) => {
+            AppError::CloneError(e.to_string()).log();
+            return;
+        }
+    };
+    let _ = writer.set_write_timeout(Some(Duration::from_secs(1)));
+    let (name, token) = {
+        let mut current = tournament.lock().unwrap();
+        let name = match request.name.as_str() {
+            "" => format!("Jucator {}", current.entrants.len() + 1),
+            name => name.to_string(),
+        };
+        let token = match current.register(&name, &request.token, writer) {
+            Ok(token) => token,
+            Err(e) => {
+                info!("Inscriere respinsa: {}", e);
+                let _ = stream.write_all(b"-6");
+                return;
+            }
+        };
+        if request.id.is_none() {
+            current.organizer = token.clone();
+        }
+        current.broadcast();
+        (name, token)
+    };
+    info!("{} s-a inscris in turneu", name);
+
+    let reason = serve_idle_client(&stream, heartbeat, |message| match message {
+        Message::StartTournament => {
+            let mut current = tournament.lock().unwrap();
+            if current.organizer == token {
+                current.start_requested = true;
+            }
+        }
+        Message::Ping(seq) => {
+            let _ = (&stream).write_all(&Message::Pong(seq).to_bytes());
+        }
+        message => debug!("Mesaj ignorat in turneu: {:?}", message),
+    });
+    debug!("{} a iesit din turneu: {}", name, reason);
+
+    let mut current = tournament.lock().unwrap();
+    if let Some(index) = current.index_of(&name) {
+        current.entrants[index].stream = None;
+    }
+    let _ = stream.shutdown(Shutdown::Both);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::tournament::TournamentRequest;
+
+    fn tournament(players: usize) -> Tournament {
+        let mut tournament = Tournament::new(0, &TournamentRequest::default());
+        for index in 0..players {
+            tournament.entrants.push(Entrant {
+                name: format!("Jucator {}", index + 1),
+                token: String::new(),
+                stream: None,
+                points: 0,
+                played: 0,
+                opponents: Vec::new(),
+                mouse_games: 0,
+                had_bye: false,
+                assigned: None,
+            });
+        }
+        tournament
+    }
+
+    // Joaca o runda ca next_round, fara camere: primul din fiecare pereche castiga.
+    fn play(tournament: &mut Tournament, pairs: &[(usize, Option<usize>)]) {
+        for &(first, second) in pairs {
+            match second {
+                Some(second) => {
+                    tournament.entrants[first].opponents.push(second);
+                    tournament.entrants[second].opponents.push(first);
+                    tournament.entrants[first].points += 2;
+                }
+                None => {
+                    tournament.entrants[first].had_bye = true;
+                    tournament.entrants[first].points += 2;
+                }
+            }
+        }
+    }
+
+    fn players_in(pairs: &[(usize, Option<usize>)]) -> Vec<usize> {
+        let mut players: Vec<usize> = pairs
+            .iter()
+            .flat_map(|&(first, second)| std::iter::once(first).chain(second))
+            .collect();
+        players.sort();
+        players
+    }
+
+    #[test]
+    fn round_robin_pairs_everyone_exactly_once() {
+        for players in 2..=7 {
+            let schedule = round_robin(players);
+            let rounds = if players.is_multiple_of(2) {
+                players - 1
+            } else {
+                players
+            };
+            assert_eq!(schedule.len(), rounds, "{} jucatori", players);
+
+            let mut met = vec![vec![0; players]; players];
+            let mut byes = vec![0; players];
+            for round in schedule.iter() {
+                assert_eq!(players_in(round), (0..players).collect::<Vec<_>>());
+                for &(first, second) in round {
+                    match second {
+                        Some(second) => {
+                            met[first][second] += 1;
+                            met[second][first] += 1;
+                        }
+                        None => byes[first] += 1,
+                    }
+                }
+            }
+            for (first, row) in met.iter().enumerate() {
+                for (second, &count) in row.iter().enumerate() {
+                    let expected = if first == second { 0 } else { 1 };
+                    assert_eq!(count, expected, "{} jucatori", players);
+                }
+            }
+            let expected_byes = if players.is_multiple_of(2) { 0 } else { 1 };
+            assert!(byes.iter().all(|&count| count == expected_byes));
+        }
+    }
+
+    #[test]
+    fn swiss_gives_no_second_bye_before_everyone_had_one() {
+        for players in [3, 5, 7] {
+            let mut tournament = tournament(players);
+            let mut byes = vec![0; players];
+            for _ in 0..players {
+                let pairs = tournament.swiss_pairs();
+                assert_eq!(players_in(&pairs), (0..players).collect::<Vec<_>>());
+                let bye: Vec<usize> = pairs
+                    .iter()
+                    .filter(|(_, second)| second.is_none())
+                    .map(|&(first, _)| first)
+                    .collect();
+                assert_eq!(bye.len(), 1);
+                byes[bye[0]] += 1;
+                play(&mut tournament, &pairs);
+            }
+            assert!(byes.iter().all(|&count| count == 1), "{:?}", byes);
+        }
+    }
+
+    #[test]
+    fn swiss_gives_the_bye_to_the_lowest_ranked_without_one() {
+        let mut tournament = tournament(3);
+        tournament.entrants[0].points = 4;
+        tournament.entrants[1].points = 2;
+        tournament.entrants[2].had_bye = true;
+        assert!(tournament.swiss_pairs().contains(&(1, None)));
+    }
+
+    #[test]
+    fn swiss_avoids_a_rematch_when_another_pairing_exists() {
+        // Dupa prima runda, ordinea din clasament ar reface exact perechile vechi.
+        let mut tournament = tournament(4);
+        play(&mut tournament, &[(0, Some(1)), (2, Some(3))]);
+        tournament.entrants[1].points = 2;
+        tournament.entrants[3].points = 0;
+        let pairs = tournament.swiss_pairs();
+        assert_eq!(players_in(&pairs), vec![0, 1, 2, 3]);
+        for &(first, second) in pairs.iter() {
+            let second = second.unwrap();
+            assert!(!tournament.entrants[first].opponents.contains(&second));
+        }
+
+        // Patru jucatori au trei runde fara revansa.
+        let mut fresh = self::tournament(4);
+        for _ in 0..3 {
+            let pairs = fresh.swiss_pairs();
+            for &(first, second) in pairs.iter() {
+                let second = second.unwrap();
+                assert!(!fresh.entrants[first].opponents.contains(&second));
+            }
+            play(&mut fresh, &pairs);
+        }
+    }
+
+    #[test]
+    fn swiss_falls_back_to_a_rematch_when_nothing_else_is_left() {
+        let mut tournament = tournament(2);
+        play(&mut tournament, &[(0, Some(1))]);
+        assert_eq!(tournament.swiss_pairs(), vec![(0, Some(1))]);
+    }
+
+    #[test]
+    fn buchholz_sums_the_opponents_points() {
+        let mut tournament = tournament(4);
+        play(&mut tournament, &[(0, Some(1)), (2, Some(3))]);
+        play(&mut tournament, &[(0, Some(2)), (1, Some(3))]);
+        // Puncte: 0 are 4, 1 are 2, 2 are 2, 3 are 0.
+        assert_eq!(tournament.buchholz(0), 2 + 2);
+        assert_eq!(tournament.buchholz(3), 2 + 2);
+        assert_eq!(tournament.buchholz(1), 4);
+        assert_eq!(tournament.buchholz(2), 4);
+    }
+}
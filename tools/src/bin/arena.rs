@@ -0,0 +1,350 @@
+use common::{
+    book::{Booked, OpeningBook},
+    engine::{board_to_text, cell_to_text, role_name, strategy_from_spec, Budget, Engine, Local},
+    external::External,
+    rules::{
+        apply_move, check_any_left_move, is_border, legal_moves, seeded_start, Board, INITIAL_WALLS,
+    },
+    AppError,
+};
+use std::{
+    fs,
+    path::Path,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+const USAGE: &str = "Utilizare: arena <motor1> <motor2> [optiuni]
+
+Motoare: euristic, anticipare, mcts, mcts:<iteratii>, extern:<comanda>
+
+Optiuni:
+  --games <n>         numarul de partide, jumatate cu fiecare rol (implicit 100)
+  --seed <n>          samanta pozitiilor de start (implicit 1)
+  --time <ms>         limita de timp pe mutare (implicit doar iteratiile)
+  --threads <n>       pe cate fire cauta mcts (implicit 1)
+  --jobs <n>          cate partide se joaca in paralel (implicit 1)
+  --walls <n>         zidurile de la inceput (implicit 10)
+  --records <fisier>  scrie partidele, cate una pe linie
+  --book <fisier>     motoarele din proces joaca din cartea de deschideri";
+
+const DEFAULT_ITERATIONS: u32 = 2000;
+// z pentru un interval de incredere de 95%.
+const Z: f64 = 1.96;
+
+struct Options {
+    specs: [String; 2],
+    games: usize,
+    seed: u64,
+    time: Option<Duration>,
+    threads: usize,
+    jobs: usize,
+    walls: usize,
+    records: Option<String>,
+    book: Option<Arc<OpeningBook>>,
+}
+
+// O partida jucata: cine a fost soarecele, cine a castigat si cat a durat fiecare mutare.
+struct Game {
+    seed: u64,
+    mouse_engine: usize,
+    winner: u8,
+    board: Board,
+    moves: Vec<(usize, usize)>,
+    think: [(Duration, usize); 2],
+    error: Option<String>,
+}
+
+#[derive(Default)]
+struct Stats {
+    wins: [usize; 2],
+    games: [usize; 2],
+    think: Duration,
+    moves: usize,
+}
+
+fn parse_args(args: &[String]) -> Result<Options, AppError> {
+    let usage = || AppError::ConfigError(String::from(USAGE));
+    let (specs, flags) = match args {
+        [first, second, flags @ ..] if !first.starts_with("--") && !second.starts_with("--") => {
+            ([first.clone(), second.clone()], flags)
+        }
+        _ => return Err(usage()),
+    };
+    let mut options = Options {
+        specs,
+        games: 100,
+        seed: 1,
+        time: None,
+        threads: 1,
+        jobs: 1,
+        walls: INITIAL_WALLS,
+        records: None,
+        book: None,
+    };
+    let mut flags = flags.iter();
+    while let Some(flag) = flags.next() {
+        let value = flags
+            .next()
+            .ok_or_else(|| AppError::ConfigError(format!("{} are nevoie de o valoare", flag)))?;
+        let number = || {
+            value
+                .parse::<u64>()
+                .map_err(|_| AppError::ConfigError(format!("{} asteapta un numar", flag)))
+        };
+        match flag.as_str() {
+            "--games" => options.games = number()?.max(1) as usize,
+            "--seed" => options.seed = number()?,
+            "--time" => options.time = Some(Duration::from_millis(number()?)),
+            "--threads" => options.threads = number()?.max(1) as usize,
+            "--jobs" => options.jobs = number()?.max(1) as usize,
+            "--walls" => options.walls = number()?.min(40) as usize,
+            "--records" => options.records = Some(value.clone()),
+            "--book" => options.book = Some(Arc::new(OpeningBook::load(Path::new(value))?)),
+            _ => return Err(usage()),
+        }
+    }
+    Ok(options)
+}
+
+fn spawn_engine(
+    options: &Options,
+    spec: &str,
+    threads: usize,
+) -> Result<Box<dyn Engine>, AppError> {
+    if let Some(command) = spec.strip_prefix("extern:") {
+        return Ok(Box::new(External::spawn(command)?));
+    }
+    let strategy = strategy_from_spec(spec, threads)?;
+    Ok(Box::new(Local::new(match options.book.as_ref() {
+        Some(book) => Box::new(Booked::new(book.clone(), strategy)),
+        None => strategy,
+    })))
+}
+
+fn iterations(spec: &str) -> Result<u32, AppError> {
+    match spec.split_once(':') {
+        Some(("extern", _)) | None => Ok(DEFAULT_ITERATIONS),
+        Some((_, iterations)) => iterations
+            .parse()
+            .map_err(|_| AppError::ConfigError(format!("numar de iteratii invalid in '{}'", spec))),
+    }
+}
+
+fn budget(options: &Options, spec: &str) -> Budget {
+    Budget {
+        iterations: Some(iterations(spec).unwrap_or(DEFAULT_ITERATIONS)),
+        time: options.time,
+    }
+}
+
+// Partidele vin in perechi cu aceeasi pozitie de start si rolurile inversate.
+fn play(options: &Options, index: usize) -> Result<Game, AppError> {
+    let seed = options.seed.wrapping_add((index / 2) as u64);
+    let (mut board, mut mouse) = seeded_start(seed, options.walls);
+
+    let mouse_engine = index % 2;
+    let specs = [
+        &options.specs[mouse_engine],
+        &options.specs[1 - mouse_engine],
+    ];
+    let mut engines = [
+        spawn_engine(options, specs[0], options.threads)?,
+        spawn_engine(options, specs[1], options.threads)?,
+    ];
+    for (seat, engine) in engines.iter_mut().enumerate() {
+        engine.new_game(&board, &mouse, seat as u8 + 1)?;
+    }
+
+    let mut game = Game {
+        seed,
+        mouse_engine,
+        winner: 0,
+        board,
+        moves: Vec::new(),
+        think: [(Duration::ZERO, 0); 2],
+        error: None,
+    };
+    let mut role = 1;
+    loop {
+        if is_border(mouse.x, mouse.y) {
+            game.winner = 1;
+            break;
+        }
+        if role == 1 && !check_any_left_move(&board, &mouse) {
+            game.winner = 2;
+            break;
+        }
+        let seat = role as usize - 1;
+        let started = Instant::now();
+        let cell = engines[seat].pick_move(budget(options, specs[seat]));
+        game.think[seat].0 += started.elapsed();
+        game.think[seat].1 += 1;
+        // Un motor care greseste sau cade pierde partida.
+        let cell = match cell {
+            Ok(Some(cell)) if legal_moves(&board, &mouse, role).contains(&cell) => cell,
+            Ok(cell) => {
+                game.error = Some(format!("{}: mutare invalida {:?}", specs[seat], cell));
+                game.winner = 3 - role;
+                break;
+            }
+            Err(e) => {
+                game.error = Some(format!("{}: {}", specs[seat], e));
+                game.winner = 3 - role;
+                break;
+            }
+        };
+        apply_move(&mut board, &mut mouse, role, cell);
+        game.moves.push(cell);
+        if let Err(e) = engines[1 - seat].opponent_move(cell) {
+            game.error = Some(format!("{}: {}", specs[1 - seat], e));
+            game.winner = role;
+            break;
+        }
+        role = 3 - role;
+    }
+    Ok(game)
+}
+
+// Intervalul Wilson pentru o proportie; merge bine si cand rata e aproape de 0 sau 1.
+fn wilson(wins: usize, games: usize) -> (f64, f64) {
+    if games == 0 {
+        return (0.0, 0.0);
+    }
+    let n = games as f64;
+    let p = wins as f64 / n;
+    let denominator = 1.0 + Z * Z / n;
+    let center = (p + Z * Z / (2.0 * n)) / denominator;
+    let half = Z * (p * (1.0 - p) / n + Z * Z / (4.0 * n * n)).sqrt() / denominator;
+    (center - half, center + half)
+}
+
+fn percent(wins: usize, games: usize) -> f64 {
+    if games == 0 {
+        0.0
+    } else {
+        wins as f64 * 100.0 / games as f64
+    }
+}
+
+fn report(options: &Options, games: &[Game], elapsed: Duration) {
+    let mut stats = [Stats::default(), Stats::default()];
+    for game in games {
+        for (seat, &(think, moves)) in game.think.iter().enumerate() {
+            let engine = if seat == 0 {
+                game.mouse_engine
+            } else {
+                1 - game.mouse_engine
+            };
+            stats[engine].games[seat] += 1;
+            stats[engine].think += think;
+            stats[engine].moves += moves;
+            if game.winner as usize == seat + 1 {
+                stats[engine].wins[seat] += 1;
+            }
+        }
+    }
+
+    let plies: usize = games.iter().map(|game| game.moves.len()).sum();
+    let mouse_wins = games.iter().filter(|game| game.winner == 1).count();
+    println!(
+        "{} partide in {:.1?}: {:.1} mutari in medie, soarecele castiga {:.1}%",
+        games.len(),
+        elapsed,
+        plies as f64 / games.len() as f64,
+        percent(mouse_wins, games.len())
+    );
+    for (engine, stats) in stats.iter().enumerate() {
+        let wins = stats.wins[0] + stats.wins[1];
+        let played = stats.games[0] + stats.games[1];
+        let (low, high) = wilson(wins, played);
+        let think = if stats.moves == 0 {
+            Duration::ZERO
+        } else {
+            stats.think / stats.moves as u32
+        };
+        println!(
+            "{}: {}/{} ({:.1}%, 95%: {:.1}-{:.1}%), ca soarece {:.1}%, ca ziduri {:.1}%, {:.2?} pe mutare",
+            options.specs[engine],
+            wins,
+            played,
+            percent(wins, played),
+            low * 100.0,
+            high * 100.0,
+            percent(stats.wins[0], stats.games[0]),
+            percent(stats.wins[1], stats.games[1]),
+            think
+        );
+    }
+    let errors = games.iter().filter_map(|game| game.error.as_ref());
+    for error in errors.take(5) {
+        println!("Eroare: {}", error);
+    }
+}
+
+// Cate o partida pe linie: samanta;soarece;ziduri;castigator;tabla;mutari.
+fn save_records(path: &str, options: &Options, games: &[Game]) -> Result<(), AppError> {
+    let mut text = String::new();
+    for game in games {
+        let moves: Vec<String> = game.moves.iter().map(|&cell| cell_to_text(cell)).collect();
+        text.push_str(&format!(
+            "{};{};{};{};{};{}\n",
+            game.seed,
+            options.specs[game.mouse_engine],
+            options.specs[1 - game.mouse_engine],
+            role_name(game.winner),
+            board_to_text(&game.board),
+            moves.join(" ")
+        ));
+    }
+    fs::write(path, text).map_err(AppError::IOError)
+}
+
+fn run(args: &[String]) -> Result<(), AppError> {
+    let options = parse_args(args)?;
+    // Pornim motoarele o data ca sa aflam devreme daca specificatia e gresita.
+    for spec in options.specs.iter() {
+        spawn_engine(&options, spec, 1)?;
+    }
+
+    let started = Instant::now();
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(Vec::with_capacity(options.games));
+    thread::scope(|scope| {
+        for _ in 0..options.jobs {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                if index >= options.games {
+                    break;
+                }
+                let result = play(&options, index);
+                results.lock().unwrap().push((index, result));
+            });
+        }
+    });
+
+    let mut results = results.into_inner().unwrap();
+    results.sort_by_key(|&(index, _)| index);
+    let mut games = Vec::with_capacity(results.len());
+    for (_, result) in results {
+        games.push(result?);
+    }
+    report(&options, &games, started.elapsed());
+    if let Some(path) = options.records.as_ref() {
+        save_records(path, &options, &games)?;
+        println!("Partidele au fost scrise in {}", path);
+    }
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Err(e) = run(&args) {
+        e.log();
+        std::process::exit(2);
+    }
+}
@@ -0,0 +1,213 @@
+use common::{
+    ai::{computer_move, lookahead_mouse_move},
+    engine::{Budget, Strategy},
+    mcts::Mcts,
+    rules::{apply_move, seeded_start, Board, INITIAL_WALLS},
+    transposition::TranspositionTable,
+    AppError, Position,
+};
+use std::time::{Duration, Instant};
+
+const USAGE: &str = "Utilizare: bench [optiuni]
+
+Compara cautarile cu si fara tabela de transpozitii, pe aceleasi pozitii, si cu
+cautarea BFS fara stare pe care o folosea calculatorul pana acum.
+
+Optiuni:
+  --positions <n>   cate pozitii (implicit 30)
+  --seed <n>        samanta primei pozitii (implicit 1)
+  --walls <n>       zidurile de la inceput (implicit 10)
+  --plies <n>       cate mutari euristice jucam din start inainte de masurare (implicit 4)
+  --depth <n>       adancimea soarecelui care anticipeaza (implicit 3)
+  --iterations <n>  iteratiile mcts pe mutare (implicit 5000)";
+
+const TABLE_BITS: u32 = 18;
+
+struct Options {
+    positions: u64,
+    seed: u64,
+    walls: usize,
+    plies: usize,
+    depth: usize,
+    iterations: u32,
+}
+
+fn parse_args(args: &[String]) -> Result<Options, AppError> {
+    let mut options = Options {
+        positions: 30,
+        seed: 1,
+        walls: INITIAL_WALLS,
+        plies: 4,
+        depth: 3,
+        iterations: 5000,
+    };
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        let value = args
+            .next()
+            .and_then(|value| value.parse::<u64>().ok())
+            .ok_or_else(|| AppError::ConfigError(String::from(USAGE)))?;
+        match flag.as_str() {
+            "--positions" => options.positions = value.max(1),
+            "--seed" => options.seed = value,
+            "--walls" => options.walls = value.min(40) as usize,
+            "--plies" => options.plies = value as usize,
+            "--depth" => options.depth = value.clamp(1, 6) as usize,
+            "--iterations" => options.iterations = value.max(1) as u32,
+            _ => return Err(AppError::ConfigError(String::from(USAGE))),
+        }
+    }
+    Ok(options)
+}
+
+// Pozitii de mijloc de joc: din fiecare start jucam cateva mutari euristice.
+fn positions(options: &Options, role: u8) -> Vec<(Board, Position)> {
+    let mut positions = Vec::new();
+    for index in 0..options.positions {
+        let (mut board, mut mouse) = seeded_start(options.seed + index, options.walls);
+        let mut to_move = 1;
+        let mut plies = 0;
+        while plies < options.plies || to_move != role {
+            let Some(cell) = computer_move(&board, &mouse, to_move) else {
+                break;
+            };
+            apply_move(&mut board, &mut mouse, to_move, cell);
+            to_move = 3 - to_move;
+            plies += 1;
+        }
+        if to_move == role && computer_move(&board, &mouse, role).is_some() {
+            positions.push((board, mouse));
+        }
+    }
+    positions
+}
+
+fn timed<T>(work: impl FnOnce() -> T) -> (T, Duration) {
+    let started = Instant::now();
+    let result = work();
+    (result, started.elapsed())
+}
+
+fn report(label: &str, positions: usize, elapsed: Duration) {
+    println!(
+        "  {}: {:.2?} ({:.2?} pe pozitie)",
+        label,
+        elapsed,
+        elapsed / positions.max(1) as u32
+    );
+}
+
+fn speedup(without: Duration, with: Duration) -> f64 {
+    without.as_secs_f64() / with.as_secs_f64().max(1e-9)
+}
+
+// Cautarea BFS fara stare (computer_move), ca reper pentru celelalte.
+fn bench_bfs(positions: &[(Board, Position)], role: u8) -> Duration {
+    let ((), elapsed) = timed(|| {
+        for (board, mouse) in positions.iter() {
+            computer_move(board, mouse, role);
+        }
+    });
+    report("BFS fara stare", positions.len(), elapsed);
+    elapsed
+}
+
+// Cautarile cu stare sunt de obicei mult mai lente decat BFS, asa ca aratam de cate ori.
+fn report_against_bfs(bfs: Duration, with: Duration) {
+    println!("  cu tabela: {:.1}x timpul BFS", speedup(with, bfs));
+}
+
+fn bench_lookahead(options: &Options) {
+    let positions = positions(options, 1);
+    println!(
+        "Soarecele care anticipeaza (adancime {}), {} pozitii:",
+        options.depth,
+        positions.len()
+    );
+    let bfs = bench_bfs(&positions, 1);
+    let (plain, without) = timed(|| {
+        positions
+            .iter()
+            .map(|(board, mouse)| {
+                lookahead_mouse_move(
+                    board,
+                    mouse,
+                    options.depth,
+                    &mut TranspositionTable::disabled(),
+                )
+            })
+            .collect::<Vec<_>>()
+    });
+    report("fara tabela", positions.len(), without);
+
+    let mut table = TranspositionTable::new(TABLE_BITS);
+    let (cached, with) = timed(|| {
+        positions
+            .iter()
+            .map(|(board, mouse)| {
+                table.clear();
+                lookahead_mouse_move(board, mouse, options.depth, &mut table)
+            })
+            .collect::<Vec<_>>()
+    });
+    report("cu tabela", positions.len(), with);
+    let same = plain
+        .iter()
+        .zip(cached.iter())
+        .filter(|(a, b)| a == b)
+        .count();
+    println!(
+        "  {:.1}x mai repede; {} din {} mutari identice",
+        speedup(without, with),
+        same,
+        positions.len()
+    );
+    report_against_bfs(bfs, with);
+}
+
+fn bench_mcts(options: &Options) {
+    let positions = positions(options, 2);
+    println!(
+        "MCTS pentru ziduri ({} iteratii), {} pozitii:",
+        options.iterations,
+        positions.len()
+    );
+    let bfs = bench_bfs(&positions, 2);
+    let mut results = Vec::new();
+    for transpositions in [false, true] {
+        let mut mcts = Mcts::new(Budget::iterations(options.iterations), 1);
+        mcts.transpositions = transpositions;
+        let ((), elapsed) = timed(|| {
+            for (board, mouse) in positions.iter() {
+                mcts.choose(board, mouse, 2);
+            }
+        });
+        report(
+            if transpositions {
+                "cu tabela"
+            } else {
+                "fara tabela"
+            },
+            positions.len(),
+            elapsed,
+        );
+        results.push(elapsed);
+    }
+    println!("  {:.1}x mai repede", speedup(results[0], results[1]));
+    report_against_bfs(bfs, results[1]);
+}
+
+fn run(args: &[String]) -> Result<(), AppError> {
+    let options = parse_args(args)?;
+    bench_lookahead(&options);
+    bench_mcts(&options);
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Err(e) = run(&args) {
+        e.log();
+        std::process::exit(2);
+    }
+}
@@ -0,0 +1,148 @@
+use common::{
+    book::OpeningBook,
+    engine::{cell_to_text, strategy_from_spec},
+    rules::{seeded_start, INITIAL_WALLS},
+    AppError,
+};
+use std::{
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    thread,
+    time::Instant,
+};
+
+const USAGE: &str = "Utilizare: book build <fisier> [optiuni]
+           book info <fisier>
+           book probe <fisier> <samanta> [--walls <n>]
+
+Optiuni pentru build:
+  --engine <motor>  motorul care alege mutarile: euristic, anticipare, mcts, mcts:<iteratii>
+                    (implicit mcts:20000)
+  --seed <n>        samanta primei pozitii de start, ca in arena (implicit 1)
+  --starts <n>      cate pozitii de start consecutive (implicit 100)
+  --plies <n>       cate mutari acopera cartea din fiecare start (implicit 3)
+  --walls <n>       zidurile de la inceput (implicit 10)
+  --jobs <n>        cate fire construiesc cartea (implicit 1)";
+
+struct Options {
+    engine: String,
+    seed: u64,
+    starts: u64,
+    plies: usize,
+    walls: usize,
+    jobs: usize,
+}
+
+fn parse_flags(flags: &[String]) -> Result<Options, AppError> {
+    let mut options = Options {
+        engine: String::from("mcts:20000"),
+        seed: 1,
+        starts: 100,
+        plies: 3,
+        walls: INITIAL_WALLS,
+        jobs: 1,
+    };
+    let mut flags = flags.iter();
+    while let Some(flag) = flags.next() {
+        let value = flags
+            .next()
+            .ok_or_else(|| AppError::ConfigError(format!("{} are nevoie de o valoare", flag)))?;
+        let number = || {
+            value
+                .parse::<u64>()
+                .map_err(|_| AppError::ConfigError(format!("{} asteapta un numar", flag)))
+        };
+        match flag.as_str() {
+            "--engine" => options.engine = value.clone(),
+            "--seed" => options.seed = number()?,
+            "--starts" => options.starts = number()?.max(1),
+            "--plies" => options.plies = number()?.min(8) as usize,
+            "--walls" => options.walls = number()?.min(40) as usize,
+            "--jobs" => options.jobs = number()?.max(1) as usize,
+            _ => return Err(AppError::ConfigError(String::from(USAGE))),
+        }
+    }
+    Ok(options)
+}
+
+// Fiecare fir isi face cartea lui din starturile pe care le ia; le unim la final.
+fn build(path: &str, options: &Options) -> Result<(), AppError> {
+    strategy_from_spec(&options.engine, 1)?;
+    let started = Instant::now();
+    let next = AtomicU64::new(0);
+    let book = Mutex::new(OpeningBook::new());
+    thread::scope(|scope| {
+        for _ in 0..options.jobs {
+            scope.spawn(|| {
+                let mut strategy = strategy_from_spec(&options.engine, 1).unwrap();
+                let mut partial = OpeningBook::new();
+                loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    if index >= options.starts {
+                        break;
+                    }
+                    let seed = options.seed.wrapping_add(index);
+                    let (board, mouse) = seeded_start(seed, options.walls);
+                    partial.extend(&board, &mouse, 1, options.plies, strategy.as_mut());
+                }
+                book.lock().unwrap().merge(partial);
+            });
+        }
+    });
+    let book = book.into_inner().unwrap();
+    book.save(Path::new(path))?;
+    println!(
+        "{} pozitii din {} starturi in {:.1?}, scrise in {}",
+        book.len(),
+        options.starts,
+        started.elapsed(),
+        path
+    );
+    Ok(())
+}
+
+fn run(args: &[String]) -> Result<(), AppError> {
+    match args {
+        [command, path, flags @ ..] if command == "build" => build(path, &parse_flags(flags)?),
+        [command, path] if command == "info" => {
+            let book = OpeningBook::load(Path::new(path))?;
+            let (mouse, walls) = book.positions();
+            println!(
+                "{} pozitii: {} cu soarecele la mutare, {} cu zidurile",
+                book.len(),
+                mouse,
+                walls
+            );
+            Ok(())
+        }
+        [command, path, seed, flags @ ..] if command == "probe" => {
+            let seed = seed
+                .parse()
+                .map_err(|_| AppError::ConfigError(String::from(USAGE)))?;
+            let options = parse_flags(flags)?;
+            let book = OpeningBook::load(Path::new(path))?;
+            let (board, mouse) = seeded_start(seed, options.walls);
+            match book.probe(&board, &mouse, 1) {
+                Some(cell) => println!(
+                    "Soarecele de la {} muta la {}",
+                    cell_to_text((mouse.x, mouse.y)),
+                    cell_to_text(cell)
+                ),
+                None => println!("Pozitia de start {} nu este in carte", seed),
+            }
+            Ok(())
+        }
+        _ => Err(AppError::ConfigError(String::from(USAGE))),
+    }
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Err(e) = run(&args) {
+        e.log();
+        std::process::exit(2);
+    }
+}
@@ -0,0 +1,33 @@
+use common::{
+    engine::{serve, strategy, Budget, Local},
+    AppError,
+};
+use std::io;
+
+const USAGE: &str = "Utilizare: engine [euristic|anticipare|mcts] [fire]
+
+Vorbeste protocolul motoarelor externe pe stdin/stdout; poate fi pornit de server cu
+--engine extern --engine-command \"engine mcts\".";
+
+fn run(args: &[String]) -> Result<(), AppError> {
+    let name = args.first().map_or("euristic", String::as_str);
+    let threads = match args.get(1) {
+        Some(threads) => threads
+            .parse()
+            .map_err(|_| AppError::ConfigError(String::from(USAGE)))?,
+        None => 1,
+    };
+    if args.len() > 2 {
+        return Err(AppError::ConfigError(String::from(USAGE)));
+    }
+    let mut engine = Local::new(strategy(name, Budget::default(), threads)?);
+    serve(&mut engine, io::stdin().lock(), io::stdout().lock())
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Err(e) = run(&args) {
+        e.log();
+        std::process::exit(2);
+    }
+}
@@ -0,0 +1,59 @@
+use common::{
+    tablebase::{Tablebase, MAX_TABLE_SIZE, MIN_TABLE_SIZE},
+    AppError,
+};
+use std::{path::Path, time::Instant};
+
+const USAGE: &str = "Utilizare: tablebase build <latura> <fisier>
+           tablebase info <fisier>";
+
+fn run(args: &[String]) -> Result<(), AppError> {
+    match args {
+        [command, size, path] if command == "build" => {
+            let size = size
+                .parse::<usize>()
+                .ok()
+                .filter(|size| (MIN_TABLE_SIZE..=MAX_TABLE_SIZE).contains(size))
+                .ok_or_else(|| {
+                    AppError::ConfigError(format!(
+                        "latura trebuie sa fie intre {} si {}",
+                        MIN_TABLE_SIZE, MAX_TABLE_SIZE
+                    ))
+                })?;
+            let started = Instant::now();
+            let table = Tablebase::solve(size)?;
+            table.save(Path::new(path))?;
+            println!(
+                "Tabla {}x{} rezolvata in {:.1?}: {} pozitii scrise in {}",
+                size,
+                size,
+                started.elapsed(),
+                table.positions(),
+                path
+            );
+            Ok(())
+        }
+        [command, path] if command == "info" => {
+            let table = Tablebase::load(Path::new(path))?;
+            let wins = table.mouse_wins();
+            println!(
+                "Tabla {}x{}: {} pozitii cu zidurile la mutare, soarecele castiga {} ({:.1}%)",
+                table.size,
+                table.size,
+                table.positions(),
+                wins,
+                wins as f64 * 100.0 / table.positions() as f64
+            );
+            Ok(())
+        }
+        _ => Err(AppError::ConfigError(String::from(USAGE))),
+    }
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Err(e) = run(&args) {
+        e.log();
+        std::process::exit(2);
+    }
+}